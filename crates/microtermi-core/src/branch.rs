@@ -0,0 +1,194 @@
+//! Gestión de ramas locales: crear (desde cualquier commit o rama remota), renombrar, borrar y
+//! fijar o quitar su upstream.
+
+use crate::git::{GitError, GitRepo};
+
+fn find_local<'r>(r: &'r git2::Repository, name: &str) -> Result<git2::Branch<'r>, GitError> {
+    r.find_branch(name, git2::BranchType::Local)
+        .map_err(|_| GitError::BranchNotFound(name.to_string()))
+}
+
+fn check_new_name(r: &git2::Repository, name: &str) -> Result<(), GitError> {
+    if !git2::Branch::name_is_valid(name)? {
+        return Err(GitError::InvalidBranchName(name.to_string()));
+    }
+    if r.find_branch(name, git2::BranchType::Local).is_ok() {
+        return Err(GitError::BranchExists(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Crea la rama local `name` en `start_point`: una rama local, una remota (`origin/x`) o cualquier
+/// revisión (id de commit, tag, `HEAD~2`...). Si parte de una rama remota queda siguiéndola como upstream.
+/// No cambia de rama.
+pub fn create_branch(repo: &GitRepo, name: &str, start_point: &str) -> Result<(), GitError> {
+    let r = &repo.0;
+    let name = name.trim();
+    check_new_name(r, name)?;
+    let start_point = start_point.trim();
+    let start_point = if start_point.is_empty() { "HEAD" } else { start_point };
+    let remote = r.find_branch(start_point, git2::BranchType::Remote).ok();
+    let commit = match &remote {
+        Some(remote) => remote.get().peel_to_commit()?,
+        None => r.revparse_single(start_point)?.peel_to_commit()?,
+    };
+    let mut branch = r.branch(name, &commit, false)?;
+    if remote.is_some() {
+        branch.set_upstream(Some(start_point))?;
+    }
+    Ok(())
+}
+
+/// Renombra una rama local; su configuración (upstream) se mueve con ella.
+pub fn rename_branch(repo: &GitRepo, name: &str, new_name: &str) -> Result<(), GitError> {
+    let r = &repo.0;
+    let new_name = new_name.trim();
+    if new_name == name {
+        return Ok(());
+    }
+    let mut branch = find_local(r, name)?;
+    check_new_name(r, new_name)?;
+    branch.rename(new_name, false)?;
+    Ok(())
+}
+
+/// Borra una rama local. Sin `force` se niega si tiene commits que no están ni en HEAD ni en su upstream
+/// (como `git branch -d`); no se puede borrar la rama actual.
+pub fn delete_branch(repo: &GitRepo, name: &str, force: bool) -> Result<(), GitError> {
+    let r = &repo.0;
+    let mut branch = find_local(r, name)?;
+    if branch.is_head() {
+        return Err(GitError::BranchCheckedOut(name.to_string()));
+    }
+    if !force {
+        let tip = branch.get().target().ok_or_else(|| GitError::BranchNotFound(name.to_string()))?;
+        let mut bases: Vec<git2::Oid> = Vec::new();
+        bases.extend(r.head().ok().and_then(|h| h.target()));
+        bases.extend(branch.upstream().ok().and_then(|u| u.get().target()));
+        let merged = bases
+            .iter()
+            .any(|&base| base == tip || r.graph_descendant_of(base, tip).unwrap_or(false));
+        if !merged {
+            return Err(GitError::BranchNotMerged(name.to_string()));
+        }
+    }
+    branch.delete()?;
+    Ok(())
+}
+
+/// Upstream de una rama local (`origin/x`), si tiene.
+pub fn branch_upstream(repo: &GitRepo, name: &str) -> Result<Option<String>, GitError> {
+    let branch = find_local(&repo.0, name)?;
+    let upstream = match branch.upstream() {
+        Ok(u) => u,
+        Err(_) => return Ok(None),
+    };
+    Ok(upstream.name()?.map(String::from))
+}
+
+/// Fija (`Some("origin/x")`) o quita (`None`) el upstream de una rama local.
+pub fn set_upstream(repo: &GitRepo, name: &str, upstream: Option<&str>) -> Result<(), GitError> {
+    let mut branch = find_local(&repo.0, name)?;
+    let upstream = upstream.map(str::trim).filter(|u| !u.is_empty());
+    if let Some(u) = upstream {
+        if repo.0.find_branch(u, git2::BranchType::Remote).is_err() {
+            return Err(GitError::BranchNotFound(u.to_string()));
+        }
+    }
+    branch.set_upstream(upstream)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempRepo;
+
+    fn tip(repo: &TempRepo, name: &str) -> git2::Oid {
+        repo.git().refname_to_id(&format!("refs/heads/{name}")).unwrap()
+    }
+
+    #[test]
+    fn create_branch_from_revisions_and_remote_branches() {
+        let origin = TempRepo::new();
+        let first = origin.commit_file("a.txt", "1\n", "primero");
+        origin.commit_file("a.txt", "2\n", "segundo");
+        let clone = TempRepo::clone_of(&origin);
+
+        create_branch(&clone.repo, " desde-head ", "").unwrap();
+        assert_eq!(tip(&clone, "desde-head"), tip(&clone, "main"));
+        assert_eq!(clone.head_branch(), "main");
+        assert_eq!(branch_upstream(&clone.repo, "desde-head").unwrap(), None);
+
+        create_branch(&clone.repo, "anterior", "HEAD~1").unwrap();
+        assert_eq!(tip(&clone, "anterior"), first);
+
+        create_branch(&clone.repo, "sigue", "origin/main").unwrap();
+        assert_eq!(branch_upstream(&clone.repo, "sigue").unwrap().as_deref(), Some("origin/main"));
+
+        assert!(matches!(create_branch(&clone.repo, "sigue", "HEAD"), Err(GitError::BranchExists(_))));
+        assert!(matches!(create_branch(&clone.repo, "mal..nombre", "HEAD"), Err(GitError::InvalidBranchName(_))));
+        assert!(matches!(create_branch(&clone.repo, "otra", "no-existe"), Err(GitError::Git(_))));
+    }
+
+    #[test]
+    fn rename_branch_keeps_its_upstream() {
+        let origin = TempRepo::new();
+        origin.commit_file("a.txt", "1\n", "primero");
+        let clone = TempRepo::clone_of(&origin);
+        create_branch(&clone.repo, "vieja", "origin/main").unwrap();
+        create_branch(&clone.repo, "otra", "HEAD").unwrap();
+
+        rename_branch(&clone.repo, "vieja", "vieja").unwrap();
+        assert!(matches!(rename_branch(&clone.repo, "vieja", "otra"), Err(GitError::BranchExists(_))));
+        assert!(matches!(rename_branch(&clone.repo, "no-existe", "x"), Err(GitError::BranchNotFound(_))));
+        rename_branch(&clone.repo, "vieja", " nueva ").unwrap();
+        assert!(matches!(branch_upstream(&clone.repo, "vieja"), Err(GitError::BranchNotFound(_))));
+        assert_eq!(branch_upstream(&clone.repo, "nueva").unwrap().as_deref(), Some("origin/main"));
+    }
+
+    #[test]
+    fn delete_branch_refuses_unmerged_or_current_branches() {
+        let repo = TempRepo::new();
+        repo.commit_file("a.txt", "1\n", "primero");
+        repo.branch("fusionada");
+        repo.branch("suelta");
+        repo.checkout("suelta");
+        repo.commit_file("b.txt", "b\n", "solo en suelta");
+        repo.checkout("main");
+
+        assert!(matches!(delete_branch(&repo.repo, "main", true), Err(GitError::BranchCheckedOut(_))));
+        assert!(matches!(delete_branch(&repo.repo, "suelta", false), Err(GitError::BranchNotMerged(_))));
+        delete_branch(&repo.repo, "fusionada", false).unwrap();
+        delete_branch(&repo.repo, "suelta", true).unwrap();
+        assert!(repo.git().find_branch("suelta", git2::BranchType::Local).is_err());
+        assert!(matches!(delete_branch(&repo.repo, "suelta", true), Err(GitError::BranchNotFound(_))));
+    }
+
+    #[test]
+    fn delete_branch_accepts_commits_already_in_the_upstream() {
+        let origin = TempRepo::new();
+        origin.commit_file("a.txt", "1\n", "primero");
+        origin.branch("feature");
+        origin.checkout("feature");
+        origin.commit_file("b.txt", "b\n", "feature");
+        origin.checkout("main");
+        let clone = TempRepo::clone_of(&origin);
+        create_branch(&clone.repo, "feature", "origin/feature").unwrap();
+        delete_branch(&clone.repo, "feature", false).unwrap();
+    }
+
+    #[test]
+    fn set_upstream_checks_the_remote_branch() {
+        let origin = TempRepo::new();
+        origin.commit_file("a.txt", "1\n", "primero");
+        let clone = TempRepo::clone_of(&origin);
+        create_branch(&clone.repo, "local", "HEAD").unwrap();
+
+        set_upstream(&clone.repo, "local", Some(" origin/main ")).unwrap();
+        assert_eq!(branch_upstream(&clone.repo, "local").unwrap().as_deref(), Some("origin/main"));
+        assert!(matches!(set_upstream(&clone.repo, "local", Some("origin/nada")), Err(GitError::BranchNotFound(_))));
+        set_upstream(&clone.repo, "local", Some("")).unwrap();
+        assert_eq!(branch_upstream(&clone.repo, "local").unwrap(), None);
+    }
+}
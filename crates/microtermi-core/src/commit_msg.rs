@@ -0,0 +1,578 @@
+//! Mensajes de commit: plantilla del repo (`commit.template`), cabeceras Conventional Commits
+//! (`tipo(scope)!: descripción`), scopes sugeridos según los proyectos afectados y validación con
+//! reglas al estilo commitlint (las de `@commitlint/config-conventional`, sobrescritas por la
+//! configuración commitlint del repo si la hay).
+
+use crate::discovery::Project;
+use crate::git::{FileChange, GitError, GitRepo};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Tipos de Conventional Commits con su descripción (los de `@commitlint/config-conventional`).
+pub const CONVENTIONAL_TYPES: &[(&str, &str)] = &[
+    ("feat", "Nueva funcionalidad"),
+    ("fix", "Corrección de un error"),
+    ("docs", "Solo documentación"),
+    ("style", "Formato, sin cambios de lógica"),
+    ("refactor", "Reestructura sin cambiar el comportamiento"),
+    ("perf", "Mejora de rendimiento"),
+    ("test", "Tests nuevos o corregidos"),
+    ("build", "Build o dependencias"),
+    ("ci", "Integración continua"),
+    ("chore", "Mantenimiento que no toca el código de la app"),
+    ("revert", "Revierte un commit anterior"),
+];
+
+/// Contenido de la plantilla de mensaje configurada en `commit.template`, o `None` si no hay.
+/// Una ruta relativa se resuelve desde la raíz del repo.
+pub fn commit_template(repo: &GitRepo) -> Result<Option<String>, GitError> {
+    let r = &repo.0;
+    let path = match r.config()?.get_path("commit.template") {
+        Ok(path) => path,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let path = match r.workdir() {
+        Some(workdir) if path.is_relative() => workdir.join(path),
+        _ => path,
+    };
+    std::fs::read_to_string(&path)
+        .map(Some)
+        .map_err(|e| GitError::CommitTemplate(format!("{}: {}", path.display(), e)))
+}
+
+/// Cabecera Conventional Commits: `tipo(scope)!: descripción`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConventionalHeader {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub scope: Option<String>,
+    /// `!`: cambio incompatible.
+    pub breaking: bool,
+    pub subject: String,
+}
+
+impl ConventionalHeader {
+    /// Interpreta la primera línea de un mensaje; `None` si no sigue el formato.
+    pub fn parse(header: &str) -> Option<ConventionalHeader> {
+        let (prefix, subject) = header.split_once(": ")?;
+        let (prefix, breaking) = match prefix.strip_suffix('!') {
+            Some(prefix) => (prefix, true),
+            None => (prefix, false),
+        };
+        let (kind, scope) = match prefix.strip_suffix(')').and_then(|p| p.split_once('(')) {
+            Some((kind, scope)) => (kind, Some(scope.to_string())),
+            None => (prefix, None),
+        };
+        if !kind.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        Some(ConventionalHeader {
+            kind: kind.to_string(),
+            scope,
+            breaking,
+            subject: subject.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ConventionalHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(scope) = self.scope.as_deref().filter(|s| !s.is_empty()) {
+            write!(f, "({})", scope)?;
+        }
+        if self.breaking {
+            write!(f, "!")?;
+        }
+        write!(f, ": {}", self.subject)
+    }
+}
+
+/// Scopes sugeridos para los cambios preparados: el nombre (sin `@org/`) del proyecto más interno
+/// que contiene cada archivo, sin repetir. `workdir` es la raíz del repo al que se refieren las rutas.
+pub fn commit_scopes(workdir: &Path, staged: &[FileChange], projects: &[Project]) -> Vec<String> {
+    let mut scopes: Vec<String> = staged
+        .iter()
+        .filter_map(|change| {
+            let file = workdir.join(&change.path);
+            projects
+                .iter()
+                .filter(|p| file.starts_with(&p.path))
+                .max_by_key(|p| p.path.components().count())
+        })
+        .map(|p| p.name.rsplit('/').next().unwrap_or(&p.name).to_string())
+        .collect();
+    scopes.sort();
+    scopes.dedup();
+    scopes
+}
+
+/// Gravedad de un problema de validación (el nivel 1 o 2 de commitlint).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintLevel {
+    Warning,
+    Error,
+}
+
+/// Regla incumplida por el mensaje.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintProblem {
+    /// Nombre commitlint de la regla (ej. `type-enum`).
+    pub rule: String,
+    pub level: LintLevel,
+    pub message: String,
+}
+
+impl fmt::Display for LintProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]", self.message, self.rule)
+    }
+}
+
+/// Configuración de una regla: `[nivel, "always" | "never", valor]` como en commitlint.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LintRule {
+    /// 0 = desactivada, 1 = aviso, 2 = error.
+    pub level: u8,
+    /// `true` = "always", `false` = "never".
+    pub always: bool,
+    pub value: Value,
+}
+
+impl LintRule {
+    fn from_json(config: &Value) -> Option<LintRule> {
+        let items = config.as_array()?;
+        Some(LintRule {
+            level: items.first()?.as_u64()?.min(2) as u8,
+            always: items.get(1).and_then(Value::as_str) != Some("never"),
+            value: items.get(2).cloned().unwrap_or(Value::Null),
+        })
+    }
+}
+
+/// Reglas de validación de mensajes (las soportadas de commitlint; el resto se ignoran).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommitLintRules {
+    pub rules: BTreeMap<String, LintRule>,
+    /// Archivo del que se leyeron; `None` = las de `@commitlint/config-conventional`.
+    pub source: Option<PathBuf>,
+    /// Aviso si la configuración no se pudo leer entera (reglas en un `commitlint.config.*` de JavaScript).
+    pub warning: Option<String>,
+}
+
+impl Default for CommitLintRules {
+    fn default() -> Self {
+        let types: Vec<&str> = CONVENTIONAL_TYPES.iter().map(|(t, _)| *t).collect();
+        let rules = [
+            ("body-leading-blank", json!([1, "always"])),
+            ("body-max-line-length", json!([2, "always", 100])),
+            ("footer-leading-blank", json!([1, "always"])),
+            ("footer-max-line-length", json!([2, "always", 100])),
+            ("header-max-length", json!([2, "always", 100])),
+            ("subject-case", json!([2, "never", ["sentence-case", "start-case", "pascal-case", "upper-case"]])),
+            ("subject-empty", json!([2, "never"])),
+            ("subject-full-stop", json!([2, "never", "."])),
+            ("type-case", json!([2, "always", "lower-case"])),
+            ("type-empty", json!([2, "never"])),
+            ("type-enum", json!([2, "always", types])),
+        ];
+        CommitLintRules {
+            rules: rules
+                .iter()
+                .filter_map(|(name, config)| Some((name.to_string(), LintRule::from_json(config)?)))
+                .collect(),
+            source: None,
+            warning: None,
+        }
+    }
+}
+
+/// Archivos de configuración commitlint que se buscan en la raíz del repo, por orden.
+const COMMITLINT_FILES: &[&str] = &[
+    ".commitlintrc",
+    ".commitlintrc.json",
+    ".commitlintrc.yaml",
+    ".commitlintrc.yml",
+    "commitlint.config.js",
+    "commitlint.config.cjs",
+    "commitlint.config.mjs",
+    "commitlint.config.ts",
+];
+
+impl CommitLintRules {
+    /// Reglas de la configuración commitlint de `root` (`.commitlintrc*`, `commitlint.config.*` o la
+    /// clave `commitlint` del `package.json`), sobre las de config-conventional. `None` si el repo no
+    /// usa commitlint. Los `commitlint.config.*` (JavaScript) no se ejecutan: si hacen algo más que extender
+    /// config-conventional se usan las de por defecto y `warning` lo indica.
+    pub fn load(root: &Path) -> Result<Option<CommitLintRules>, GitError> {
+        let read = |path: &Path| {
+            std::fs::read_to_string(path).map_err(|e| GitError::CommitLintConfig(format!("{}: {}", path.display(), e)))
+        };
+        let mut found = None;
+        let mut warning = None;
+        for name in COMMITLINT_FILES {
+            let path = root.join(name);
+            if !path.is_file() {
+                continue;
+            }
+            let config = if name.starts_with(".commitlintrc") {
+                serde_yaml::from_str::<Value>(&read(&path)?)
+                    .map_err(|e| GitError::CommitLintConfig(format!("{}: {}", path.display(), e)))?
+            } else {
+                let script = read(&path)?;
+                if script.contains("rules") || !script.contains("config-conventional") {
+                    warning = Some(format!(
+                        "No se pueden leer las reglas de {} (JavaScript); se validan las de @commitlint/config-conventional",
+                        name
+                    ));
+                }
+                Value::Null
+            };
+            found = Some((path, config));
+            break;
+        }
+        if found.is_none() {
+            let manifest = root.join("package.json");
+            if manifest.is_file() {
+                let package: Value = serde_json::from_str(&read(&manifest)?).unwrap_or(Value::Null);
+                if let Some(config) = package.get("commitlint") {
+                    found = Some((manifest, config.clone()));
+                }
+            }
+        }
+        let Some((path, config)) = found else {
+            return Ok(None);
+        };
+        let mut rules = CommitLintRules::default();
+        if let Some(overrides) = config.get("rules").and_then(Value::as_object) {
+            for (name, config) in overrides {
+                if let Some(rule) = LintRule::from_json(config) {
+                    rules.rules.insert(name.clone(), rule);
+                }
+            }
+        }
+        rules.source = Some(path);
+        rules.warning = warning;
+        Ok(Some(rules))
+    }
+
+    /// Tipos permitidos por `type-enum` (los de Conventional Commits si la regla no los limita).
+    pub fn types(&self) -> Vec<String> {
+        match self.rules.get("type-enum") {
+            Some(rule) if rule.level > 0 && rule.always => string_list(&rule.value),
+            _ => CONVENTIONAL_TYPES.iter().map(|(t, _)| t.to_string()).collect(),
+        }
+    }
+
+    /// Scopes permitidos por `scope-enum`; vacío si cualquiera vale.
+    pub fn scopes(&self) -> Vec<String> {
+        match self.rules.get("scope-enum") {
+            Some(rule) if rule.level > 0 && rule.always => string_list(&rule.value),
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn string_list(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(items) => items.iter().filter_map(Value::as_str).map(String::from).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Comprueba si `text` está escrito en el caso de commitlint `case` (`lower-case`, `sentence-case`…).
+fn is_case(text: &str, case: &str) -> bool {
+    let mut chars = text.chars();
+    let first = chars.next();
+    let words = || text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty());
+    let no_separators = text.chars().all(char::is_alphanumeric);
+    match case {
+        "lower-case" | "lowercase" => text == text.to_lowercase(),
+        "upper-case" | "uppercase" => text == text.to_uppercase(),
+        "sentence-case" | "sentencecase" => first.is_some_and(char::is_uppercase),
+        "start-case" => words().all(|w| w.chars().next().is_some_and(|c| !c.is_lowercase())),
+        "pascal-case" => no_separators && first.is_some_and(char::is_uppercase),
+        "camel-case" => no_separators && first.is_some_and(char::is_lowercase),
+        "kebab-case" => text == text.to_lowercase() && !text.contains([' ', '_']),
+        "snake-case" => text == text.to_lowercase() && !text.contains([' ', '-']),
+        _ => true,
+    }
+}
+
+/// Mensaje partido como lo hace commitlint: cabecera, cuerpo y pie (`Token: valor`, `BREAKING CHANGE: …`).
+struct ParsedMessage<'a> {
+    header: &'a str,
+    /// Si hay línea en blanco entre la cabecera y el cuerpo, y entre el cuerpo y el pie.
+    body_blank: bool,
+    body: Vec<&'a str>,
+    footer_blank: bool,
+    footer: Vec<&'a str>,
+}
+
+/// Línea de trailer: `Token: valor` o `Token #valor`, con un token de git (letras y guiones) o
+/// `BREAKING CHANGE`.
+fn is_footer_line(line: &str) -> bool {
+    if line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING CHANGE #") {
+        return true;
+    }
+    let token = line.split_once(": ").or_else(|| line.split_once(" #")).map(|(t, _)| t);
+    token.is_some_and(|t| !t.is_empty() && t.chars().all(|c| c.is_ascii_alphabetic() || c == '-'))
+}
+
+fn trim_blank<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    let start = lines.iter().position(|l| !l.trim().is_empty()).unwrap_or(lines.len());
+    let end = lines.iter().rposition(|l| !l.trim().is_empty()).map_or(start, |i| i + 1);
+    lines[start..end].to_vec()
+}
+
+fn parse_message(message: &str) -> ParsedMessage<'_> {
+    let mut lines = message.lines();
+    let header = lines.next().unwrap_or_default();
+    let rest: Vec<&str> = lines.collect();
+    // Como los trailers de git, el footer es el último párrafo y solo si empieza por un trailer.
+    let last_paragraph = rest.iter().rposition(|l| l.trim().is_empty()).map_or(0, |i| i + 1);
+    let footer_start = match rest.get(last_paragraph) {
+        Some(line) if is_footer_line(line) => last_paragraph,
+        _ => rest.len(),
+    };
+    let (body, footer) = rest.split_at(footer_start);
+    ParsedMessage {
+        header,
+        body_blank: rest.first().is_some_and(|l| l.trim().is_empty()),
+        footer_blank: body.last().is_some_and(|l| l.trim().is_empty()),
+        body: trim_blank(body),
+        footer: trim_blank(footer),
+    }
+}
+
+/// Valida `message` contra `rules` y devuelve los problemas, errores primero. Los comentarios (`#`) se
+/// quitan antes, como hace `git commit`; los merges, reverts y `fixup!`/`squash!` no se validan.
+pub fn lint_commit_message(message: &str, rules: &CommitLintRules) -> Vec<LintProblem> {
+    let message = git2::message_prettify(message, Some(b'#')).unwrap_or_else(|_| message.to_string());
+    let ignored = ["Merge ", "Revert \"", "fixup! ", "squash! ", "amend! "];
+    if ignored.iter().any(|prefix| message.starts_with(prefix)) {
+        return Vec::new();
+    }
+    let parsed = parse_message(&message);
+    let header = ConventionalHeader::parse(parsed.header).unwrap_or_default();
+    let scopes: Vec<&str> = header.scope.as_deref().map(|s| s.split([',', '/']).map(str::trim).collect()).unwrap_or_default();
+    let mut problems = Vec::new();
+    for (name, rule) in &rules.rules {
+        let level = match rule.level {
+            0 => continue,
+            1 => LintLevel::Warning,
+            _ => LintLevel::Error,
+        };
+        let limit = rule.value.as_u64().map(|n| n as usize);
+        let cases = string_list(&rule.value);
+        // `ok` es si el mensaje cumple la condición de la regla en su forma "always"; el mensaje dice lo
+        // que pide la regla según sea "always" o "never".
+        let (must, must_not) = if rule.always { ("debe", "no puede") } else { ("no puede", "debe") };
+        let (ok, what) = match name.as_str() {
+            "type-empty" => (header.kind.is_empty(), format!("el tipo {} estar vacío", must)),
+            "type-enum" if header.kind.is_empty() => continue,
+            "type-enum" => (cases.contains(&header.kind), format!("el tipo {} ser uno de: {}", must, cases.join(", "))),
+            "type-case" if header.kind.is_empty() => continue,
+            "type-case" => (
+                cases.iter().any(|c| is_case(&header.kind, c)),
+                format!("el tipo {} estar en {}", must, cases.join(", ")),
+            ),
+            "scope-empty" => (scopes.is_empty(), format!("el scope {} estar vacío", must)),
+            "scope-enum" if scopes.is_empty() => continue,
+            "scope-enum" => (
+                scopes.iter().all(|s| cases.iter().any(|c| c == s)),
+                format!("el scope {} ser uno de: {}", must, cases.join(", ")),
+            ),
+            "scope-case" if scopes.is_empty() => continue,
+            "scope-case" => (
+                scopes.iter().all(|s| cases.iter().any(|c| is_case(s, c))),
+                format!("el scope {} estar en {}", must, cases.join(", ")),
+            ),
+            "subject-empty" => (header.subject.trim().is_empty(), format!("la descripción {} estar vacía", must)),
+            "subject-case" if header.subject.is_empty() => continue,
+            "subject-case" => (
+                cases.iter().any(|c| is_case(&header.subject, c)),
+                format!("la descripción {} estar en {}", must, cases.join(", ")),
+            ),
+            "subject-full-stop" => {
+                let stop = rule.value.as_str().unwrap_or(".");
+                (header.subject.ends_with(stop), format!("la descripción {} terminar en «{}»", must, stop))
+            }
+            "header-max-length" => {
+                let max = limit.unwrap_or(100);
+                (parsed.header.chars().count() <= max, format!("la cabecera {} pasar de {} caracteres", must_not, max))
+            }
+            "body-leading-blank" if parsed.body.is_empty() => continue,
+            "body-leading-blank" => (parsed.body_blank, format!("el cuerpo {} ir tras una línea en blanco", must)),
+            "body-max-line-length" => {
+                let max = limit.unwrap_or(100);
+                let ok = parsed.body.iter().all(|l| l.chars().count() <= max || !l.contains(' '));
+                (ok, format!("las líneas del cuerpo {} pasar de {} caracteres", must_not, max))
+            }
+            "footer-leading-blank" if parsed.footer.is_empty() => continue,
+            "footer-leading-blank" => (parsed.footer_blank, format!("el pie {} ir tras una línea en blanco", must)),
+            "footer-max-line-length" => {
+                let max = limit.unwrap_or(100);
+                let ok = parsed.footer.iter().all(|l| l.chars().count() <= max || !l.contains(' '));
+                (ok, format!("las líneas del pie {} pasar de {} caracteres", must_not, max))
+            }
+            _ => continue,
+        };
+        if ok != rule.always {
+            problems.push(LintProblem { rule: name.clone(), level, message: what });
+        }
+    }
+    problems.sort_by_key(|p| std::cmp::Reverse(p.level));
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(overrides: Value) -> CommitLintRules {
+        let mut rules = CommitLintRules::default();
+        for (name, config) in overrides.as_object().unwrap() {
+            rules.rules.insert(name.clone(), LintRule::from_json(config).unwrap());
+        }
+        rules
+    }
+
+    fn lint(message: &str, rules: &CommitLintRules) -> Vec<(String, String)> {
+        lint_commit_message(message, rules).into_iter().map(|p| (p.rule, p.message)).collect()
+    }
+
+    #[test]
+    fn header_with_scope_and_breaking_marker() {
+        let header = ConventionalHeader::parse("feat(api)!: drop v1 endpoints").unwrap();
+        assert_eq!(header.kind, "feat");
+        assert_eq!(header.scope.as_deref(), Some("api"));
+        assert!(header.breaking);
+        assert_eq!(header.subject, "drop v1 endpoints");
+        assert_eq!(header.to_string(), "feat(api)!: drop v1 endpoints");
+        let header = ConventionalHeader::parse("fix!: crash on start").unwrap();
+        assert_eq!((header.scope, header.breaking), (None, true));
+        assert_eq!(ConventionalHeader::parse("not conventional"), None);
+        assert_eq!(ConventionalHeader::parse("feat api: spaces in type"), None);
+    }
+
+    #[test]
+    fn body_and_final_trailer_paragraph() {
+        let parsed = parse_message("fix: x\n\nFirst paragraph.\nRefs: not a footer here\n\nSecond.\n\nRefs: #12\nBREAKING CHANGE: y");
+        assert_eq!(parsed.header, "fix: x");
+        assert!(parsed.body_blank && parsed.footer_blank);
+        assert_eq!(parsed.body, vec!["First paragraph.", "Refs: not a footer here", "", "Second."]);
+        assert_eq!(parsed.footer, vec!["Refs: #12", "BREAKING CHANGE: y"]);
+        let parsed = parse_message("fix: x\nno blank line\n\nplain closing paragraph");
+        assert!(!parsed.body_blank);
+        assert_eq!(parsed.body, vec!["no blank line", "", "plain closing paragraph"]);
+        assert!(parsed.footer.is_empty());
+        let parsed = parse_message("fix: x\n\nSigned-off-by: A <a@b.c>");
+        assert!(parsed.body.is_empty());
+        assert_eq!(parsed.footer, vec!["Signed-off-by: A <a@b.c>"]);
+    }
+
+    #[test]
+    fn default_rules_accept_a_conventional_message() {
+        let message = "feat(ui)!: add dark mode\n\nExplains why.\n\nBREAKING CHANGE: themes moved";
+        assert_eq!(lint(message, &CommitLintRules::default()), Vec::new());
+    }
+
+    #[test]
+    fn default_rules_report_type_and_subject_problems() {
+        let problems = lint("Feature: Added stuff.", &CommitLintRules::default());
+        let names: Vec<&str> = problems.iter().map(|(rule, _)| rule.as_str()).collect();
+        assert_eq!(names, vec!["subject-case", "subject-full-stop", "type-case", "type-enum"]);
+        let messages: BTreeMap<&str, &str> = problems.iter().map(|(r, m)| (r.as_str(), m.as_str())).collect();
+        assert_eq!(messages["subject-case"], "la descripción no puede estar en sentence-case, start-case, pascal-case, upper-case");
+        assert_eq!(messages["subject-full-stop"], "la descripción no puede terminar en «.»");
+        assert_eq!(messages["type-case"], "el tipo debe estar en lower-case");
+        assert!(lint("no header format", &CommitLintRules::default()).iter().any(|(r, _)| r == "type-empty"));
+        // Los merges, reverts y fixups no se validan.
+        assert_eq!(lint("Merge branch 'main' into feature", &CommitLintRules::default()), Vec::new());
+        assert_eq!(lint("fixup! Whatever.", &CommitLintRules::default()), Vec::new());
+    }
+
+    #[test]
+    fn case_rules_word_the_message_after_always_or_never() {
+        let always = rules(json!({ "subject-case": [2, "always", ["lower-case"]], "type-case": [2, "never", "upper-case"] }));
+        assert_eq!(
+            lint("FIX: Add thing", &always),
+            vec![
+                ("subject-case".to_string(), "la descripción debe estar en lower-case".to_string()),
+                ("type-case".to_string(), "el tipo no puede estar en upper-case".to_string()),
+                ("type-enum".to_string(), format!("el tipo debe ser uno de: {}", always.types().join(", "))),
+            ]
+        );
+        assert_eq!(lint("fix: add thing", &always), Vec::new());
+        let scope = rules(json!({ "scope-case": [2, "never", "upper-case"] }));
+        assert_eq!(lint("fix(API): add thing", &scope), vec![("scope-case".to_string(), "el scope no puede estar en upper-case".to_string())]);
+        assert_eq!(lint("fix(api): add thing", &scope), Vec::new());
+    }
+
+    #[test]
+    fn scope_enum_and_scope_empty() {
+        let rules = rules(json!({ "scope-enum": [2, "always", ["api", "ui"]], "scope-empty": [1, "never"] }));
+        assert_eq!(rules.scopes(), vec!["api", "ui"]);
+        assert_eq!(lint("fix(api,ui): x", &rules), Vec::new());
+        assert_eq!(lint("fix(api/ui): x", &rules), Vec::new());
+        assert_eq!(lint("fix(db): x", &rules), vec![("scope-enum".to_string(), "el scope debe ser uno de: api, ui".to_string())]);
+        let problems = lint_commit_message("fix: x", &rules);
+        assert_eq!(problems.len(), 1);
+        assert_eq!((problems[0].rule.as_str(), problems[0].level), ("scope-empty", LintLevel::Warning));
+        assert_eq!(problems[0].message, "el scope no puede estar vacío");
+    }
+
+    #[test]
+    fn length_and_blank_line_rules() {
+        let rules = rules(json!({ "header-max-length": [2, "always", 20], "body-max-line-length": [1, "always", 10] }));
+        assert_eq!(lint("fix: exactly twenty!", &rules).len(), 0);
+        assert_eq!(
+            lint("fix: twenty one chars", &rules),
+            vec![("header-max-length".to_string(), "la cabecera no puede pasar de 20 caracteres".to_string())]
+        );
+        // Una línea larga sin espacios (una URL) no cuenta.
+        assert_eq!(lint("fix: x\n\nhttps://example.com/a/long/path", &rules), Vec::new());
+        assert_eq!(lint("fix: x\n\nsome long words", &rules)[0].0, "body-max-line-length");
+        assert_eq!(lint("fix: x\nbody", &rules)[0], ("body-leading-blank".to_string(), "el cuerpo debe ir tras una línea en blanco".to_string()));
+        assert_eq!(lint("fix: x\n\nbody\nRefs: #1", &rules), Vec::new());
+        assert_eq!(lint("fix: x\n\nbody\n\n# comentario\nRefs: #1", &rules), Vec::new());
+    }
+
+    #[test]
+    fn disabled_rules_and_errors_first() {
+        let rules = rules(json!({ "type-enum": [0], "subject-full-stop": [1, "never", "."] }));
+        let problems = lint_commit_message("custom: Done.", &rules);
+        let levels: Vec<(&str, LintLevel)> = problems.iter().map(|p| (p.rule.as_str(), p.level)).collect();
+        assert_eq!(levels, vec![("subject-case", LintLevel::Error), ("subject-full-stop", LintLevel::Warning)]);
+    }
+
+    #[test]
+    fn load_reads_commitlintrc_over_the_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(CommitLintRules::load(dir.path()).unwrap(), None);
+        std::fs::write(dir.path().join(".commitlintrc.yml"), "extends: ['@commitlint/config-conventional']\nrules:\n  subject-case: [2, always, [lower-case]]\n").unwrap();
+        let rules = CommitLintRules::load(dir.path()).unwrap().unwrap();
+        assert_eq!(rules.source.as_deref(), Some(dir.path().join(".commitlintrc.yml").as_path()));
+        assert_eq!(rules.rules["subject-case"], LintRule { level: 2, always: true, value: json!(["lower-case"]) });
+        assert_eq!(rules.rules["type-enum"], CommitLintRules::default().rules["type-enum"]);
+        assert_eq!(rules.warning, None);
+    }
+
+    #[test]
+    fn load_warns_about_javascript_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("commitlint.config.js"), "module.exports = { extends: ['@commitlint/config-conventional'] };\n").unwrap();
+        assert_eq!(CommitLintRules::load(dir.path()).unwrap().unwrap().warning, None);
+        std::fs::write(dir.path().join("commitlint.config.js"), "module.exports = { rules: { 'type-enum': [2, 'always', ['x']] } };\n").unwrap();
+        let rules = CommitLintRules::load(dir.path()).unwrap().unwrap();
+        assert!(rules.warning.unwrap().contains("commitlint.config.js"));
+        assert_eq!(rules.rules, CommitLintRules::default().rules);
+    }
+}
@@ -0,0 +1,313 @@
+//! Token de GitLab en el llavero del sistema (Secret Service/libsecret, Keychain, Credential Manager)
+//! y credenciales para las operaciones remotas de git (fetch, pull, push, clone).
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use thiserror::Error;
+
+const SERVICE: &str = "microtermi";
+const GITLAB_TOKEN_USER: &str = "gitlab_token";
+
+/// Token de la sesión actual (p. ej. guardado en texto plano por opt-in); tiene prioridad sobre el llavero.
+static SESSION_GITLAB_TOKEN: Mutex<Option<String>> = Mutex::new(None);
+
+/// URL base de GitLab configurada: el token solo se ofrece a remotos de ese host.
+static SESSION_GITLAB_URL: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Error)]
+pub enum CredentialError {
+    #[error("Keyring error: {0}")]
+    Keyring(#[from] keyring::Error),
+}
+
+fn gitlab_entry() -> Result<keyring::Entry, CredentialError> {
+    Ok(keyring::Entry::new(SERVICE, GITLAB_TOKEN_USER)?)
+}
+
+/// Lee el token de GitLab del llavero. `None` si no hay ninguno guardado.
+pub fn load_gitlab_token() -> Result<Option<String>, CredentialError> {
+    match gitlab_entry()?.get_password() {
+        Ok(t) => Ok(Some(t)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Guarda el token de GitLab en el llavero. Un token vacío elimina la entrada.
+pub fn store_gitlab_token(token: &str) -> Result<(), CredentialError> {
+    let token = token.trim();
+    if token.is_empty() {
+        return delete_gitlab_token();
+    }
+    gitlab_entry()?.set_password(token)?;
+    Ok(())
+}
+
+/// Elimina el token de GitLab del llavero (no falla si no había ninguno).
+pub fn delete_gitlab_token() -> Result<(), CredentialError> {
+    match gitlab_entry()?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Token de GitLab resuelto a partir de config.json y el llavero.
+#[derive(Debug)]
+pub struct GitlabTokenSource {
+    pub token: Option<String>,
+    /// Si config.json debe seguir guardando el token: opt-in a texto plano, o no se pudo mover al llavero.
+    pub in_config: bool,
+    /// Error del llavero al migrar o leer el token.
+    pub error: Option<CredentialError>,
+}
+
+/// Resuelve el token de GitLab según config.json. Con `plaintext` se usa el de config.json; si no, el del
+/// llavero, y un token heredado en config.json se mueve al llavero (si falla, se queda en config.json).
+pub fn resolve_gitlab_token(config_token: Option<&str>, plaintext: bool) -> GitlabTokenSource {
+    let config_token = config_token.map(str::trim).filter(|t| !t.is_empty()).map(String::from);
+    match config_token {
+        Some(token) if plaintext => GitlabTokenSource { token: Some(token), in_config: true, error: None },
+        None if plaintext => GitlabTokenSource { token: None, in_config: false, error: None },
+        Some(token) => {
+            let error = store_gitlab_token(&token).err();
+            GitlabTokenSource { token: Some(token), in_config: error.is_some(), error }
+        }
+        None => match load_gitlab_token() {
+            Ok(token) => GitlabTokenSource { token, in_config: false, error: None },
+            Err(e) => GitlabTokenSource { token: None, in_config: false, error: Some(e) },
+        },
+    }
+}
+
+/// Guarda el token según la preferencia: en el llavero, o con `plaintext` (el llamador lo escribe en
+/// config.json) borrando la copia que hubiera en el llavero.
+pub fn save_gitlab_token(token: &str, plaintext: bool) -> Result<(), CredentialError> {
+    if plaintext {
+        delete_gitlab_token()
+    } else {
+        store_gitlab_token(token)
+    }
+}
+
+/// Fija el token de GitLab que usan las operaciones remotas de git en esta sesión.
+/// Sin token de sesión se recurre al del llavero.
+pub fn set_session_gitlab_token(token: Option<&str>) {
+    let token = token.map(str::trim).filter(|t| !t.is_empty()).map(String::from);
+    if let Ok(mut guard) = SESSION_GITLAB_TOKEN.lock() {
+        *guard = token;
+    }
+}
+
+/// Fija la URL base de GitLab (la de los ajustes). Sin URL el token no se ofrece a ningún remoto.
+pub fn set_session_gitlab_url(url: Option<&str>) {
+    let url = url.map(str::trim).filter(|u| !u.is_empty()).map(String::from);
+    if let Ok(mut guard) = SESSION_GITLAB_URL.lock() {
+        *guard = url;
+    }
+}
+
+/// Host y puerto de una URL; la URL base de GitLab puede venir sin esquema (se asume https).
+fn url_host(url: &str) -> Option<(String, Option<u16>)> {
+    let parsed = reqwest::Url::parse(url)
+        .ok()
+        .filter(|u| u.has_host())
+        .or_else(|| reqwest::Url::parse(&format!("https://{}", url)).ok())?;
+    Some((parsed.host_str()?.to_ascii_lowercase(), parsed.port_or_known_default()))
+}
+
+/// Si `url` apunta al servidor de GitLab configurado.
+fn is_gitlab_remote(url: &str) -> bool {
+    let base = SESSION_GITLAB_URL.lock().ok().and_then(|g| g.clone());
+    match (base.as_deref().and_then(url_host), url_host(url)) {
+        (Some(base), Some(remote)) => base == remote,
+        _ => false,
+    }
+}
+
+fn gitlab_token() -> Option<String> {
+    let session = SESSION_GITLAB_TOKEN.lock().ok().and_then(|g| g.clone());
+    session.or_else(|| load_gitlab_token().ok().flatten())
+}
+
+/// Claves SSH por defecto en ~/.ssh, en el orden en que las prueba OpenSSH.
+fn ssh_key_files() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    let Some(ssh_dir) = home.map(|h| PathBuf::from(h).join(".ssh")) else {
+        return Vec::new();
+    };
+    ["id_ed25519", "id_ecdsa", "id_rsa"]
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// Callbacks de credenciales para fetch/push/clone. libgit2 vuelve a llamar tras cada intento fallido,
+/// así que cada llamada prueba la siguiente fuente: ssh-agent, claves SSH en ~/.ssh, los credential
+/// helpers de git (según `config`, la del repo) y por último el token de GitLab (usuario `oauth2`), solo si
+/// el remoto es del servidor de GitLab configurado. Nunca se escribe nada en la URL del remoto.
+pub(crate) fn remote_callbacks<'a>(config: git2::Config) -> git2::RemoteCallbacks<'a> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    let key_files = ssh_key_files();
+    let mut ssh_step = 0;
+    let mut helper_tried = false;
+    let mut token_tried = false;
+    callbacks.credentials(move |url, username, allowed| {
+        let user = username.unwrap_or("git");
+        if allowed.contains(git2::CredentialType::USERNAME) {
+            return git2::Cred::username(user);
+        }
+        if allowed.contains(git2::CredentialType::SSH_KEY) {
+            while ssh_step <= key_files.len() {
+                let step = ssh_step;
+                ssh_step += 1;
+                if step == 0 {
+                    return git2::Cred::ssh_key_from_agent(user);
+                }
+                let key = &key_files[step - 1];
+                let public = key.with_extension("pub");
+                let public = public.is_file().then_some(public.as_path());
+                if let Ok(cred) = git2::Cred::ssh_key(user, public, key, None) {
+                    return Ok(cred);
+                }
+            }
+        }
+        if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+            if !helper_tried {
+                helper_tried = true;
+                if let Ok(cred) = git2::Cred::credential_helper(&config, url, username) {
+                    return Ok(cred);
+                }
+            }
+            if !token_tried && is_gitlab_remote(url) {
+                token_tried = true;
+                if let Some(token) = gitlab_token() {
+                    return git2::Cred::userpass_plaintext("oauth2", &token);
+                }
+            }
+        }
+        Err(git2::Error::from_str(&format!("no valid credentials for {}", url)))
+    });
+    callbacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    // El mock de keyring no comparte datos entre `Entry`s (cada `Entry::new` empieza vacío), así que
+    // los tests usan un llavero en memoria compartido, con un interruptor para simular que falla.
+    type Secrets = HashMap<(String, String), Vec<u8>>;
+
+    static STORE: Mutex<Option<Secrets>> = Mutex::new(None);
+    static FAIL: AtomicBool = AtomicBool::new(false);
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    struct MemoryCredential(String, String);
+
+    impl keyring::credential::CredentialApi for MemoryCredential {
+        fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+            if FAIL.load(Ordering::SeqCst) {
+                return Err(keyring::Error::NoStorageAccess("locked".into()));
+            }
+            let mut store = STORE.lock().unwrap();
+            store.get_or_insert_with(HashMap::new).insert((self.0.clone(), self.1.clone()), secret.to_vec());
+            Ok(())
+        }
+
+        fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+            let store = STORE.lock().unwrap();
+            let key = (self.0.clone(), self.1.clone());
+            store.as_ref().and_then(|s| s.get(&key).cloned()).ok_or(keyring::Error::NoEntry)
+        }
+
+        fn delete_credential(&self) -> keyring::Result<()> {
+            let mut store = STORE.lock().unwrap();
+            let key = (self.0.clone(), self.1.clone());
+            store.as_mut().and_then(|s| s.remove(&key)).map(|_| ()).ok_or(keyring::Error::NoEntry)
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    struct MemoryBuilder;
+
+    impl keyring::credential::CredentialBuilderApi for MemoryBuilder {
+        fn build(&self, _: Option<&str>, service: &str, user: &str) -> keyring::Result<Box<keyring::credential::Credential>> {
+            Ok(Box::new(MemoryCredential(service.to_string(), user.to_string())))
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    /// Llavero en memoria vacío; el guard serializa los tests (el llavero y el token de sesión son globales).
+    fn keyring() -> std::sync::MutexGuard<'static, ()> {
+        let guard = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        keyring::set_default_credential_builder(Box::new(MemoryBuilder));
+        *STORE.lock().unwrap() = None;
+        FAIL.store(false, Ordering::SeqCst);
+        guard
+    }
+
+    #[test]
+    fn store_and_load_round_trip() {
+        let _k = keyring();
+        assert!(load_gitlab_token().unwrap().is_none());
+        store_gitlab_token("  glpat-abc  ").unwrap();
+        assert_eq!(load_gitlab_token().unwrap().as_deref(), Some("glpat-abc"));
+        store_gitlab_token("").unwrap();
+        assert!(load_gitlab_token().unwrap().is_none());
+        delete_gitlab_token().unwrap();
+    }
+
+    #[test]
+    fn legacy_config_token_is_migrated_to_keyring() {
+        let _k = keyring();
+        let source = resolve_gitlab_token(Some("glpat-legacy"), false);
+        assert_eq!(source.token.as_deref(), Some("glpat-legacy"));
+        assert!(!source.in_config && source.error.is_none());
+        assert_eq!(load_gitlab_token().unwrap().as_deref(), Some("glpat-legacy"));
+        let source = resolve_gitlab_token(None, false);
+        assert_eq!(source.token.as_deref(), Some("glpat-legacy"));
+    }
+
+    #[test]
+    fn failed_migration_keeps_token_in_config() {
+        let _k = keyring();
+        FAIL.store(true, Ordering::SeqCst);
+        let source = resolve_gitlab_token(Some("glpat-legacy"), false);
+        assert_eq!(source.token.as_deref(), Some("glpat-legacy"));
+        assert!(source.in_config && source.error.is_some());
+        assert!(save_gitlab_token("glpat-new", false).is_err());
+    }
+
+    #[test]
+    fn plaintext_opt_in_uses_config_and_clears_keyring() {
+        let _k = keyring();
+        store_gitlab_token("glpat-old").unwrap();
+        let source = resolve_gitlab_token(Some("glpat-plain"), true);
+        assert_eq!(source.token.as_deref(), Some("glpat-plain"));
+        assert!(source.in_config && source.error.is_none());
+        save_gitlab_token("glpat-plain", true).unwrap();
+        assert!(load_gitlab_token().unwrap().is_none());
+        assert!(resolve_gitlab_token(None, true).token.is_none());
+    }
+
+    #[test]
+    fn token_is_only_offered_to_the_configured_gitlab_host() {
+        let _k = keyring();
+        set_session_gitlab_url(Some("gitlab.example.com/"));
+        assert!(is_gitlab_remote("https://gitlab.example.com/group/app.git"));
+        assert!(is_gitlab_remote("https://GitLab.example.com:443/group/app.git"));
+        assert!(!is_gitlab_remote("https://github.com/group/app.git"));
+        assert!(!is_gitlab_remote("https://gitlab.example.com:8443/group/app.git"));
+        set_session_gitlab_url(None);
+        assert!(!is_gitlab_remote("https://gitlab.example.com/group/app.git"));
+    }
+}
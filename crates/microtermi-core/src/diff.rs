@@ -0,0 +1,572 @@
+//! Diff estructurado (archivos, hunks y líneas) y preparación parcial de hunks o líneas en el index.
+
+use crate::git::{GitError, GitRepo};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Qué comparar.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum DiffTarget {
+    /// Árbol de trabajo frente al index: cambios sin preparar (incluye archivos sin seguimiento).
+    Worktree,
+    /// Index frente a HEAD: cambios preparados para el próximo commit.
+    Index,
+    /// Un commit (id o prefijo) frente a su primer padre.
+    Commit(String),
+    /// `stash@{n}` frente al commit sobre el que se guardó, con sus archivos sin seguimiento.
+    Stash(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+    /// Contenido sin el salto de línea final.
+    pub content: String,
+    /// La línea es la última del archivo y no termina en salto de línea.
+    pub no_newline: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffHunk {
+    /// Cabecera `@@ -a,b +c,d @@ ...`.
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// Ruta anterior si el archivo se renombró.
+    pub old_path: Option<String>,
+    /// "added", "deleted", "modified", "renamed", "untracked"...
+    pub status: String,
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+fn delta_status(status: git2::Delta) -> &'static str {
+    match status {
+        git2::Delta::Added => "added",
+        git2::Delta::Deleted => "deleted",
+        git2::Delta::Modified => "modified",
+        git2::Delta::Renamed => "renamed",
+        git2::Delta::Copied => "copied",
+        git2::Delta::Untracked => "untracked",
+        git2::Delta::Typechange => "typechange",
+        _ => "changed",
+    }
+}
+
+fn file_path(file: git2::DiffFile<'_>) -> Option<String> {
+    file.path().map(|p| p.to_string_lossy().replace('\\', "/"))
+}
+
+fn raw_diff<'r>(
+    r: &'r git2::Repository,
+    target: &DiffTarget,
+    path: Option<&Path>,
+) -> Result<git2::Diff<'r>, GitError> {
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(3);
+    if let Some(p) = path {
+        opts.pathspec(p).disable_pathspec_match(true);
+    }
+    let mut diff = match target {
+        DiffTarget::Worktree => {
+            opts.include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .show_untracked_content(true);
+            r.diff_index_to_workdir(None, Some(&mut opts))?
+        }
+        DiffTarget::Index => {
+            let head_tree = r.head().ok().and_then(|h| h.peel_to_tree().ok());
+            r.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))?
+        }
+        DiffTarget::Commit(id) => {
+            let commit = r.revparse_single(id.trim())?.peel_to_commit()?;
+            let parent_tree = commit.parent(0).ok().map(|p| p.tree()).transpose()?;
+            r.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut opts))?
+        }
+        DiffTarget::Stash(index) => {
+            let stash = r
+                .revparse_single(&format!("stash@{{{}}}", index))
+                .map_err(|_| GitError::StashNotFound(*index))?
+                .peel_to_commit()?;
+            let base = stash.parent(0)?.tree()?;
+            let mut d = r.diff_tree_to_tree(Some(&base), Some(&stash.tree()?), Some(&mut opts))?;
+            // Con -u los archivos sin seguimiento van en un tercer padre.
+            if let Ok(untracked) = stash.parent(2) {
+                d.merge(&r.diff_tree_to_tree(None, Some(&untracked.tree()?), Some(&mut opts))?)?;
+            }
+            d
+        }
+    };
+    if !matches!(target, DiffTarget::Worktree) {
+        diff.find_similar(None)?;
+    }
+    Ok(diff)
+}
+
+/// Diff por archivo con sus hunks y líneas. Con `path` solo ese archivo.
+pub fn diff(
+    repo: &GitRepo,
+    target: &DiffTarget,
+    path: Option<&Path>,
+) -> Result<Vec<FileDiff>, GitError> {
+    let d = raw_diff(&repo.0, target, path)?;
+    let mut out = Vec::new();
+    for idx in 0..d.deltas().len() {
+        let Some(delta) = d.get_delta(idx) else {
+            continue;
+        };
+        let old = file_path(delta.old_file());
+        let new = file_path(delta.new_file());
+        let path = new.clone().or_else(|| old.clone()).unwrap_or_default();
+        let old_path = old.filter(|o| delta.status() == git2::Delta::Renamed && *o != path);
+        let mut file = FileDiff {
+            path,
+            old_path,
+            status: delta_status(delta.status()).to_string(),
+            binary: delta.flags().is_binary(),
+            hunks: Vec::new(),
+        };
+        if let Some(patch) = git2::Patch::from_diff(&d, idx)? {
+            file.binary |= patch.delta().flags().is_binary();
+            for h in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(h)?;
+                let mut lines: Vec<DiffLine> = Vec::with_capacity(line_count);
+                for l in 0..line_count {
+                    let line = patch.line_in_hunk(h, l)?;
+                    let kind = match line.origin() {
+                        ' ' => DiffLineKind::Context,
+                        '+' => DiffLineKind::Added,
+                        '-' => DiffLineKind::Removed,
+                        // '=', '>' y '<': marcas de "sin salto de línea al final" de la línea anterior.
+                        _ => {
+                            if let Some(prev) = lines.last_mut() {
+                                prev.no_newline = true;
+                            }
+                            continue;
+                        }
+                    };
+                    let content = String::from_utf8_lossy(line.content());
+                    lines.push(DiffLine {
+                        kind,
+                        old_lineno: line.old_lineno(),
+                        new_lineno: line.new_lineno(),
+                        content: content.trim_end_matches(['\n', '\r']).to_string(),
+                        no_newline: false,
+                    });
+                }
+                file.hunks.push(DiffHunk {
+                    header: String::from_utf8_lossy(hunk.header())
+                        .trim_end()
+                        .to_string(),
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                    lines,
+                });
+            }
+        }
+        out.push(file);
+    }
+    Ok(out)
+}
+
+/// Línea del parche parcial: `prefix` es ' ', '+' o '-'.
+struct PatchLine<'a> {
+    prefix: char,
+    content: &'a str,
+    no_newline: bool,
+}
+
+/// Líneas del parche para un bloque de cambios (líneas seguidas sin contexto) con las borradas
+/// (marcando si están elegidas) y las añadidas elegidas. Las añadidas van tras la última borrada
+/// elegida, que es la que reemplazan (o al final si no se borra ninguna); las borradas no elegidas
+/// siguen en su sitio como contexto.
+fn push_change_block<'a>(out: &mut Vec<PatchLine<'a>>, removed: &mut Vec<(bool, &'a DiffLine)>, added: &mut Vec<&'a DiffLine>) {
+    let split = removed.iter().rposition(|(selected, _)| *selected).map_or(removed.len(), |i| i + 1);
+    let as_patch_line = |(selected, line): &(bool, &'a DiffLine)| PatchLine {
+        prefix: if *selected { '-' } else { ' ' },
+        content: &line.content,
+        no_newline: line.no_newline,
+    };
+    let (before, after) = removed.split_at(split);
+    out.extend(before.iter().map(as_patch_line));
+    out.extend(added.iter().map(|line| PatchLine { prefix: '+', content: &line.content, no_newline: line.no_newline }));
+    out.extend(after.iter().map(as_patch_line));
+    removed.clear();
+    added.clear();
+}
+
+/// Parche de un solo hunk con las líneas elegidas (`None` = todas) para aplicar al index.
+/// Las líneas añadidas no elegidas se omiten y las borradas no elegidas pasan a ser contexto.
+/// Con `reverse` se invierte el hunk (para quitar del index lo que ya estaba preparado).
+/// `new_file` lleva el modo del archivo si aún no está en el index. `None` si la selección no cambia
+/// nada (solo líneas de contexto).
+fn partial_patch(
+    file: &FileDiff,
+    hunk: &DiffHunk,
+    lines: Option<&[usize]>,
+    reverse: bool,
+    new_file: Option<u32>,
+) -> Option<String> {
+    let mut out: Vec<PatchLine> = Vec::new();
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    for (i, line) in hunk.lines.iter().enumerate() {
+        let selected = lines.is_none_or(|sel| sel.contains(&i));
+        let kind = match (line.kind, reverse) {
+            (DiffLineKind::Added, true) => DiffLineKind::Removed,
+            (DiffLineKind::Removed, true) => DiffLineKind::Added,
+            (k, _) => k,
+        };
+        match kind {
+            DiffLineKind::Context => {
+                push_change_block(&mut out, &mut removed, &mut added);
+                out.push(PatchLine { prefix: ' ', content: &line.content, no_newline: line.no_newline });
+            }
+            DiffLineKind::Added if selected => added.push(line),
+            DiffLineKind::Added => {}
+            DiffLineKind::Removed => removed.push((selected, line)),
+        }
+    }
+    push_change_block(&mut out, &mut removed, &mut added);
+    if out.iter().all(|l| l.prefix == ' ') {
+        return None;
+    }
+    // Solo la última línea del archivo nuevo puede quedar sin salto de línea. Si al omitir líneas una
+    // que no lo tenía deja de ser la última, lo gana: la añadida se escribe con él y el contexto se
+    // cambia por borrarla y añadirla de nuevo.
+    let last_new = out.iter().rposition(|l| l.prefix != '-');
+    let mut body = String::new();
+    let (mut old_count, mut new_count) = (0, 0);
+    for (i, line) in out.iter().enumerate() {
+        let parts = if !line.no_newline || line.prefix == '-' || Some(i) == last_new {
+            vec![(line.prefix, true)]
+        } else if line.prefix == ' ' {
+            vec![('-', true), ('+', false)]
+        } else {
+            vec![('+', false)]
+        };
+        for (prefix, keep_no_newline) in parts {
+            if prefix != '+' {
+                old_count += 1;
+            }
+            if prefix != '-' {
+                new_count += 1;
+            }
+            body.push(prefix);
+            body.push_str(line.content);
+            body.push('\n');
+            if line.no_newline && keep_no_newline {
+                body.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+    let old_start = if reverse {
+        hunk.new_start
+    } else {
+        hunk.old_start
+    };
+    let old_name = file
+        .old_path
+        .as_deref()
+        .filter(|_| !reverse)
+        .unwrap_or(&file.path);
+    let mut patch = format!("diff --git a/{} b/{}\n", old_name, file.path);
+    if let Some(mode) = new_file {
+        patch.push_str(&format!("new file mode {:o}\n--- /dev/null\n", mode));
+    } else {
+        patch.push_str(&format!("--- a/{}\n", old_name));
+    }
+    patch.push_str(&format!("+++ b/{}\n", file.path));
+    patch.push_str(&format!(
+        "@@ -{},{} +{},{} @@\n",
+        if old_count == 0 { 0 } else { old_start.max(1) },
+        old_count,
+        if new_count == 0 { 0 } else { old_start.max(1) },
+        new_count
+    ));
+    patch.push_str(&body);
+    Some(patch)
+}
+
+fn apply_hunk(
+    repo: &GitRepo,
+    path: &Path,
+    hunk_index: usize,
+    lines: Option<&[usize]>,
+    reverse: bool,
+) -> Result<(), GitError> {
+    let r = &repo.0;
+    let target = if reverse {
+        DiffTarget::Index
+    } else {
+        DiffTarget::Worktree
+    };
+    let files = diff(repo, &target, Some(path))?;
+    let file = files
+        .first()
+        .ok_or_else(|| git2::Error::from_str("no changes for this file"))?;
+    if file.binary {
+        return Err(git2::Error::from_str("cannot stage part of a binary file").into());
+    }
+    let hunk = file
+        .hunks
+        .get(hunk_index)
+        .ok_or_else(|| git2::Error::from_str("hunk not found; refresh the diff"))?;
+    // Preparar un archivo sin seguimiento, o quitar del index un borrado preparado, crea el archivo en el index.
+    let new_file = match (reverse, file.status.as_str()) {
+        (false, "untracked") => Some(worktree_mode(r, path)),
+        (true, "deleted") => Some(head_mode(r, path)),
+        _ => None,
+    };
+    let Some(patch) = partial_patch(file, hunk, lines, reverse, new_file) else {
+        return Ok(());
+    };
+    let d = git2::Diff::from_buffer(patch.as_bytes())?;
+    r.apply(&d, git2::ApplyLocation::Index, None)?;
+    // Quitar todas las líneas de un archivo nuevo, o preparar todo el borrado de uno borrado, lo deja fuera
+    // del index, no como blob vacío.
+    if matches!((reverse, file.status.as_str()), (true, "added") | (false, "deleted")) {
+        let mut index = r.index()?;
+        let empty = index
+            .get_path(path, 0)
+            .is_some_and(|e| r.find_blob(e.id).is_ok_and(|b| b.size() == 0));
+        if empty {
+            index.remove_path(path)?;
+            index.write()?;
+        }
+    }
+    Ok(())
+}
+
+/// Modo con el que git añadiría el archivo del árbol de trabajo (`100755` si es ejecutable, `120000` si es
+/// un enlace simbólico); `100644` si no se puede saber.
+fn worktree_mode(r: &git2::Repository, path: &Path) -> u32 {
+    let mut opts = git2::DiffOptions::new();
+    opts.include_untracked(true).disable_pathspec_match(true).pathspec(path);
+    r.diff_index_to_workdir(None, Some(&mut opts))
+        .ok()
+        .and_then(|d| d.deltas().next().map(|delta| u32::from(delta.new_file().mode())))
+        .filter(|&mode| mode != 0)
+        .unwrap_or(0o100644)
+}
+
+/// Modo del archivo en HEAD; `100644` si no está.
+fn head_mode(r: &git2::Repository, path: &Path) -> u32 {
+    r.head()
+        .and_then(|h| h.peel_to_tree())
+        .and_then(|tree| tree.get_path(path))
+        .map_or(0o100644, |entry| entry.filemode() as u32)
+}
+
+/// Prepara en el index un hunk del diff sin preparar de `path`, o solo algunas de sus líneas
+/// (índices en `DiffHunk::lines`).
+pub fn stage_hunk(
+    repo: &GitRepo,
+    path: &Path,
+    hunk: usize,
+    lines: Option<&[usize]>,
+) -> Result<(), GitError> {
+    apply_hunk(repo, path, hunk, lines, false)
+}
+
+/// Quita del index un hunk ya preparado de `path`, o solo algunas de sus líneas.
+pub fn unstage_hunk(
+    repo: &GitRepo,
+    path: &Path,
+    hunk: usize,
+    lines: Option<&[usize]>,
+) -> Result<(), GitError> {
+    apply_hunk(repo, path, hunk, lines, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempRepo;
+
+    /// Contenido de `path` en el index, o `None` si no está.
+    fn index_content(repo: &TempRepo, path: &str) -> Option<String> {
+        let r = repo.git();
+        let entry = r.index().unwrap().get_path(Path::new(path), 0)?;
+        Some(String::from_utf8(r.find_blob(entry.id).unwrap().content().to_vec()).unwrap())
+    }
+
+    fn index_mode(repo: &TempRepo, path: &str) -> Option<u32> {
+        repo.git().index().unwrap().get_path(Path::new(path), 0).map(|e| e.mode)
+    }
+
+    fn hunks(repo: &TempRepo, target: DiffTarget, path: &str) -> Vec<DiffHunk> {
+        let mut files = diff(&repo.repo, &target, Some(Path::new(path))).unwrap();
+        assert_eq!(files.len(), 1, "{:?}", files);
+        files.remove(0).hunks
+    }
+
+    /// Índices de las líneas del hunk cuyo contenido está en `contents` (y no son contexto).
+    fn select(hunk: &DiffHunk, contents: &[&str]) -> Vec<usize> {
+        let changed = hunk.lines.iter().enumerate().filter(|(_, l)| l.kind != DiffLineKind::Context);
+        changed.filter(|(_, l)| contents.contains(&l.content.as_str())).map(|(i, _)| i).collect()
+    }
+
+    #[test]
+    fn stage_and_unstage_selected_lines_of_a_modified_file() {
+        let repo = TempRepo::new();
+        repo.commit_file("f.txt", "one\ntwo\nthree\n", "init");
+        repo.write("f.txt", "one\nTWO\nthree\nfour\n");
+        let hunk = &hunks(&repo, DiffTarget::Worktree, "f.txt")[0];
+        stage_hunk(&repo.repo, Path::new("f.txt"), 0, Some(&select(hunk, &["four"]))).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "one\ntwo\nthree\nfour\n");
+        let hunk = &hunks(&repo, DiffTarget::Worktree, "f.txt")[0];
+        stage_hunk(&repo.repo, Path::new("f.txt"), 0, Some(&select(hunk, &["two", "TWO"]))).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "one\nTWO\nthree\nfour\n");
+        let hunk = &hunks(&repo, DiffTarget::Index, "f.txt")[0];
+        unstage_hunk(&repo.repo, Path::new("f.txt"), 0, Some(&select(hunk, &["four"]))).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "one\nTWO\nthree\n");
+        unstage_hunk(&repo.repo, Path::new("f.txt"), 0, None).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(repo.read("f.txt"), "one\nTWO\nthree\nfour\n");
+    }
+
+    #[test]
+    fn stage_lines_of_an_untracked_file() {
+        let repo = TempRepo::new();
+        repo.commit_file("a.txt", "a\n", "init");
+        repo.write("new.txt", "keep 1\nskip\nkeep 2\n");
+        let hunk = &hunks(&repo, DiffTarget::Worktree, "new.txt")[0];
+        stage_hunk(&repo.repo, Path::new("new.txt"), 0, Some(&select(hunk, &["keep 1", "keep 2"]))).unwrap();
+        assert_eq!(index_content(&repo, "new.txt").unwrap(), "keep 1\nkeep 2\n");
+        assert_eq!(index_mode(&repo, "new.txt"), Some(0o100644));
+        // Quitar todas sus líneas lo saca del index (vuelve a estar sin seguimiento).
+        unstage_hunk(&repo.repo, Path::new("new.txt"), 0, None).unwrap();
+        assert_eq!(index_content(&repo, "new.txt"), None);
+        assert_eq!(repo.read("new.txt"), "keep 1\nskip\nkeep 2\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stage_an_untracked_executable_keeps_its_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let repo = TempRepo::new();
+        repo.commit_file("a.txt", "a\n", "init");
+        repo.write("run.sh", "#!/bin/sh\necho hi\n");
+        std::fs::set_permissions(repo.path().join("run.sh"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        stage_hunk(&repo.repo, Path::new("run.sh"), 0, None).unwrap();
+        assert_eq!(index_mode(&repo, "run.sh"), Some(0o100755));
+        assert_eq!(index_content(&repo, "run.sh").unwrap(), "#!/bin/sh\necho hi\n");
+    }
+
+    #[test]
+    fn stage_lines_of_a_deleted_file() {
+        let repo = TempRepo::new();
+        repo.commit_file("gone.txt", "1\n2\n3\n", "init");
+        std::fs::remove_file(repo.path().join("gone.txt")).unwrap();
+        let hunk = &hunks(&repo, DiffTarget::Worktree, "gone.txt")[0];
+        stage_hunk(&repo.repo, Path::new("gone.txt"), 0, Some(&select(hunk, &["2"]))).unwrap();
+        assert_eq!(index_content(&repo, "gone.txt").unwrap(), "1\n3\n");
+        // El resto del hunk completa el borrado: el archivo sale del index.
+        stage_hunk(&repo.repo, Path::new("gone.txt"), 0, None).unwrap();
+        assert_eq!(index_content(&repo, "gone.txt"), None);
+        // Y quitar el borrado preparado lo vuelve a dejar en el index.
+        unstage_hunk(&repo.repo, Path::new("gone.txt"), 0, None).unwrap();
+        assert_eq!(index_content(&repo, "gone.txt").unwrap(), "1\n2\n3\n");
+    }
+
+    #[test]
+    fn stage_lines_without_a_final_newline() {
+        let repo = TempRepo::new();
+        repo.commit_file("f.txt", "a\nb", "init");
+        repo.write("f.txt", "A\nb\nc");
+        let file_hunks = hunks(&repo, DiffTarget::Worktree, "f.txt");
+        let hunk = &file_hunks[0];
+        assert!(hunk.lines.iter().any(|l| l.no_newline));
+        stage_hunk(&repo.repo, Path::new("f.txt"), 0, Some(&select(hunk, &["a", "A"]))).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "A\nb");
+        stage_hunk(&repo.repo, Path::new("f.txt"), 0, None).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "A\nb\nc");
+        unstage_hunk(&repo.repo, Path::new("f.txt"), 0, None).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "a\nb");
+    }
+
+    #[test]
+    fn unselected_removed_lines_keep_their_place_around_the_replacements() {
+        let repo = TempRepo::new();
+        repo.commit_file("f.txt", "x\na\nb\ny\n", "init");
+        repo.write("f.txt", "x\nA\nB\ny\n");
+        let hunk = &hunks(&repo, DiffTarget::Worktree, "f.txt")[0];
+        stage_hunk(&repo.repo, Path::new("f.txt"), 0, Some(&select(hunk, &["b", "B"]))).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "x\na\nB\ny\n");
+        let hunk = &hunks(&repo, DiffTarget::Index, "f.txt")[0];
+        unstage_hunk(&repo.repo, Path::new("f.txt"), 0, Some(&select(hunk, &["b", "B"]))).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "x\na\nb\ny\n");
+        let hunk = &hunks(&repo, DiffTarget::Worktree, "f.txt")[0];
+        stage_hunk(&repo.repo, Path::new("f.txt"), 0, Some(&select(hunk, &["a", "A"]))).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "x\nA\nb\ny\n");
+    }
+
+    #[test]
+    fn lines_added_after_a_last_line_without_newline() {
+        let repo = TempRepo::new();
+        repo.commit_file("f.txt", "a\nb", "init");
+        repo.write("f.txt", "A\nb\nc");
+        let hunk = &hunks(&repo, DiffTarget::Worktree, "f.txt")[0];
+        stage_hunk(&repo.repo, Path::new("f.txt"), 0, Some(&select(hunk, &["c"]))).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "a\nb\nc");
+        let repo = TempRepo::new();
+        repo.commit_file("f.txt", "x\ny", "init");
+        repo.write("f.txt", "z");
+        let hunk = &hunks(&repo, DiffTarget::Worktree, "f.txt")[0];
+        stage_hunk(&repo.repo, Path::new("f.txt"), 0, Some(&select(hunk, &["x", "z"]))).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "z\ny");
+    }
+
+    #[test]
+    fn unstage_part_of_a_staged_deletion() {
+        let repo = TempRepo::new();
+        repo.commit_file("gone.txt", "1\n2\n3\n", "init");
+        std::fs::remove_file(repo.path().join("gone.txt")).unwrap();
+        stage_hunk(&repo.repo, Path::new("gone.txt"), 0, None).unwrap();
+        assert_eq!(index_content(&repo, "gone.txt"), None);
+        let hunk = &hunks(&repo, DiffTarget::Index, "gone.txt")[0];
+        unstage_hunk(&repo.repo, Path::new("gone.txt"), 0, Some(&select(hunk, &["1", "3"]))).unwrap();
+        assert_eq!(index_content(&repo, "gone.txt").unwrap(), "1\n3\n");
+        assert_eq!(index_mode(&repo, "gone.txt"), Some(0o100644));
+    }
+
+    #[test]
+    fn context_only_selection_is_an_empty_patch() {
+        let repo = TempRepo::new();
+        repo.commit_file("f.txt", "one\ntwo\nthree\n", "init");
+        repo.write("f.txt", "one\nTWO\nthree\n");
+        let files = diff(&repo.repo, &DiffTarget::Worktree, Some(Path::new("f.txt"))).unwrap();
+        let hunk = &files[0].hunks[0];
+        let context: Vec<usize> = (0..hunk.lines.len()).filter(|&i| hunk.lines[i].kind == DiffLineKind::Context).collect();
+        assert_eq!(partial_patch(&files[0], hunk, Some(&context), false, None), None);
+        assert_eq!(partial_patch(&files[0], hunk, Some(&[]), false, None), None);
+        assert!(partial_patch(&files[0], hunk, None, false, None).is_some());
+        stage_hunk(&repo.repo, Path::new("f.txt"), 0, Some(&context)).unwrap();
+        assert_eq!(index_content(&repo, "f.txt").unwrap(), "one\ntwo\nthree\n");
+        // En un archivo sin seguimiento no se añade un blob vacío al index.
+        repo.write("new.txt", "x\n");
+        stage_hunk(&repo.repo, Path::new("new.txt"), 0, Some(&[])).unwrap();
+        assert_eq!(index_content(&repo, "new.txt"), None);
+    }
+}
@@ -1,5 +1,6 @@
-use serde::Serialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::path::Path;
 use thiserror::Error;
 
@@ -34,36 +35,210 @@ impl Environment {
 pub enum EnvError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Encryption error: {0}")]
+    Crypto(String),
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Formato inválido: {0}")]
+    Format(String),
 }
 
-/// Load env vars from root/.env.<env> or root/.env
-pub fn load_env(root: &Path, env: Environment) -> Result<HashMap<String, String>, EnvError> {
-    let mut vars = HashMap::new();
-    let env_path = root.join(env.env_file_name());
-    if env_path.exists() {
-        let content = std::fs::read_to_string(&env_path)?;
-        for line in content.lines() {
+/// Cifrado del fichero de variables de un ambiente.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum EnvEncryption {
+    /// root/.env.<env> en texto plano.
+    Plain,
+    /// root/.env.<env>.age (age con armadura ASCII).
+    Age,
+    /// root/.env.<env> en formato dotenv de SOPS (se descifra con el binario `sops`, versión
+    /// `SOPS_MIN_VERSION` o posterior).
+    Sops,
+}
+
+/// Detecta cómo está guardado el fichero del ambiente.
+pub fn env_encryption(root: &Path, env: Environment) -> EnvEncryption {
+    if root.join(age_file_name(env)).exists() {
+        return EnvEncryption::Age;
+    }
+    match std::fs::read_to_string(root.join(env.env_file_name())) {
+        Ok(content) if is_sops_dotenv(&content) => EnvEncryption::Sops,
+        _ => EnvEncryption::Plain,
+    }
+}
+
+fn age_file_name(env: Environment) -> String {
+    format!("{}.age", env.env_file_name())
+}
+
+fn is_sops_dotenv(content: &str) -> bool {
+    content
+        .lines()
+        .any(|l| l.trim_start().starts_with("sops_version=") || l.trim_start().starts_with("sops_mac="))
+}
+
+/// Ruta del fichero de claves age: $SOPS_AGE_KEY_FILE o la ruta por defecto de SOPS
+/// (~/.config/sops/age/keys.txt; %APPDATA%/sops/age/keys.txt en Windows).
+pub fn age_key_file() -> Option<std::path::PathBuf> {
+    if let Some(p) = std::env::var_os("SOPS_AGE_KEY_FILE") {
+        return Some(p.into());
+    }
+    #[cfg(windows)]
+    let base = std::env::var_os("APPDATA").map(std::path::PathBuf::from);
+    #[cfg(not(windows))]
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| std::path::PathBuf::from(h).join(".config")));
+    base.map(|b| b.join("sops").join("age").join("keys.txt"))
+}
+
+fn load_age_identities() -> Result<Vec<age::x25519::Identity>, EnvError> {
+    let path = age_key_file().ok_or_else(|| EnvError::Crypto("no se encontró el fichero de claves age".to_string()))?;
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| EnvError::Crypto(format!("no se pudo leer {}: {}", path.display(), e)))?;
+    let mut identities: Vec<age::x25519::Identity> = Vec::new();
+    let mut invalid = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if !line.starts_with("AGE-SECRET-KEY-") {
+            continue;
+        }
+        match line.parse() {
+            Ok(identity) => identities.push(identity),
+            // Solo el número de línea: la clave en sí no debe acabar en un mensaje.
+            Err(_) => invalid.push((n + 1).to_string()),
+        }
+    }
+    if !invalid.is_empty() {
+        return Err(EnvError::Crypto(format!(
+            "{}: clave age no válida en la(s) línea(s) {}",
+            path.display(),
+            invalid.join(", ")
+        )));
+    }
+    if identities.is_empty() {
+        return Err(EnvError::Crypto(format!("{} no contiene claves age", path.display())));
+    }
+    Ok(identities)
+}
+
+/// Destinatarios al cifrar: las claves públicas de las identidades locales más las de root/.age-recipients.
+fn load_age_recipients(root: &Path) -> Result<Vec<age::x25519::Recipient>, EnvError> {
+    let mut recipients: Vec<age::x25519::Recipient> =
+        load_age_identities()?.iter().map(|i| i.to_public()).collect();
+    let extra = root.join(".age-recipients");
+    if extra.exists() {
+        for line in std::fs::read_to_string(&extra)?.lines() {
             let line = line.trim();
             if line.is_empty() || line.starts_with('#') {
                 continue;
             }
-            if let Some((k, v)) = parse_env_line(line) {
-                vars.insert(k, v);
+            let r: age::x25519::Recipient = line
+                .parse()
+                .map_err(|e| EnvError::Crypto(format!(".age-recipients: {}: {}", line, e)))?;
+            recipients.push(r);
+        }
+    }
+    Ok(recipients)
+}
+
+fn age_decrypt(ciphertext: &[u8]) -> Result<String, EnvError> {
+    use std::io::Read;
+    let identities = load_age_identities()?;
+    let decryptor = age::Decryptor::new(age::armor::ArmoredReader::new(ciphertext))
+        .map_err(|e| EnvError::Crypto(e.to_string()))?;
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|i| i as &dyn age::Identity))
+        .map_err(|e| EnvError::Crypto(e.to_string()))?;
+    let mut plaintext = String::new();
+    reader.read_to_string(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+fn age_encrypt(root: &Path, plaintext: &str) -> Result<Vec<u8>, EnvError> {
+    use std::io::Write;
+    let recipients = load_age_recipients(root)?;
+    let encryptor = age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))
+        .map_err(|e| EnvError::Crypto(e.to_string()))?;
+    let mut out = Vec::new();
+    let armor = age::armor::ArmoredWriter::wrap_output(&mut out, age::armor::Format::AsciiArmor)?;
+    let mut writer = encryptor.wrap_output(armor)?;
+    writer.write_all(plaintext.as_bytes())?;
+    writer.finish()?.finish()?;
+    Ok(out)
+}
+
+/// Versión mínima de `sops`: se usan los subcomandos `decrypt`/`encrypt` y `encrypt --filename-override`
+/// (para cifrar desde stdin con las reglas de `.sops.yaml` del fichero), que llegaron en la 3.9.
+pub const SOPS_MIN_VERSION: &str = "3.9";
+
+/// Ejecuta `sops` pasando `input` por stdin y devuelve stdout (nunca escribe texto plano a disco).
+fn run_sops(args: &[&str], input: Option<&str>) -> Result<String, EnvError> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    let mut child = Command::new("sops")
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| EnvError::Crypto(format!("no se pudo ejecutar sops: {}", e)))?;
+    if let (Some(text), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(EnvError::Crypto(format!(
+            "sops (requiere {} o posterior): {}",
+            SOPS_MIN_VERSION,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn parse_env_content(content: &str, vars: &mut HashMap<String, String>) {
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((k, v)) = parse_env_line(line) {
+            vars.insert(k, v);
+        }
+    }
+}
+
+/// Load env vars from root/.env.<env> (plain or SOPS), root/.env.<env>.age or root/.env.
+/// Encrypted files are decrypted in memory only.
+pub fn load_env(root: &Path, env: Environment) -> Result<HashMap<String, String>, EnvError> {
+    let mut vars = HashMap::new();
+    let env_path = root.join(env.env_file_name());
+    match env_encryption(root, env) {
+        EnvEncryption::Age => {
+            let ciphertext = std::fs::read(root.join(age_file_name(env)))?;
+            parse_env_content(&age_decrypt(&ciphertext)?, &mut vars);
+        }
+        EnvEncryption::Sops => {
+            let path = env_path.to_string_lossy();
+            let plaintext = run_sops(
+                &["decrypt", "--input-type", "dotenv", "--output-type", "dotenv", &path],
+                None,
+            )?;
+            parse_env_content(&plaintext, &mut vars);
+        }
+        EnvEncryption::Plain => {
+            if env_path.exists() {
+                let content = std::fs::read_to_string(&env_path)?;
+                parse_env_content(&content, &mut vars);
             }
         }
     }
     let fallback = root.join(".env");
     if fallback.exists() && vars.is_empty() {
         let content = std::fs::read_to_string(&fallback)?;
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-            if let Some((k, v)) = parse_env_line(line) {
-                vars.insert(k, v);
-            }
-        }
+        parse_env_content(&content, &mut vars);
     }
     Ok(vars)
 }
@@ -76,18 +251,646 @@ fn parse_env_line(line: &str) -> Option<(String, String)> {
     Some((k, v))
 }
 
-/// Save env vars to root/.env.<env>
+fn format_env(vars: &HashMap<String, String>) -> String {
+    let mut lines: Vec<String> = vars
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect();
+    lines.sort();
+    lines.join("\n")
+}
+
+/// Save env vars to root/.env.<env>. If the environment is encrypted (age or SOPS) it is
+/// re-encrypted in memory and only the ciphertext is written.
 pub fn save_env(
     root: &Path,
     env: Environment,
     vars: &HashMap<String, String>,
 ) -> Result<(), EnvError> {
     let path = root.join(env.env_file_name());
-    let mut lines: Vec<String> = vars
+    let content = format_env(vars);
+    match env_encryption(root, env) {
+        EnvEncryption::Age => {
+            let ciphertext = age_encrypt(root, &content)?;
+            std::fs::write(root.join(age_file_name(env)), ciphertext)?;
+        }
+        EnvEncryption::Sops => {
+            let path_str = path.to_string_lossy();
+            let ciphertext = run_sops(
+                &[
+                    "encrypt",
+                    "--input-type",
+                    "dotenv",
+                    "--output-type",
+                    "dotenv",
+                    "--filename-override",
+                    &path_str,
+                ],
+                Some(&content),
+            )?;
+            std::fs::write(path, ciphertext)?;
+        }
+        EnvEncryption::Plain => std::fs::write(path, content)?,
+    }
+    Ok(())
+}
+
+/// Cifra el ambiente con age: escribe root/.env.<env>.age y elimina root/.env.<env> en claro.
+pub fn encrypt_env(
+    root: &Path,
+    env: Environment,
+    vars: &HashMap<String, String>,
+) -> Result<(), EnvError> {
+    let ciphertext = age_encrypt(root, &format_env(vars))?;
+    std::fs::write(root.join(age_file_name(env)), ciphertext)?;
+    let plain = root.join(env.env_file_name());
+    if plain.exists() {
+        std::fs::remove_file(plain)?;
+    }
+    Ok(())
+}
+
+/// Tipo esperado de una variable declarada en el esquema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvVarType {
+    #[default]
+    String,
+    Url,
+    Port,
+    Bool,
+    Int,
+    Enum,
+}
+
+/// Declaración de una variable: tipo, si es obligatoria, valor por defecto y valores permitidos (enum).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnvVarSpec {
+    #[serde(rename = "type", default)]
+    pub kind: EnvVarType,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+/// Esquema de variables esperadas (clave → declaración).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EnvSchema {
+    pub vars: BTreeMap<String, EnvVarSpec>,
+}
+
+/// Error de validación de una variable concreta.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnvVarIssue {
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for EnvVarIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+/// Carga el esquema desde root/.env.schema (JSON) o, si no existe, desde root/.env.example.
+/// En .env.example cada clave es obligatoria salvo que tenga un valor de ejemplo, que se usa como default.
+pub fn load_env_schema(root: &Path) -> Result<Option<EnvSchema>, EnvError> {
+    let schema_path = root.join(".env.schema");
+    if schema_path.exists() {
+        let content = std::fs::read_to_string(&schema_path)?;
+        let schema: EnvSchema = serde_json::from_str(&content)?;
+        return Ok(Some(schema));
+    }
+    let example_path = root.join(".env.example");
+    if example_path.exists() {
+        let content = std::fs::read_to_string(&example_path)?;
+        let mut vars = BTreeMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((k, v)) = parse_env_line(line) {
+                let default = if v.is_empty() { None } else { Some(v) };
+                vars.insert(
+                    k,
+                    EnvVarSpec {
+                        required: default.is_none(),
+                        default,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+        return Ok(Some(EnvSchema { vars }));
+    }
+    Ok(None)
+}
+
+/// Comprueba un valor contra su declaración. Devuelve el motivo si no es válido.
+pub fn validate_env_value(spec: &EnvVarSpec, value: &str) -> Option<String> {
+    match spec.kind {
+        EnvVarType::String => None,
+        EnvVarType::Url => {
+            let rest = value
+                .strip_prefix("http://")
+                .or_else(|| value.strip_prefix("https://"))
+                .or_else(|| value.split_once("://").map(|(_, r)| r));
+            match rest {
+                Some(r) if !r.is_empty() => None,
+                _ => Some(format!("\"{}\" no es una URL válida", value)),
+            }
+        }
+        EnvVarType::Port => match value.parse::<u16>() {
+            Ok(p) if p > 0 => None,
+            _ => Some(format!("\"{}\" no es un puerto válido (1-65535)", value)),
+        },
+        EnvVarType::Bool => match value.to_lowercase().as_str() {
+            "true" | "false" | "1" | "0" | "yes" | "no" => None,
+            _ => Some(format!("\"{}\" no es un booleano (true/false)", value)),
+        },
+        EnvVarType::Int => match value.parse::<i64>() {
+            Ok(_) => None,
+            Err(_) => Some(format!("\"{}\" no es un entero", value)),
+        },
+        EnvVarType::Enum => {
+            if spec.values.iter().any(|v| v == value) {
+                None
+            } else {
+                Some(format!(
+                    "\"{}\" no es uno de: {}",
+                    value,
+                    spec.values.join(", ")
+                ))
+            }
+        }
+    }
+}
+
+/// Valida las variables contra el esquema. Si todo es correcto devuelve las variables con los
+/// defaults del esquema aplicados; si no, la lista de errores por clave.
+pub fn validate_env(
+    schema: &EnvSchema,
+    vars: &HashMap<String, String>,
+) -> Result<HashMap<String, String>, Vec<EnvVarIssue>> {
+    let mut out = vars.clone();
+    let mut issues = Vec::new();
+    for (key, spec) in &schema.vars {
+        let value = match vars.get(key).filter(|v| !v.is_empty()) {
+            Some(v) => v.clone(),
+            None => match &spec.default {
+                Some(d) => {
+                    out.insert(key.clone(), d.clone());
+                    continue;
+                }
+                None if spec.required => {
+                    issues.push(EnvVarIssue {
+                        key: key.clone(),
+                        message: "falta (obligatoria)".to_string(),
+                    });
+                    continue;
+                }
+                None => continue,
+            },
+        };
+        if let Some(message) = validate_env_value(spec, &value) {
+            issues.push(EnvVarIssue {
+                key: key.clone(),
+                message,
+            });
+        }
+    }
+    if issues.is_empty() {
+        Ok(out)
+    } else {
+        Err(issues)
+    }
+}
+
+/// Patrones por defecto de claves que se consideran secretas (`*` = cualquier secuencia).
+pub const DEFAULT_SECRET_PATTERNS: &[&str] = &[
+    "*_TOKEN",
+    "*_SECRET",
+    "*_KEY",
+    "*PASSWORD*",
+    "*_PASS",
+];
+
+/// Compara `text` con un patrón con comodines `*`, sin distinguir mayúsculas.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_uppercase();
+    let text = text.to_uppercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let mut rest = text.as_str();
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            continue;
+        }
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(r) => rest = r,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(pos) => rest = &rest[pos + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
+/// Indica si la clave coincide con alguno de los patrones de secretos.
+pub fn is_secret_key(key: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|p| wildcard_match(p.trim(), key))
+}
+
+/// Longitud mínima de un valor secreto para redactarlo: uno más corto (`1`, `on`) taparía medio log.
+pub const MIN_SECRET_LEN: usize = 4;
+
+/// Valores de las variables secretas (de al menos `MIN_SECRET_LEN` caracteres), de más largo a más corto
+/// para redactar sin solapes.
+pub fn secret_values(vars: &HashMap<String, String>, patterns: &[String]) -> Vec<String> {
+    let mut values: Vec<String> = vars
         .iter()
-        .map(|(k, v)| format!("{}={}", k, v))
+        .filter(|(k, v)| v.chars().count() >= MIN_SECRET_LEN && is_secret_key(k, patterns))
+        .map(|(_, v)| v.clone())
         .collect();
-    lines.sort();
-    std::fs::write(path, lines.join("\n"))?;
-    Ok(())
+    values.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    values.dedup();
+    values
+}
+
+/// Sustituye cualquier aparición de los valores secretos en la línea por `****`.
+pub fn redact_secrets(line: &str, secrets: &[String]) -> String {
+    let mut out = line.to_string();
+    for s in secrets {
+        if !s.is_empty() && out.contains(s.as_str()) {
+            out = out.replace(s.as_str(), "****");
+        }
+    }
+    out
+}
+
+/// Formatos de intercambio para importar/exportar un conjunto de variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvFormat {
+    /// `KEY=value`, compatible con `env_file` de docker-compose.
+    Dotenv,
+    Json,
+    K8sConfigMap,
+    K8sSecret,
+}
+
+impl EnvFormat {
+    pub const ALL: [EnvFormat; 4] = [
+        EnvFormat::Dotenv,
+        EnvFormat::Json,
+        EnvFormat::K8sConfigMap,
+        EnvFormat::K8sSecret,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EnvFormat::Dotenv => "docker-compose env_file",
+            EnvFormat::Json => "JSON",
+            EnvFormat::K8sConfigMap => "Kubernetes ConfigMap",
+            EnvFormat::K8sSecret => "Kubernetes Secret",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            EnvFormat::Dotenv => "env",
+            EnvFormat::Json => "json",
+            EnvFormat::K8sConfigMap | EnvFormat::K8sSecret => "yaml",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct K8sMetadata {
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct K8sManifest {
+    api_version: String,
+    kind: String,
+    metadata: K8sMetadata,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    secret_type: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    data: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    string_data: BTreeMap<String, String>,
+}
+
+/// Nombre de recurso Kubernetes válido a partir de un nombre libre (minúsculas, `-`).
+fn k8s_resource_name(name: &str) -> String {
+    let mut out: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '-' })
+        .collect();
+    out = out.trim_matches(|c| c == '-' || c == '.').to_string();
+    if out.is_empty() {
+        "env".to_string()
+    } else {
+        out
+    }
+}
+
+/// Exporta las variables del ambiente al formato indicado. `name` se usa como metadata.name en
+/// Kubernetes (se le añade el ambiente, ej. `api-prod`).
+pub fn export_env(
+    vars: &HashMap<String, String>,
+    env: Environment,
+    format: EnvFormat,
+    name: &str,
+) -> Result<String, EnvError> {
+    let sorted: BTreeMap<String, String> = vars.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    let resource_name = k8s_resource_name(&format!("{}-{}", name, env.as_str()));
+    match format {
+        EnvFormat::Dotenv => {
+            let lines: Vec<String> = sorted
+                .iter()
+                .map(|(k, v)| {
+                    if v.contains('"') {
+                        format!("{}='{}'", k, v)
+                    } else if v.contains(char::is_whitespace) || v.contains('#') {
+                        format!("{}=\"{}\"", k, v)
+                    } else {
+                        format!("{}={}", k, v)
+                    }
+                })
+                .collect();
+            Ok(lines.join("\n") + "\n")
+        }
+        EnvFormat::Json => Ok(serde_json::to_string_pretty(&sorted)?),
+        EnvFormat::K8sConfigMap => Ok(serde_yaml::to_string(&K8sManifest {
+            api_version: "v1".to_string(),
+            kind: "ConfigMap".to_string(),
+            metadata: K8sMetadata { name: resource_name },
+            secret_type: None,
+            data: sorted,
+            string_data: BTreeMap::new(),
+        })?),
+        EnvFormat::K8sSecret => {
+            use base64::Engine;
+            let data = sorted
+                .into_iter()
+                .map(|(k, v)| (k, base64::engine::general_purpose::STANDARD.encode(v)))
+                .collect();
+            Ok(serde_yaml::to_string(&K8sManifest {
+                api_version: "v1".to_string(),
+                kind: "Secret".to_string(),
+                metadata: K8sMetadata { name: resource_name },
+                secret_type: Some("Opaque".to_string()),
+                data,
+                string_data: BTreeMap::new(),
+            })?)
+        }
+    }
+}
+
+/// Importa variables desde el formato indicado. Para Kubernetes se aceptan ConfigMap y Secret
+/// (`data` en base64 y `stringData` en claro).
+pub fn import_env(content: &str, format: EnvFormat) -> Result<HashMap<String, String>, EnvError> {
+    match format {
+        EnvFormat::Dotenv => {
+            let mut vars = HashMap::new();
+            parse_env_content(content, &mut vars);
+            Ok(vars)
+        }
+        EnvFormat::Json => {
+            let map: BTreeMap<String, serde_json::Value> = serde_json::from_str(content)?;
+            Ok(map
+                .into_iter()
+                .map(|(k, v)| {
+                    let v = match v {
+                        serde_json::Value::String(s) => s,
+                        serde_json::Value::Null => String::new(),
+                        other => other.to_string(),
+                    };
+                    (k, v)
+                })
+                .collect())
+        }
+        EnvFormat::K8sConfigMap | EnvFormat::K8sSecret => {
+            let manifest: K8sManifest = serde_yaml::from_str(content)?;
+            let mut vars = HashMap::new();
+            match manifest.kind.as_str() {
+                "ConfigMap" => vars.extend(manifest.data),
+                "Secret" => {
+                    use base64::Engine;
+                    for (k, v) in manifest.data {
+                        let bytes = base64::engine::general_purpose::STANDARD
+                            .decode(v.trim())
+                            .map_err(|e| EnvError::Format(format!("{}: base64 inválido ({})", k, e)))?;
+                        let value = String::from_utf8(bytes)
+                            .map_err(|_| EnvError::Format(format!("{}: no es UTF-8", k)))?;
+                        vars.insert(k, value);
+                    }
+                }
+                other => return Err(EnvError::Format(format!("kind \"{}\" no soportado", other))),
+            }
+            vars.extend(manifest.string_data);
+            Ok(vars)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn spec(kind: EnvVarType) -> EnvVarSpec {
+        EnvVarSpec { kind, ..Default::default() }
+    }
+
+    #[test]
+    fn values_are_checked_against_their_type() {
+        let url = spec(EnvVarType::Url);
+        assert_eq!(validate_env_value(&url, "https://api.example.com"), None);
+        assert_eq!(validate_env_value(&url, "postgres://db/app"), None);
+        assert!(validate_env_value(&url, "https://").is_some());
+        assert!(validate_env_value(&url, "api.example.com").is_some());
+
+        let port = spec(EnvVarType::Port);
+        assert_eq!(validate_env_value(&port, "8080"), None);
+        for bad in ["0", "65536", "http"] {
+            assert!(validate_env_value(&port, bad).is_some(), "{bad}");
+        }
+
+        let flag = spec(EnvVarType::Bool);
+        for good in ["true", "FALSE", "1", "0", "yes", "no"] {
+            assert_eq!(validate_env_value(&flag, good), None, "{good}");
+        }
+        assert!(validate_env_value(&flag, "si").is_some());
+
+        let int = spec(EnvVarType::Int);
+        assert_eq!(validate_env_value(&int, "-42"), None);
+        assert!(validate_env_value(&int, "4.2").is_some());
+
+        let level = EnvVarSpec { values: vec!["debug".into(), "info".into()], ..spec(EnvVarType::Enum) };
+        assert_eq!(validate_env_value(&level, "info"), None);
+        assert_eq!(validate_env_value(&level, "trace").as_deref(), Some("\"trace\" no es uno de: debug, info"));
+        assert_eq!(validate_env_value(&spec(EnvVarType::String), ""), None);
+    }
+
+    #[test]
+    fn validation_applies_defaults_and_lists_every_issue() {
+        let schema: EnvSchema = serde_json::from_str(
+            r#"{
+                "API_URL": { "type": "url", "required": true },
+                "PORT": { "type": "port", "default": "3000" },
+                "DEBUG": { "type": "bool" },
+                "TOKEN": { "required": true }
+            }"#,
+        )
+        .unwrap();
+
+        let ok = validate_env(&schema, &vars(&[("API_URL", "https://x.dev"), ("TOKEN", "t"), ("EXTRA", "1")])).unwrap();
+        assert_eq!(ok, vars(&[("API_URL", "https://x.dev"), ("TOKEN", "t"), ("EXTRA", "1"), ("PORT", "3000")]));
+
+        // Vacía cuenta como ausente; las opcionales sin valor no dan error.
+        let issues = validate_env(&schema, &vars(&[("API_URL", "x"), ("PORT", "99999"), ("TOKEN", "")])).unwrap_err();
+        let keys: Vec<&str> = issues.iter().map(|i| i.key.as_str()).collect();
+        assert_eq!(keys, ["API_URL", "PORT", "TOKEN"]);
+        assert_eq!(issues[2].to_string(), "TOKEN: falta (obligatoria)");
+    }
+
+    #[test]
+    fn schema_falls_back_to_env_example() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_env_schema(dir.path()).unwrap().is_none());
+
+        std::fs::write(dir.path().join(".env.example"), "# comentario\nAPI_URL=\nPORT=3000\n").unwrap();
+        let schema = load_env_schema(dir.path()).unwrap().unwrap();
+        assert!(schema.vars["API_URL"].required);
+        assert_eq!(schema.vars["PORT"].default.as_deref(), Some("3000"));
+        assert!(!schema.vars["PORT"].required);
+
+        // .env.schema tiene prioridad sobre .env.example.
+        std::fs::write(dir.path().join(".env.schema"), r#"{ "PORT": { "type": "port" } }"#).unwrap();
+        let schema = load_env_schema(dir.path()).unwrap().unwrap();
+        assert_eq!(schema.vars.keys().collect::<Vec<_>>(), ["PORT"]);
+        assert_eq!(schema.vars["PORT"].kind, EnvVarType::Port);
+
+        std::fs::write(dir.path().join(".env.schema"), "{ no es json").unwrap();
+        assert!(matches!(load_env_schema(dir.path()), Err(EnvError::Json(_))));
+    }
+
+    fn default_patterns() -> Vec<String> {
+        DEFAULT_SECRET_PATTERNS.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn secret_keys_match_the_patterns() {
+        let patterns = default_patterns();
+        for key in ["GITHUB_TOKEN", "api_key", "DB_PASSWORD", "PASSWORD_FILE", "SMTP_PASS", "APP_SECRET"] {
+            assert!(is_secret_key(key, &patterns), "{key}");
+        }
+        for key in ["TOKEN_URL", "KEYBOARD", "PASSPORT", "PORT"] {
+            assert!(!is_secret_key(key, &patterns), "{key}");
+        }
+        assert!(is_secret_key("STRIPE", &[" stripe ".to_string()]));
+        assert!(is_secret_key("AWS_ACCESS_ID", &["AWS_*_ID".to_string()]));
+        assert!(!is_secret_key("AWS_ID", &["AWS_*_ID".to_string()]));
+    }
+
+    #[test]
+    fn only_secrets_of_min_length_are_redacted() {
+        let short = "x".repeat(MIN_SECRET_LEN - 1);
+        let exact = "y".repeat(MIN_SECRET_LEN);
+        let multibyte = "ñ".repeat(MIN_SECRET_LEN);
+        let vars = vars(&[
+            ("SHORT_TOKEN", short.as_str()),
+            ("EXACT_TOKEN", exact.as_str()),
+            ("UTF8_KEY", multibyte.as_str()),
+            ("EMPTY_SECRET", ""),
+            ("PUBLIC_URL", "https://example.com"),
+        ]);
+        // Se cuentan caracteres, no bytes.
+        assert_eq!(secret_values(&vars, &default_patterns()), [multibyte.clone(), exact.clone()]);
+        let line = format!("{short} {exact} {multibyte} https://example.com");
+        assert_eq!(
+            redact_secrets(&line, &secret_values(&vars, &default_patterns())),
+            format!("{short} **** **** https://example.com")
+        );
+    }
+
+    #[test]
+    fn longer_secrets_are_redacted_first() {
+        let vars = vars(&[("A_TOKEN", "abcd"), ("B_TOKEN", "abcdefgh"), ("C_TOKEN", "abcd")]);
+        let secrets = secret_values(&vars, &default_patterns());
+        assert_eq!(secrets, ["abcdefgh", "abcd"]);
+        assert_eq!(redact_secrets("t=abcdefgh u=abcd abcd", &secrets), "t=**** u=**** ****");
+        assert_eq!(redact_secrets("sin secretos", &secrets), "sin secretos");
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        let vars = vars(&[
+            ("API_URL", "https://api.example.com/v1?a=1&b=2"),
+            ("GREETING", "hola mundo # no es comentario"),
+            ("QUOTED", r#"dice "hola""#),
+            ("EMPTY", ""),
+            ("UNICODE", "contraseña✓"),
+            ("PORT", "8080"),
+        ]);
+        for format in EnvFormat::ALL {
+            let exported = export_env(&vars, Environment::Prod, format, "Mi API").unwrap();
+            assert_eq!(import_env(&exported, format).unwrap(), vars, "{format:?}:\n{exported}");
+        }
+    }
+
+    #[test]
+    fn kubernetes_exports_name_and_encode_the_data() {
+        let vars = vars(&[("TOKEN", "s3cr3t")]);
+        let secret = export_env(&vars, Environment::Staging, EnvFormat::K8sSecret, "Mi API_v2").unwrap();
+        assert!(secret.contains("kind: Secret"));
+        assert!(secret.contains("name: mi-api-v2-staging"));
+        assert!(secret.contains("type: Opaque"));
+        assert!(secret.contains("TOKEN: czNjcjN0"));
+        assert!(!secret.contains("s3cr3t"));
+        let config_map = export_env(&vars, Environment::Dev, EnvFormat::K8sConfigMap, "--").unwrap();
+        assert!(config_map.contains("name: dev"));
+        assert!(config_map.contains("TOKEN: s3cr3t"));
+    }
+
+    #[test]
+    fn kubernetes_import_reads_string_data_and_rejects_bad_input() {
+        let manifest = "apiVersion: v1\nkind: Secret\nmetadata:\n  name: api\ndata:\n  A: YQ==\nstringData:\n  B: b\n";
+        assert_eq!(import_env(manifest, EnvFormat::K8sSecret).unwrap(), vars(&[("A", "a"), ("B", "b")]));
+
+        let bad_base64 = manifest.replace("YQ==", "no-base64!");
+        assert!(matches!(import_env(&bad_base64, EnvFormat::K8sSecret), Err(EnvError::Format(_))));
+        let deployment = manifest.replace("kind: Secret", "kind: Deployment");
+        assert!(matches!(import_env(&deployment, EnvFormat::K8sConfigMap), Err(EnvError::Format(_))));
+    }
+
+    #[test]
+    fn json_import_stringifies_other_values() {
+        let imported = import_env(r#"{ "PORT": 8080, "DEBUG": true, "NAME": "api", "NONE": null }"#, EnvFormat::Json).unwrap();
+        assert_eq!(imported, vars(&[("PORT", "8080"), ("DEBUG", "true"), ("NAME", "api"), ("NONE", "")]));
+    }
 }
@@ -1,13 +1,79 @@
 use serde::Serialize;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use thiserror::Error;
 
+/// Tipo de cambio de un archivo en el index (staged) o en el árbol de trabajo (unstaged).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    TypeChange,
+}
+
+impl FileChangeKind {
+    /// Letra corta al estilo de `git status --short`.
+    pub fn letter(&self) -> &'static str {
+        match self {
+            FileChangeKind::Added => "A",
+            FileChangeKind::Modified => "M",
+            FileChangeKind::Deleted => "D",
+            FileChangeKind::Renamed => "R",
+            FileChangeKind::TypeChange => "T",
+        }
+    }
+}
+
+/// Archivo cambiado en el index o en el árbol de trabajo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: FileChangeKind,
+    /// Ruta anterior si el archivo se renombró.
+    pub old_path: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct GitStatus {
     pub branch: String,
     pub is_clean: bool,
+    /// Todas las rutas con cambios (staged o no), sin repetir; no incluye las no seguidas.
     pub modified: Vec<String>,
     pub untracked: Vec<String>,
+    /// Cambios en el index: lo que entra en el próximo commit.
+    pub staged: Vec<FileChange>,
+    /// Cambios del árbol de trabajo que no están en el index.
+    pub unstaged: Vec<FileChange>,
+    /// Archivos con conflictos de merge sin resolver.
+    pub conflicted: Vec<String>,
+    /// Rama de seguimiento (ej. `origin/main`); `None` si la rama no tiene upstream.
+    pub upstream: Option<String>,
+    /// Commits locales que no están en el upstream (por subir).
+    pub ahead: usize,
+    /// Commits del upstream que no están en local (por bajar).
+    pub behind: usize,
+    pub merging: bool,
+    pub rebasing: bool,
+    pub cherry_picking: bool,
+    pub reverting: bool,
+    /// HEAD apunta a un commit y no a una rama.
+    pub detached: bool,
+}
+
+impl GitStatus {
+    /// Resumen tipo "↑2 ↓5" respecto al upstream; vacío si está al día o no hay upstream.
+    pub fn ahead_behind_label(&self) -> String {
+        match (self.ahead, self.behind) {
+            (0, 0) => String::new(),
+            (a, 0) => format!("↑{}", a),
+            (0, b) => format!("↓{}", b),
+            (a, b) => format!("↑{} ↓{}", a, b),
+        }
+    }
 }
 
 /// Entrada del historial de commits (log).
@@ -24,9 +90,38 @@ pub struct CommitInfo {
 pub struct CommitFileChange {
     pub path: String,
     pub status: String, // "added", "modified", "deleted"
+    /// Ruta anterior si se renombró o copió.
+    pub old_path: Option<String>,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub binary: bool,
+}
+
+/// Entrada de la lista de stashes (`stash@{index}`).
+#[derive(Debug, Clone, Serialize)]
+pub struct StashEntry {
+    pub index: usize,
+    pub id_short: String,
+    /// Mensaje tal como lo guarda git: "On rama: mensaje" o "WIP on rama: abc1234 asunto".
+    pub message: String,
+    /// Rama en la que se creó el stash (None si HEAD estaba separado).
+    pub branch: Option<String>,
+    pub date: String,
+}
+
+/// Opciones al guardar un stash.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct StashOptions {
+    /// Mensaje del stash; vacío para el "WIP on rama: ..." de git.
+    pub message: String,
+    /// Guarda también los archivos sin seguimiento (git stash -u).
+    pub include_untracked: bool,
+    /// Deja en el index lo preparado (git stash --keep-index).
+    pub keep_index: bool,
 }
 
-pub struct GitRepo(git2::Repository);
+pub struct GitRepo(pub(crate) git2::Repository);
 
 #[derive(Debug, Error)]
 pub enum GitError {
@@ -34,6 +129,199 @@ pub enum GitError {
     Git(#[from] git2::Error),
     #[error("No repository found")]
     NoRepo,
+    #[error("Local changes would be overwritten by checkout: {}", .0.join(", "))]
+    CheckoutConflicts(Vec<String>),
+    #[error("Unresolved conflicts: {}", .0.join(", "))]
+    UnresolvedConflicts(Vec<String>),
+    #[error("Cannot abort: these files have unstaged changes on top of the merge that would be lost: {}", .0.join(", "))]
+    AbortWouldDiscard(Vec<String>),
+    #[error("Branch switched, but the auto-stash could not be reapplied cleanly; it was kept as stash@{{0}}")]
+    StashNotReapplied,
+    #[error("Operation cancelled")]
+    Cancelled,
+    #[error("Branch '{0}' has no upstream; push with set-upstream to publish it")]
+    NoUpstream(String),
+    #[error("Push rejected: {}", .0.join("; "))]
+    PushRejected(Vec<String>),
+    #[error("Nothing staged to commit")]
+    NothingToCommit,
+    #[error("No local changes to stash")]
+    NothingToStash,
+    #[error("stash@{{{0}}} does not exist")]
+    StashNotFound(usize),
+    #[error("Invalid branch name '{0}'")]
+    InvalidBranchName(String),
+    #[error("Branch '{0}' already exists")]
+    BranchExists(String),
+    #[error("Branch '{0}' not found")]
+    BranchNotFound(String),
+    #[error("Branch '{0}' is checked out; switch to another branch first")]
+    BranchCheckedOut(String),
+    #[error("Branch '{0}' is not fully merged; delete it with force to discard its commits")]
+    BranchNotMerged(String),
+    #[error("stash@{{{0}}} conflicts with the current files and was not applied; commit or stash your changes first")]
+    StashConflicts(usize),
+    #[error("stash@{{{0}}} was applied with conflicts in {}; it was kept in the stash list", .1.join(", "))]
+    StashAppliedWithConflicts(usize, Vec<String>),
+    #[error("Invalid date '{0}'; use YYYY-MM-DD")]
+    InvalidDate(String),
+    #[error("Commit '{0}' not found or ambiguous")]
+    CommitNotFound(String),
+    #[error("Invalid tag name '{0}'")]
+    InvalidTagName(String),
+    #[error("Tag '{0}' already exists")]
+    TagExists(String),
+    #[error("Tag '{0}' not found")]
+    TagNotFound(String),
+    #[error("Cannot read package.json: {0}")]
+    PackageJson(String),
+    #[error("Aborting commit due to empty commit message")]
+    EmptyCommitMessage,
+    #[error("There is no commit to amend")]
+    NothingToAmend,
+    #[error("Cannot read commit template: {0}")]
+    CommitTemplate(String),
+    #[error("Cannot read commitlint config: {0}")]
+    CommitLintConfig(String),
+    #[error("Hook '{0}' failed ({1}); commit aborted")]
+    HookFailed(String, String),
+    #[error("Cannot sign commit: {0}")]
+    Signing(String),
+    #[error("Cannot access {0}: {1}")]
+    Io(String, String),
+    #[error("Repository worker panicked: {0}")]
+    WorkerPanicked(String),
+}
+
+/// Progreso de una operación git larga (red o rebase), para mostrar una barra de progreso.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GitProgress {
+    /// Fetch/clone: objetos recibidos e indexados sobre el total, y bytes recibidos.
+    Transfer {
+        received_objects: usize,
+        indexed_objects: usize,
+        total_objects: usize,
+        received_bytes: usize,
+    },
+    /// Push: objetos enviados sobre el total, y bytes enviados.
+    Push { current: usize, total: usize, bytes: usize },
+    /// Rebase: commit que se está reaplicando sobre el total.
+    Rebase { current: usize, total: usize },
+}
+
+impl GitProgress {
+    /// Fracción completada (0.0 a 1.0); 0.0 mientras no se conoce el total.
+    pub fn fraction(&self) -> f32 {
+        let (done, total) = match *self {
+            GitProgress::Transfer { received_objects, indexed_objects, total_objects, .. } => {
+                (received_objects + indexed_objects, total_objects * 2)
+            }
+            GitProgress::Push { current, total, .. } => (current, total),
+            GitProgress::Rebase { current, total } => (current, total),
+        };
+        if total == 0 {
+            0.0
+        } else {
+            done as f32 / total as f32
+        }
+    }
+}
+
+/// Token para cancelar desde otro hilo una operación git en curso.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Dónde informar el progreso de una operación y con qué token cancelarla.
+/// `ProgressSink::default()` no informa nada y no se puede cancelar.
+#[derive(Clone, Copy, Default)]
+pub struct ProgressSink<'a> {
+    pub on_progress: Option<&'a dyn Fn(GitProgress)>,
+    pub cancel: Option<&'a CancelToken>,
+}
+
+impl<'a> ProgressSink<'a> {
+    pub fn new(on_progress: &'a dyn Fn(GitProgress), cancel: &'a CancelToken) -> Self {
+        Self { on_progress: Some(on_progress), cancel: Some(cancel) }
+    }
+
+    fn report(&self, progress: GitProgress) {
+        if let Some(f) = self.on_progress {
+            f(progress);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.is_some_and(|c| c.is_cancelled())
+    }
+
+    /// Un error de libgit2 tras cancelar se devuelve como `Cancelled`.
+    pub(crate) fn error(&self, e: git2::Error) -> GitError {
+        if self.is_cancelled() {
+            GitError::Cancelled
+        } else {
+            GitError::Git(e)
+        }
+    }
+}
+
+/// Callbacks remotos: credenciales (con la config de `config`) más progreso y cancelación según `sink`.
+/// Un push solo se puede cancelar antes de empezar a subir el pack.
+pub(crate) fn remote_callbacks(config: git2::Config, sink: ProgressSink<'_>) -> git2::RemoteCallbacks<'_> {
+    let mut callbacks = crate::credentials::remote_callbacks(config);
+    callbacks.transfer_progress(move |p| {
+        sink.report(GitProgress::Transfer {
+            received_objects: p.received_objects(),
+            indexed_objects: p.indexed_objects(),
+            total_objects: p.total_objects(),
+            received_bytes: p.received_bytes(),
+        });
+        !sink.is_cancelled()
+    });
+    callbacks.sideband_progress(move |_| !sink.is_cancelled());
+    callbacks.push_transfer_progress(move |current, total, bytes| {
+        sink.report(GitProgress::Push { current, total, bytes });
+    });
+    callbacks.push_negotiation(move |_| {
+        if sink.is_cancelled() {
+            Err(git2::Error::from_str("push cancelled"))
+        } else {
+            Ok(())
+        }
+    });
+    callbacks
+}
+
+fn fetch_options(config: git2::Config, sink: ProgressSink<'_>) -> git2::FetchOptions<'_> {
+    let mut opts = git2::FetchOptions::new();
+    opts.remote_callbacks(remote_callbacks(config, sink));
+    opts
+}
+
+/// Cómo tratar los cambios locales al cambiar de rama.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckoutMode {
+    /// Lleva los cambios a la nueva rama si no chocan; si chocan devuelve `CheckoutConflicts`.
+    Safe,
+    /// Guarda los cambios en el stash, cambia de rama y los reaplica.
+    AutoStash,
+    /// Descarta los cambios locales que choquen (requiere confirmación del usuario).
+    Force,
 }
 
 pub fn open_repo(path: &Path) -> Result<GitRepo, GitError> {
@@ -47,53 +335,143 @@ pub fn open_repo(path: &Path) -> Result<GitRepo, GitError> {
     Ok(GitRepo(repo))
 }
 
-/// Clona un repositorio por URL en la ruta indicada.
-pub fn clone_repo(url: &str, path: &Path) -> Result<GitRepo, GitError> {
-    let repo = git2::Repository::clone(url, path)?;
+/// Clona un repositorio por URL en la ruta indicada. Las credenciales se piden al vuelo (ver `credentials`),
+/// así que la URL guardada en `.git/config` queda limpia.
+pub fn clone_repo(url: &str, path: &Path, sink: ProgressSink<'_>) -> Result<GitRepo, GitError> {
+    let repo = git2::build::RepoBuilder::new()
+        .fetch_options(fetch_options(git2::Config::open_default()?, sink))
+        .clone(url, path)
+        .map_err(|e| sink.error(e))?;
     Ok(GitRepo(repo))
 }
 
+fn index_change_kind(status: git2::Status) -> Option<FileChangeKind> {
+    if status.is_index_new() {
+        Some(FileChangeKind::Added)
+    } else if status.is_index_modified() {
+        Some(FileChangeKind::Modified)
+    } else if status.is_index_deleted() {
+        Some(FileChangeKind::Deleted)
+    } else if status.is_index_renamed() {
+        Some(FileChangeKind::Renamed)
+    } else if status.is_index_typechange() {
+        Some(FileChangeKind::TypeChange)
+    } else {
+        None
+    }
+}
+
+fn worktree_change_kind(status: git2::Status) -> Option<FileChangeKind> {
+    if status.is_wt_modified() {
+        Some(FileChangeKind::Modified)
+    } else if status.is_wt_deleted() {
+        Some(FileChangeKind::Deleted)
+    } else if status.is_wt_renamed() {
+        Some(FileChangeKind::Renamed)
+    } else if status.is_wt_typechange() {
+        Some(FileChangeKind::TypeChange)
+    } else {
+        None
+    }
+}
+
+fn delta_paths(delta: Option<git2::DiffDelta<'_>>) -> (Option<String>, Option<String>) {
+    let path = |f: git2::DiffFile<'_>| f.path().map(|p| p.to_string_lossy().replace('\\', "/"));
+    match delta {
+        Some(d) => (path(d.old_file()), path(d.new_file())),
+        None => (None, None),
+    }
+}
+
 pub fn status(repo: &GitRepo) -> Result<GitStatus, GitError> {
     let r = &repo.0;
     let branch = current_branch(repo)?.unwrap_or_else(|| "HEAD".to_string());
-    let mut modified = Vec::new();
+    let mut modified: Vec<String> = Vec::new();
     let mut untracked = Vec::new();
+    let mut staged = Vec::new();
+    let mut unstaged = Vec::new();
+    let mut conflicted = Vec::new();
     let mut status_opts = git2::StatusOptions::new();
-    status_opts.include_untracked(true);
-    status_opts.exclude_submodules(true);
+    status_opts
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .exclude_submodules(true)
+        .renames_head_to_index(true)
+        .renames_index_to_workdir(true);
     for entry in &r.statuses(Some(&mut status_opts))? {
+        let st = entry.status();
         let path = entry.path().unwrap_or("").to_string();
-        match entry.status() {
-            s if s.is_index_new() || s.is_index_modified() || s.is_wt_modified() || s.is_wt_new() => {
-                if !modified.contains(&path) {
-                    modified.push(path);
-                }
-            }
-            s if s.is_wt_new() && entry.status() == git2::Status::WT_NEW => {
-                if !untracked.contains(&path) {
-                    untracked.push(path);
-                }
-            }
-            _ => {}
+        if st.is_conflicted() {
+            conflicted.push(path.clone());
+            modified.push(path);
+            continue;
+        }
+        if st.is_wt_new() && index_change_kind(st).is_none() {
+            untracked.push(path);
+            continue;
+        }
+        if let Some(kind) = index_change_kind(st) {
+            let (old, new) = delta_paths(entry.head_to_index());
+            let path = new.unwrap_or_else(|| path.clone());
+            let old_path = old.filter(|o| kind == FileChangeKind::Renamed && *o != path);
+            staged.push(FileChange { path, kind, old_path });
+        }
+        if let Some(kind) = worktree_change_kind(st) {
+            let (old, new) = delta_paths(entry.index_to_workdir());
+            let path = new.unwrap_or_else(|| path.clone());
+            let old_path = old.filter(|o| kind == FileChangeKind::Renamed && *o != path);
+            unstaged.push(FileChange { path, kind, old_path });
         }
     }
-    for entry in &r.statuses(Some(&mut status_opts))? {
-        let path = entry.path().unwrap_or("").to_string();
-        if entry.status() == git2::Status::WT_NEW {
-            if !untracked.contains(&path) {
-                untracked.push(path);
-            }
+    for change in staged.iter().chain(unstaged.iter()) {
+        if !modified.contains(&change.path) {
+            modified.push(change.path.clone());
         }
     }
     let is_clean = modified.is_empty() && untracked.is_empty();
+    let detached = r.head_detached().unwrap_or(false);
+    let (upstream, ahead, behind) = if detached { (None, 0, 0) } else { upstream_tracking(r, &branch) };
     Ok(GitStatus {
         branch,
         is_clean,
         modified,
         untracked,
+        staged,
+        unstaged,
+        conflicted,
+        upstream,
+        ahead,
+        behind,
+        merging: merge_in_progress(repo),
+        rebasing: rebase_in_progress(repo),
+        cherry_picking: matches!(
+            r.state(),
+            git2::RepositoryState::CherryPick | git2::RepositoryState::CherryPickSequence
+        ),
+        reverting: matches!(
+            r.state(),
+            git2::RepositoryState::Revert | git2::RepositoryState::RevertSequence
+        ),
+        detached,
     })
 }
 
+/// Upstream de la rama local y commits por delante/detrás (según el último fetch).
+fn upstream_tracking(r: &git2::Repository, branch: &str) -> (Option<String>, usize, usize) {
+    let Ok(local) = r.find_branch(branch, git2::BranchType::Local) else {
+        return (None, 0, 0);
+    };
+    let Ok(upstream) = local.upstream() else {
+        return (None, 0, 0);
+    };
+    let name = upstream.name().ok().flatten().map(String::from);
+    let counts = match (local.get().target(), upstream.get().target()) {
+        (Some(l), Some(u)) => r.graph_ahead_behind(l, u).unwrap_or((0, 0)),
+        _ => (0, 0),
+    };
+    (name, counts.0, counts.1)
+}
+
 pub fn current_branch(repo: &GitRepo) -> Result<Option<String>, GitError> {
     let r = &repo.0;
     let head = match r.head() {
@@ -117,231 +495,1922 @@ pub fn branches(repo: &GitRepo) -> Result<Vec<String>, GitError> {
     Ok(names)
 }
 
-/// Cambia a la rama indicada (git checkout).
-pub fn checkout_branch(repo: &GitRepo, branch_name: &str) -> Result<(), GitError> {
-    let r = &repo.0;
-    let refname = format!("refs/heads/{}", branch_name);
-    let _obj = r.revparse_single(&refname).map_err(|_| GitError::NoRepo)?;
-    r.set_head(&refname)?;
-    let mut opts = git2::build::CheckoutBuilder::new();
-    opts.force();
-    r.checkout_head(Some(&mut opts))?;
+/// Hace checkout del árbol del commit `target` y apunta HEAD a `refname`. En modo seguro, si hay archivos
+/// locales que se sobrescribirían, no toca nada y devuelve `CheckoutConflicts` con sus rutas.
+fn checkout_tree_and_head(
+    r: &git2::Repository,
+    target: git2::Oid,
+    refname: &str,
+    force: bool,
+) -> Result<(), GitError> {
+    let target = r.find_object(target, None)?;
+    let mut conflicts: Vec<String> = Vec::new();
+    let result = {
+        let mut opts = git2::build::CheckoutBuilder::new();
+        if force {
+            opts.force();
+        } else {
+            opts.safe();
+            opts.notify_on(git2::CheckoutNotificationType::CONFLICT);
+            opts.notify(|_, path, _, _, _| {
+                if let Some(p) = path {
+                    conflicts.push(p.to_string_lossy().into_owned());
+                }
+                true
+            });
+        }
+        r.checkout_tree(&target, Some(&mut opts))
+    };
+    if let Err(e) = result {
+        if !conflicts.is_empty() {
+            return Err(GitError::CheckoutConflicts(conflicts));
+        }
+        return Err(e.into());
+    }
+    r.set_head(refname)?;
     Ok(())
 }
 
-pub fn commit(repo: &GitRepo, message: &str, paths: &[&Path]) -> Result<(), GitError> {
+/// True si hay cambios locales (index, working tree o archivos sin seguimiento).
+fn has_local_changes(r: &git2::Repository) -> Result<bool, GitError> {
+    let mut opts = git2::StatusOptions::new();
+    opts.include_untracked(true);
+    opts.exclude_submodules(true);
+    Ok(!r.statuses(Some(&mut opts))?.is_empty())
+}
+
+/// Checkout de `refname` según el modo. Con `AutoStash`, si el stash no se puede reaplicar se
+/// conserva en la lista y se devuelve `StashNotReapplied` (la rama ya está cambiada).
+fn checkout_with_mode(
+    r: &mut git2::Repository,
+    target: git2::Oid,
+    refname: &str,
+    mode: CheckoutMode,
+) -> Result<(), GitError> {
+    match mode {
+        CheckoutMode::Safe => checkout_tree_and_head(r, target, refname, false),
+        CheckoutMode::Force => checkout_tree_and_head(r, target, refname, true),
+        CheckoutMode::AutoStash => {
+            if !has_local_changes(r)? {
+                return checkout_tree_and_head(r, target, refname, false);
+            }
+            let sig = r.signature()?;
+            let msg = format!("microtermi: auto-stash antes de checkout {}", refname);
+            r.stash_save(&sig, &msg, Some(git2::StashFlags::INCLUDE_UNTRACKED))?;
+            if let Err(e) = checkout_tree_and_head(r, target, refname, false) {
+                let _ = r.stash_pop(0, None);
+                return Err(e);
+            }
+            r.stash_apply(0, None).map_err(|_| GitError::StashNotReapplied)?;
+            // Si choca con la rama nueva libgit2 lo aplica igual, con marcas de conflicto: no se quita de la lista.
+            if r.index()?.has_conflicts() {
+                return Err(GitError::StashNotReapplied);
+            }
+            r.stash_drop(0)?;
+            Ok(())
+        }
+    }
+}
+
+/// Cambia a la rama indicada (git checkout). Ver `CheckoutMode` para el trato de los cambios locales.
+pub fn checkout_branch(repo: &mut GitRepo, branch_name: &str, mode: CheckoutMode) -> Result<(), GitError> {
+    let r = &mut repo.0;
+    let refname = format!("refs/heads/{}", branch_name);
+    let target = r.revparse_single(&refname).map_err(|_| GitError::NoRepo)?.id();
+    checkout_with_mode(r, target, &refname, mode)
+}
+
+/// Añade al index los archivos indicados (`git add`); un archivo borrado del disco se quita del index.
+pub fn stage(repo: &GitRepo, paths: &[&Path]) -> Result<(), GitError> {
     let r = &repo.0;
+    let workdir = r.workdir().ok_or(GitError::NoRepo)?;
     let mut index = r.index()?;
-    if paths.is_empty() {
-        index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
-    } else {
-        for p in paths {
+    for p in paths {
+        if workdir.join(p).exists() {
             index.add_path(p)?;
+        } else {
+            index.remove_path(p)?;
         }
     }
     index.write()?;
-    let tree_id = index.write_tree()?;
-    let tree = r.find_tree(tree_id)?;
-    let sig = r.signature()?;
-    let parent = r.head().ok().and_then(|h| h.peel_to_commit().ok());
-    let mut parents = Vec::new();
-    if let Some(p) = parent {
-        parents.push(p);
-    }
-    let _commit_id = r.commit(
-        Some("HEAD"),
-        &sig,
-        &sig,
-        message,
-        &tree,
-        parents.iter().collect::<Vec<_>>().as_slice(),
-    )?;
     Ok(())
 }
 
-pub fn pull(repo: &GitRepo) -> Result<String, GitError> {
+/// Saca del index los archivos indicados dejando sus cambios en el árbol de trabajo (`git restore --staged`).
+pub fn unstage(repo: &GitRepo, paths: &[&Path]) -> Result<(), GitError> {
     let r = &repo.0;
-    let branch = current_branch(repo)?.unwrap_or_else(|| "main".to_string());
-    let mut remote = r.find_remote("origin").or_else(|_| r.remote_anonymous("origin"))?;
-    remote.fetch(&[] as &[&str], None, None)?;
-    let fetch_head = r.find_reference("FETCH_HEAD")?;
-    let fetch_commit = r.reference_to_annotated_commit(&fetch_head)?;
-    let (analysis, _) = r.merge_analysis(&[&fetch_commit])?;
-    if analysis.is_up_to_date() {
-        return Ok("Already up to date.".to_string());
-    }
-    let refname = format!("refs/heads/{}", branch);
-    if analysis.is_fast_forward() {
-        let mut reference = r.find_reference(&refname)?;
-        reference.set_target(fetch_commit.id(), "Fast-Forward")?;
-        r.set_head(&refname)?;
-        let mut opts = git2::build::CheckoutBuilder::new();
-        opts.force();
-        r.checkout_head(Some(&mut opts))?;
-        Ok("Pull (fast-forward) completed.".to_string())
-    } else if analysis.is_normal() {
-        r.merge(&[&fetch_commit], None, None)?;
-        Ok("Pull (merge) completed.".to_string())
-    } else {
-        Err(git2::Error::from_str("Merge not possible").into())
+    match r.head().and_then(|h| h.peel_to_commit()) {
+        Ok(head) => r.reset_default(Some(head.as_object()), paths.iter())?,
+        Err(_) => {
+            // Repo sin commits: no hay HEAD al que volver, se quitan del index sin más.
+            let mut index = r.index()?;
+            for p in paths {
+                index.remove_path(p)?;
+            }
+            index.write()?;
+        }
     }
+    Ok(())
 }
 
-pub fn push(repo: &GitRepo) -> Result<String, GitError> {
-    let r = &repo.0;
-    let mut remote = r.find_remote("origin").map_err(|_| GitError::NoRepo)?;
-    let branch = current_branch(repo)?.unwrap_or_else(|| "main".to_string());
-    let refspec = format!("refs/heads/{}:refs/heads/{}", branch, branch);
-    remote.push(&[&refspec], None)?;
-    Ok("Push completed".to_string())
-}
-
-/// Historial de commits (log) de la rama actual.
-pub fn log(repo: &GitRepo, max_count: usize) -> Result<Vec<CommitInfo>, GitError> {
+/// Descarta los cambios sin preparar de los archivos indicados (`git restore`): vuelven a su versión
+/// del index. Los archivos no seguidos se borran.
+pub fn discard(repo: &GitRepo, paths: &[&Path]) -> Result<(), GitError> {
     let r = &repo.0;
-    let mut revwalk = r.revwalk()?;
-    revwalk.push_head()?;
-    revwalk.set_sorting(git2::Sort::TIME)?;
-    let mut out = Vec::new();
-    for oid in revwalk.take(max_count) {
-        let oid = oid?;
-        let c = r.find_commit(oid)?;
-        let msg = c.message().unwrap_or("").trim().lines().next().unwrap_or("").to_string();
-        let author = c.author().name().unwrap_or("").to_string();
-        let time = c.time();
-        let date = format_timestamp(time.seconds());
-        out.push(CommitInfo {
-            id_short: oid.to_string()[..7.min(oid.to_string().len())].to_string(),
-            message: msg,
-            author,
-            date,
-        });
+    let workdir = r.workdir().ok_or(GitError::NoRepo)?;
+    let index = r.index()?;
+    let mut opts = git2::build::CheckoutBuilder::new();
+    opts.force();
+    let mut tracked = 0;
+    for p in paths {
+        if index.get_path(p, 0).is_some() {
+            opts.path(p);
+            tracked += 1;
+        } else {
+            let full = workdir.join(p);
+            if full.is_dir() {
+                std::fs::remove_dir_all(&full).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+            } else if full.exists() {
+                std::fs::remove_file(&full).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+            }
+        }
     }
-    Ok(out)
+    if tracked > 0 {
+        r.checkout_index(None, Some(&mut opts))?;
+    }
+    Ok(())
 }
 
-fn format_timestamp(secs: i64) -> String {
-    use chrono::TimeZone;
-    chrono::Utc
-        .timestamp_opt(secs, 0)
-        .single()
-        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
-        .unwrap_or_else(|| format!("{}", secs))
+/// Opciones de commit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct CommitOptions {
+    /// Reemplaza el último commit (`git commit --amend`): conserva sus padres y su autor. Con el
+    /// mensaje vacío se mantiene el del commit original.
+    pub amend: bool,
+    /// No ejecuta los hooks `pre-commit` ni `commit-msg` (`git commit --no-verify`).
+    pub no_verify: bool,
 }
 
-/// Archivos cambiados en un commit (respecto al padre).
-pub fn commit_changes(repo: &GitRepo, commit_id_short: &str) -> Result<Vec<CommitFileChange>, GitError> {
+/// Crea un commit con lo que hay en el index. Si `paths` no está vacío, antes se preparan esos archivos.
+/// Como `git commit`, ejecuta los hooks del repo (`pre-commit`, `prepare-commit-msg`, `commit-msg` y
+/// `post-commit`) con su salida en `hooks`; si `pre-commit` o `commit-msg` fallan no se hace el commit
+/// (`HookFailed`). Del mensaje se quitan las líneas de comentario (`#`) y los espacios sobrantes.
+/// Devuelve `NothingToCommit` si el index no difiere de HEAD (salvo con `amend`), `EmptyCommitMessage`
+/// si el mensaje queda vacío y `UnresolvedConflicts` si quedan conflictos.
+pub fn commit(
+    repo: &GitRepo,
+    message: &str,
+    paths: &[&Path],
+    options: CommitOptions,
+    hooks: crate::hooks::HookSink<'_>,
+) -> Result<(), GitError> {
     let r = &repo.0;
-    let prefix = commit_id_short.trim();
-    if prefix.len() < 7 {
-        return Err(git2::Error::from_str("commit id too short").into());
-    }
-    let oid = {
-        let mut revwalk = r.revwalk()?;
-        revwalk.push_head()?;
-        revwalk
-            .find(|o| o.as_ref().map(|id| id.to_string().starts_with(prefix)).unwrap_or(false))
-            .ok_or_else(|| git2::Error::from_str("commit not found"))??
+    if !paths.is_empty() {
+        stage(repo, paths)?;
+    }
+    let mut index = r.index()?;
+    if index.has_conflicts() {
+        return Err(GitError::UnresolvedConflicts(index_conflicts(r)?));
+    }
+    let parent = r.head().ok().and_then(|h| h.peel_to_commit().ok());
+    if options.amend && parent.is_none() {
+        return Err(GitError::NothingToAmend);
+    }
+    let nothing_staged = |index: &mut git2::Index| -> Result<bool, GitError> {
+        let tree_id = index.write_tree()?;
+        let head_tree = parent.as_ref().map(|p| p.tree_id());
+        Ok(!options.amend && (head_tree == Some(tree_id) || (parent.is_none() && r.find_tree(tree_id)?.is_empty())))
     };
-    let commit = r.find_commit(oid)?;
-    let tree = commit.tree()?;
-    let parent_tree = match commit.parent(0) {
-        Ok(p) => p.tree()?,
-        Err(_) => {
-            return Ok(vec![CommitFileChange {
-                path: "(commit inicial)".to_string(),
-                status: "initial".to_string(),
-            }]);
+    if nothing_staged(&mut index)? {
+        return Err(GitError::NothingToCommit);
+    }
+    if !options.no_verify {
+        crate::hooks::run_hook(repo, "pre-commit", &[], hooks)?;
+        // El hook puede haber cambiado el index (lint-staged vuelve a preparar lo que formatea).
+        index.read(true)?;
+        if nothing_staged(&mut index)? {
+            return Err(GitError::NothingToCommit);
+        }
+    }
+    // Los hooks de mensaje reciben y pueden reescribir .git/COMMIT_EDITMSG.
+    let mut message = message.to_string();
+    if let (true, Some(head)) = (options.amend && message.trim().is_empty(), &parent) {
+        message = head.message().unwrap_or_default().to_string();
+    }
+    let msg_file = r.path().join("COMMIT_EDITMSG");
+    let msg_path = msg_file.to_string_lossy().to_string();
+    let io_failed = |e: std::io::Error| GitError::Io(msg_file.display().to_string(), e.to_string());
+    std::fs::write(&msg_file, &message).map_err(io_failed)?;
+    let source: &[&str] = if options.amend { &["commit", "HEAD"] } else { &["message"] };
+    let mut ran = crate::hooks::run_hook(repo, "prepare-commit-msg", &[&[msg_path.as_str()], source].concat(), hooks)?;
+    if !options.no_verify {
+        ran |= crate::hooks::run_hook(repo, "commit-msg", &[&msg_path], hooks)?;
+    }
+    if ran {
+        message = std::fs::read_to_string(&msg_file).map_err(io_failed)?;
+    }
+    let message = git2::message_prettify(&message, Some(b'#'))?;
+    if message.is_empty() {
+        return Err(GitError::EmptyCommitMessage);
+    }
+    let tree = r.find_tree(index.write_tree()?)?;
+    let sig = r.signature()?;
+    match parent {
+        Some(head) if options.amend => {
+            let parents: Vec<git2::Commit> = head.parents().collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            create_commit(r, &head.author(), &sig, &message, &tree, &parents, "commit (amend)")?;
+        }
+        parent => {
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            let kind = if parents.is_empty() { "commit (initial)" } else { "commit" };
+            create_commit(r, &sig, &sig, &message, &tree, &parents, kind)?;
+        }
+    }
+    // Como en git, un fallo de post-commit no deshace el commit.
+    if let Err(e) = crate::hooks::run_hook(repo, "post-commit", &[], hooks) {
+        let reason = match e {
+            GitError::HookFailed(_, reason) => reason,
+            e => e.to_string(),
+        };
+        hooks.output(&format!("post-commit failed ({}); the commit was kept", reason));
+    }
+    Ok(())
+}
+
+/// Crea el commit y mueve HEAD a él (con `kind` como prefijo del reflog). Si el repo tiene
+/// `commit.gpgsign` se firma, como hace git en cualquier commit (también merges, cherry-picks y reverts).
+fn create_commit(
+    r: &git2::Repository,
+    author: &git2::Signature<'_>,
+    committer: &git2::Signature<'_>,
+    message: &str,
+    tree: &git2::Tree<'_>,
+    parents: &[&git2::Commit<'_>],
+    kind: &str,
+) -> Result<git2::Oid, GitError> {
+    let oid = match crate::signing::signing_config(r)? {
+        Some(signing) => {
+            let buffer = r.commit_create_buffer(author, committer, message, tree, parents)?;
+            let buffer = std::str::from_utf8(&buffer).map_err(|e| GitError::Signing(e.to_string()))?;
+            let key = format!("{} <{}>", committer.name().unwrap_or_default(), committer.email().unwrap_or_default());
+            let signature = crate::signing::sign_buffer(&signing, &key, buffer)?;
+            r.commit_signed(buffer, &signature, Some("gpgsig"))?
         }
+        None => r.commit(None, author, committer, message, tree, parents)?,
     };
-    let mut out = Vec::new();
-    let diff = r.diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)?;
-    diff.foreach(
-        &mut |delta, _progress| {
-            let path = delta
-                .new_file()
-                .path()
-                .or_else(|| delta.old_file().path())
-                .map(|p| p.to_string_lossy().into_owned())
-                .unwrap_or_default();
-            let status = match delta.status() {
-                git2::Delta::Added => "added",
-                git2::Delta::Deleted => "deleted",
-                git2::Delta::Modified => "modified",
-                git2::Delta::Renamed => "renamed",
-                git2::Delta::Copied => "copied",
-                _ => "changed",
-            };
-            out.push(CommitFileChange {
-                path,
-                status: status.to_string(),
-            });
-            true
-        },
-        None,
-        None,
-        None,
-    )?;
-    Ok(out)
+    move_head(r, oid, &format!("{}: {}", kind, message.lines().next().unwrap_or_default()))?;
+    Ok(oid)
 }
 
-/// Lista los nombres de las ramas remotas (origin/xxx → xxx). Ejecutar fetch antes para tener refs actualizados.
-pub fn branches_remote(repo: &GitRepo) -> Result<Vec<String>, GitError> {
-    let r = &repo.0;
-    let mut names = Vec::new();
-    for name in r.branches(Some(git2::BranchType::Remote))? {
-        let (branch, _) = name?;
-        if let Ok(Some(s)) = branch.name() {
-            let s = s.to_string();
-            if let Some(short) = s.split('/').nth(1) {
-                if !short.is_empty() && !names.contains(&short.to_string()) {
-                    names.push(short.to_string());
-                }
+/// Mueve la rama actual (o HEAD, si está separado) a `oid`, como hace `git commit`.
+fn move_head(r: &git2::Repository, oid: git2::Oid, reflog: &str) -> Result<(), GitError> {
+    match r.find_reference("HEAD")?.symbolic_target() {
+        Some(branch) => {
+            r.reference(branch, oid, true, reflog)?;
+        }
+        None => r.set_head_detached(oid)?,
+    }
+    Ok(())
+}
+
+/// Resultado de un pull.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "paths", rename_all = "snake_case")]
+pub enum PullOutcome {
+    UpToDate,
+    FastForward,
+    /// Merge sin conflictos: se creó el commit de merge.
+    Merged,
+    /// Rebase completado: los commits locales quedaron sobre origin/<rama>.
+    Rebased,
+    /// El merge o rebase quedó a medias con estos archivos en conflicto
+    /// (`merge_continue`/`merge_abort` o `rebase_continue`/`rebase_skip`/`rebase_abort`).
+    Conflicts(Vec<String>),
+}
+
+impl std::fmt::Display for PullOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PullOutcome::UpToDate => write!(f, "Already up to date."),
+            PullOutcome::FastForward => write!(f, "Pull (fast-forward) completed."),
+            PullOutcome::Merged => write!(f, "Pull (merge) completed."),
+            PullOutcome::Rebased => write!(f, "Pull (rebase) completed."),
+            PullOutcome::Conflicts(paths) => {
+                write!(f, "Conflicts in {} file(s): {}", paths.len(), paths.join(", "))
             }
         }
     }
-    names.sort();
-    Ok(names)
 }
 
-/// Actualiza las referencias remotas (fetch origin).
-pub fn fetch(repo: &GitRepo) -> Result<(), GitError> {
-    let r = &repo.0;
-    let mut remote = r.find_remote("origin").map_err(|_| GitError::NoRepo)?;
-    remote.fetch(&[] as &[&str], None, None)?;
-    Ok(())
+/// Rutas con conflicto en el index (merge/rebase a medias).
+pub fn conflicted_paths(repo: &GitRepo) -> Result<Vec<String>, GitError> {
+    index_conflicts(&repo.0)
 }
 
-/// Cambia a una rama remota: si existe local la hace checkout; si no, crea la rama local desde origin/name y hace checkout.
-pub fn checkout_remote_branch(repo: &GitRepo, branch_name: &str) -> Result<(), GitError> {
-    let r = &repo.0;
-    let local_ref = format!("refs/heads/{}", branch_name);
-    let remote_ref = format!("refs/remotes/origin/{}", branch_name);
-    if r.find_reference(&local_ref).is_ok() {
-        return checkout_branch(repo, branch_name);
+fn index_conflicts(r: &git2::Repository) -> Result<Vec<String>, GitError> {
+    let index = r.index()?;
+    let mut out = Vec::new();
+    if !index.has_conflicts() {
+        return Ok(out);
     }
-    let remote_ref_obj = r.find_reference(&remote_ref)?;
-    let commit = remote_ref_obj.peel_to_commit()?;
-    let _ = r.branch(branch_name, &commit, false)?;
-    r.set_head(&local_ref)?;
-    let mut opts = git2::build::CheckoutBuilder::new();
-    opts.force();
-    r.checkout_head(Some(&mut opts))?;
-    Ok(())
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            let path = String::from_utf8_lossy(&entry.path).into_owned();
+            if !out.contains(&path) {
+                out.push(path);
+            }
+        }
+    }
+    Ok(out)
 }
 
-/// Guarda los cambios actuales en el stash (git stash).
-pub fn stash(repo: &mut GitRepo) -> Result<(), GitError> {
-    let r = &mut repo.0;
+/// True si hay un merge en curso (MERGE_HEAD presente).
+pub fn merge_in_progress(repo: &GitRepo) -> bool {
+    repo.0.state() == git2::RepositoryState::Merge
+}
+
+/// Crea el commit de merge con HEAD y los MERGE_HEAD como padres y limpia el estado de merge.
+fn commit_merge(r: &git2::Repository, message: &str) -> Result<(), GitError> {
+    let mut index = r.index()?;
+    let tree = r.find_tree(index.write_tree()?)?;
     let sig = r.signature()?;
-    r.stash_save(&sig, "", None)?;
+    let head = r.head()?.peel_to_commit()?;
+    let mut parents = vec![head];
+    let merge_head = std::fs::read_to_string(r.path().join("MERGE_HEAD")).unwrap_or_default();
+    for line in merge_head.lines().filter(|l| !l.trim().is_empty()) {
+        parents.push(r.find_commit(git2::Oid::from_str(line.trim())?)?);
+    }
+    create_commit(r, &sig, &sig, message, &tree, &parents.iter().collect::<Vec<_>>(), "commit (merge)")?;
+    r.cleanup_state()?;
     Ok(())
 }
 
-/// Aplica el último stash y lo elimina de la lista (git stash pop).
-pub fn stash_pop(repo: &mut GitRepo) -> Result<(), GitError> {
-    let r = &mut repo.0;
-    r.stash_apply(0, None)?;
-    r.stash_drop(0)?;
+/// Fetch de origin y commit de la rama remota correspondiente a la rama actual (o FETCH_HEAD).
+fn fetch_upstream<'r>(
+    repo: &'r GitRepo,
+    sink: ProgressSink<'_>,
+) -> Result<(String, git2::AnnotatedCommit<'r>), GitError> {
+    let r = &repo.0;
+    let branch = current_branch(repo)?.unwrap_or_else(|| "main".to_string());
+    let mut remote = r.find_remote("origin").or_else(|_| r.remote_anonymous("origin"))?;
+    remote
+        .fetch(&[] as &[&str], Some(&mut fetch_options(r.config()?, sink)), None)
+        .map_err(|e| sink.error(e))?;
+    let upstream_ref = format!("refs/remotes/origin/{}", branch);
+    let fetch_ref = r
+        .find_reference(&upstream_ref)
+        .or_else(|_| r.find_reference("FETCH_HEAD"))?;
+    let fetch_commit = r.reference_to_annotated_commit(&fetch_ref)?;
+    Ok((branch, fetch_commit))
+}
+
+/// Avanza la rama a `target` con checkout seguro (no pisa cambios locales).
+fn fast_forward(r: &git2::Repository, branch: &str, target: git2::Oid) -> Result<(), GitError> {
+    let refname = format!("refs/heads/{}", branch);
+    checkout_tree_and_head(r, target, &refname, false)?;
+    let mut reference = r.find_reference(&refname)?;
+    reference.set_target(target, "Fast-Forward")?;
     Ok(())
 }
+
+pub fn pull(repo: &GitRepo, sink: ProgressSink<'_>) -> Result<PullOutcome, GitError> {
+    let r = &repo.0;
+    let (branch, fetch_commit) = fetch_upstream(repo, sink)?;
+    let (analysis, _) = r.merge_analysis(&[&fetch_commit])?;
+    if analysis.is_up_to_date() {
+        return Ok(PullOutcome::UpToDate);
+    }
+    if analysis.is_fast_forward() {
+        fast_forward(r, &branch, fetch_commit.id())?;
+        Ok(PullOutcome::FastForward)
+    } else if analysis.is_normal() {
+        let message = format!("Merge remote-tracking branch 'origin/{}' into {}", branch, branch);
+        r.merge(&[&fetch_commit], None, None)?;
+        let conflicts = conflicted_paths(repo)?;
+        if !conflicts.is_empty() {
+            // `merge_continue` usa MERGE_MSG: el mismo mensaje que un merge sin conflictos.
+            let msg_file = r.path().join("MERGE_MSG");
+            std::fs::write(&msg_file, format!("{}\n", message))
+                .map_err(|e| GitError::Io(msg_file.display().to_string(), e.to_string()))?;
+            return Ok(PullOutcome::Conflicts(conflicts));
+        }
+        commit_merge(r, &message)?;
+        Ok(PullOutcome::Merged)
+    } else {
+        Err(git2::Error::from_str("Merge not possible").into())
+    }
+}
+
+/// Termina un merge a medias: añade al index los archivos en conflicto ya resueltos (sin marcas
+/// `<<<<<<<`) y crea el commit de merge. Si queda alguno sin resolver devuelve `UnresolvedConflicts`.
+pub fn merge_continue(repo: &GitRepo) -> Result<(), GitError> {
+    let r = &repo.0;
+    if r.state() != git2::RepositoryState::Merge {
+        return Err(git2::Error::from_str("no merge in progress").into());
+    }
+    stage_resolved_conflicts(r)?;
+    let message = prepared_message(r).unwrap_or_else(|_| "Merge".to_string());
+    commit_merge(r, &message)
+}
+
+/// Añade al index los archivos en conflicto que ya no tienen marcas `<<<<<<<`. Si queda alguno
+/// sin resolver devuelve `UnresolvedConflicts`.
+fn stage_resolved_conflicts(r: &git2::Repository) -> Result<(), GitError> {
+    let workdir = r.workdir().ok_or(GitError::NoRepo)?.to_path_buf();
+    let mut index = r.index()?;
+    let mut unresolved = Vec::new();
+    for path in index_conflicts(r)? {
+        let full = workdir.join(&path);
+        if full.exists() {
+            let content = std::fs::read(&full).unwrap_or_default();
+            if String::from_utf8_lossy(&content).contains("<<<<<<< ") {
+                unresolved.push(path);
+                continue;
+            }
+            index.add_path(Path::new(&path))?;
+        } else {
+            index.remove_path(Path::new(&path))?;
+        }
+    }
+    index.write()?;
+    if !unresolved.is_empty() {
+        return Err(GitError::UnresolvedConflicts(unresolved));
+    }
+    Ok(())
+}
+
+/// True si hay un rebase en curso (pausado por conflictos).
+pub fn rebase_in_progress(repo: &GitRepo) -> bool {
+    matches!(
+        repo.0.state(),
+        git2::RepositoryState::Rebase
+            | git2::RepositoryState::RebaseInteractive
+            | git2::RepositoryState::RebaseMerge
+    )
+}
+
+/// Commit del paso actual del rebase. Un paso que queda vacío (ya estaba en upstream) se omite.
+fn commit_rebase_step(r: &git2::Repository, rebase: &mut git2::Rebase, sig: &git2::Signature) -> Result<(), GitError> {
+    if crate::signing::signing_config(r)?.is_some() {
+        return commit_signed_rebase_step(r, rebase, sig);
+    }
+    match rebase.commit(None, sig, None) {
+        Ok(_) => Ok(()),
+        Err(e) if e.code() == git2::ErrorCode::Applied => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// libgit2 no sabe firmar los commits del rebase: con `commit.gpgsign` el paso se hace como lo haría
+/// `rebase.commit` (autor y mensaje del original, HEAD separado) pero pasando por `create_commit`.
+fn commit_signed_rebase_step(r: &git2::Repository, rebase: &mut git2::Rebase, sig: &git2::Signature) -> Result<(), GitError> {
+    let original = rebase
+        .operation_current()
+        .and_then(|i| rebase.nth(i))
+        .map(|op| op.id())
+        .ok_or_else(|| git2::Error::from_str("no rebase step in progress"))?;
+    let original = r.find_commit(original)?;
+    let mut index = r.index()?;
+    if index.has_conflicts() {
+        return Err(GitError::UnresolvedConflicts(index_conflicts(r)?));
+    }
+    let tree_id = index.write_tree()?;
+    let head = r.head()?.peel_to_commit()?;
+    if head.tree_id() == tree_id {
+        return Ok(());
+    }
+    let tree = r.find_tree(tree_id)?;
+    let message = original.message().unwrap_or_default();
+    let oid = create_commit(r, &original.author(), sig, message, &tree, &[&head], "rebase (pick)")?;
+    // Como libgit2, se anota el par original → nuevo para que `finish` copie las notas.
+    let path = r.path().join("rebase-merge").join("rewritten");
+    let io_failed = |e: std::io::Error| GitError::Io(path.display().to_string(), e.to_string());
+    let mut rewritten = std::fs::OpenOptions::new().create(true).append(true).open(&path).map_err(io_failed)?;
+    std::io::Write::write_all(&mut rewritten, format!("{} {}\n", original.id(), oid).as_bytes()).map_err(io_failed)?;
+    Ok(())
+}
+
+/// Aplica los pasos pendientes del rebase informando `GitProgress::Rebase` antes de cada commit.
+/// Si un paso deja conflictos, el rebase queda pausado y se devuelven las rutas. Si se cancela,
+/// se detiene entre dos commits y el rebase queda pausado (se puede continuar o abortar).
+fn run_rebase(
+    r: &git2::Repository,
+    rebase: &mut git2::Rebase,
+    sink: ProgressSink<'_>,
+) -> Result<PullOutcome, GitError> {
+    let sig = r.signature()?;
+    let total = rebase.len();
+    loop {
+        if sink.is_cancelled() {
+            return Err(GitError::Cancelled);
+        }
+        let Some(op) = rebase.next() else { break };
+        op?;
+        let current = rebase.operation_current().map(|i| i + 1).unwrap_or(total);
+        sink.report(GitProgress::Rebase { current, total });
+        let conflicts = index_conflicts(r)?;
+        if !conflicts.is_empty() {
+            return Ok(PullOutcome::Conflicts(conflicts));
+        }
+        commit_rebase_step(r, rebase, &sig)?;
+    }
+    rebase.finish(Some(&sig))?;
+    Ok(PullOutcome::Rebased)
+}
+
+/// Pull con rebase (git pull --rebase): reaplica los commits locales sobre origin/<rama>.
+pub fn pull_rebase(repo: &GitRepo, sink: ProgressSink<'_>) -> Result<PullOutcome, GitError> {
+    let r = &repo.0;
+    let (branch, upstream) = fetch_upstream(repo, sink)?;
+    let (analysis, _) = r.merge_analysis(&[&upstream])?;
+    if analysis.is_up_to_date() {
+        return Ok(PullOutcome::UpToDate);
+    }
+    if analysis.is_fast_forward() {
+        fast_forward(r, &branch, upstream.id())?;
+        return Ok(PullOutcome::FastForward);
+    }
+    let head = r.reference_to_annotated_commit(&r.head()?)?;
+    let mut rebase = r.rebase(Some(&head), Some(&upstream), None, None)?;
+    run_rebase(r, &mut rebase, sink)
+}
+
+/// Continúa un rebase pausado: añade los archivos resueltos, hace commit del paso y sigue.
+pub fn rebase_continue(repo: &GitRepo, sink: ProgressSink<'_>) -> Result<PullOutcome, GitError> {
+    let r = &repo.0;
+    let mut rebase = r.open_rebase(None)?;
+    stage_resolved_conflicts(r)?;
+    commit_rebase_step(r, &mut rebase, &r.signature()?)?;
+    run_rebase(r, &mut rebase, sink)
+}
+
+/// Salta el commit actual del rebase (descarta sus cambios) y sigue con el resto.
+pub fn rebase_skip(repo: &GitRepo, sink: ProgressSink<'_>) -> Result<PullOutcome, GitError> {
+    let r = &repo.0;
+    let mut rebase = r.open_rebase(None)?;
+    // No usar reset --hard: libgit2 limpia también el estado del rebase.
+    let head_tree = r.head()?.peel_to_tree()?;
+    let mut index = r.index()?;
+    index.read_tree(&head_tree)?;
+    index.write()?;
+    let mut opts = git2::build::CheckoutBuilder::new();
+    opts.force();
+    r.checkout_head(Some(&mut opts))?;
+    run_rebase(r, &mut rebase, sink)
+}
+
+/// Aborta el rebase y deja la rama como estaba antes del pull.
+pub fn rebase_abort(repo: &GitRepo) -> Result<(), GitError> {
+    let mut rebase = repo.0.open_rebase(None)?;
+    rebase.abort()?;
+    Ok(())
+}
+
+/// Aborta un merge a medias como `git merge --abort`: vuelve a HEAD solo los archivos que tocó el merge (los
+/// que difieren entre HEAD y el índice, más los conflictos) y limpia el estado. Los cambios locales en otros
+/// archivos se conservan; si un archivo del merge tiene además cambios sin añadir, no se aborta nada.
+pub fn merge_abort(repo: &GitRepo) -> Result<(), GitError> {
+    let r = &repo.0;
+    let head = r.head()?.peel_to_commit()?;
+    let head_tree = head.tree()?;
+    let index = r.index()?;
+    let conflicted = index_conflicts(r)?;
+    let mut paths = conflicted.clone();
+    for delta in r.diff_tree_to_index(Some(&head_tree), Some(&index), None)?.deltas() {
+        for file in [delta.old_file(), delta.new_file()] {
+            if let Some(path) = file.path().and_then(|p| p.to_str()) {
+                if !paths.iter().any(|p| p == path) {
+                    paths.push(path.to_string());
+                }
+            }
+        }
+    }
+    if !paths.is_empty() {
+        let mut opts = git2::DiffOptions::new();
+        opts.disable_pathspec_match(true);
+        for path in &paths {
+            opts.pathspec(path);
+        }
+        let dirty: Vec<String> = r
+            .diff_index_to_workdir(Some(&index), Some(&mut opts))?
+            .deltas()
+            .filter_map(|d| d.new_file().path().and_then(|p| p.to_str()).map(String::from))
+            .filter(|p| !conflicted.contains(p))
+            .collect();
+        if !dirty.is_empty() {
+            return Err(GitError::AbortWouldDiscard(dirty));
+        }
+        let mut checkout = git2::build::CheckoutBuilder::new();
+        checkout.force();
+        for path in &paths {
+            checkout.path(path);
+        }
+        r.checkout_tree(head_tree.as_object(), Some(&mut checkout))?;
+        r.reset_default(Some(head.as_object()), paths.iter())?;
+    }
+    r.cleanup_state()?;
+    Ok(())
+}
+
+/// Resultado de un cherry-pick o revert.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum PickOutcome {
+    /// Se creó el commit (id corto).
+    Committed(String),
+    /// Quedó a medias con estos archivos en conflicto (`pick_continue`/`pick_abort`).
+    Conflicts(Vec<String>),
+}
+
+impl std::fmt::Display for PickOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PickOutcome::Committed(id) => write!(f, "Created commit {}.", id),
+            PickOutcome::Conflicts(paths) => {
+                write!(f, "Conflicts in {} file(s): {}", paths.len(), paths.join(", "))
+            }
+        }
+    }
+}
+
+/// True si hay un cherry-pick o un revert pausado por conflictos.
+pub fn pick_in_progress(repo: &GitRepo) -> bool {
+    matches!(repo.0.state(), git2::RepositoryState::CherryPick | git2::RepositoryState::Revert)
+}
+
+fn short_id(oid: git2::Oid) -> String {
+    oid.to_string()[..7].to_string()
+}
+
+/// Commit con lo que hay en el index tras un cherry-pick o revert y limpia el estado. `author` es el
+/// autor original en un cherry-pick; en un revert, el usuario.
+fn commit_pick(r: &git2::Repository, message: &str, author: &git2::Signature<'_>) -> Result<String, GitError> {
+    let mut index = r.index()?;
+    let tree_id = index.write_tree()?;
+    let head = r.head()?.peel_to_commit()?;
+    if head.tree_id() == tree_id {
+        r.cleanup_state()?;
+        return Err(GitError::NothingToCommit);
+    }
+    let tree = r.find_tree(tree_id)?;
+    let committer = r.signature()?;
+    let kind = if r.state() == git2::RepositoryState::Revert { "revert" } else { "cherry-pick" };
+    let id = create_commit(r, author, &committer, message, &tree, &[&head], kind)?;
+    r.cleanup_state()?;
+    Ok(short_id(id))
+}
+
+/// Mensaje preparado para el commit en curso (MERGE_MSG) sin las líneas de comentario que añaden libgit2
+/// y git (`#Conflicts:`...), como las quita `git commit`.
+fn prepared_message(r: &git2::Repository) -> Result<String, GitError> {
+    let message = r.message()?;
+    Ok(git2::message_prettify(message, Some(b'#'))?.trim().to_string())
+}
+
+fn check_no_operation(r: &git2::Repository) -> Result<(), GitError> {
+    if r.state() != git2::RepositoryState::Clean {
+        return Err(git2::Error::from_str("another merge, rebase, cherry-pick or revert is in progress").into());
+    }
+    Ok(())
+}
+
+/// Padre base de un commit de merge para cherry-pick/revert: el primero (`-m 1`); 0 si no es merge.
+fn mainline(commit: &git2::Commit<'_>) -> u32 {
+    if commit.parent_count() > 1 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Aplica los cambios de `rev` sobre HEAD y crea un commit con su mensaje y autor. Con conflictos el
+/// cherry-pick queda a medias (`PickOutcome::Conflicts`).
+pub fn cherry_pick(repo: &GitRepo, rev: &str) -> Result<PickOutcome, GitError> {
+    let r = &repo.0;
+    check_no_operation(r)?;
+    let commit = find_commit(r, rev)?;
+    let mut options = git2::CherrypickOptions::new();
+    options.mainline(mainline(&commit));
+    r.cherrypick(&commit, Some(&mut options))?;
+    let conflicts = index_conflicts(r)?;
+    if !conflicts.is_empty() {
+        return Ok(PickOutcome::Conflicts(conflicts));
+    }
+    let message = commit.message().unwrap_or("").to_string();
+    let author = commit.author();
+    commit_pick(r, &message, &author).map(PickOutcome::Committed)
+}
+
+/// Deshace los cambios de `rev` con un commit nuevo ("Revert ..."). Con conflictos el revert queda a
+/// medias (`PickOutcome::Conflicts`).
+pub fn revert(repo: &GitRepo, rev: &str) -> Result<PickOutcome, GitError> {
+    let r = &repo.0;
+    check_no_operation(r)?;
+    let commit = find_commit(r, rev)?;
+    let mut options = git2::RevertOptions::new();
+    options.mainline(mainline(&commit));
+    r.revert(&commit, Some(&mut options))?;
+    let conflicts = index_conflicts(r)?;
+    if !conflicts.is_empty() {
+        return Ok(PickOutcome::Conflicts(conflicts));
+    }
+    let message = prepared_message(r)?;
+    commit_pick(r, &message, &r.signature()?).map(PickOutcome::Committed)
+}
+
+/// Termina un cherry-pick o revert a medias: añade los archivos resueltos y crea el commit. Devuelve
+/// su id corto o `UnresolvedConflicts` si quedan conflictos.
+pub fn pick_continue(repo: &GitRepo) -> Result<String, GitError> {
+    let r = &repo.0;
+    let author = match r.state() {
+        git2::RepositoryState::CherryPick => {
+            let id = std::fs::read_to_string(r.path().join("CHERRY_PICK_HEAD")).unwrap_or_default();
+            r.find_commit(git2::Oid::from_str(id.trim())?)?.author().to_owned()
+        }
+        git2::RepositoryState::Revert => r.signature()?,
+        _ => return Err(git2::Error::from_str("no cherry-pick or revert in progress").into()),
+    };
+    stage_resolved_conflicts(r)?;
+    let message = prepared_message(r)?;
+    commit_pick(r, &message, &author)
+}
+
+/// Aborta un cherry-pick o revert a medias: como `merge_abort`, solo vuelve a HEAD los archivos afectados.
+pub fn pick_abort(repo: &GitRepo) -> Result<(), GitError> {
+    merge_abort(repo)
+}
+
+/// Opciones de push (equivalen a `--set-upstream` y `--force-with-lease`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct PushOptions {
+    /// Si la rama no tiene upstream, publicarla en origin con el mismo nombre y configurarlo.
+    pub set_upstream: bool,
+    /// Forzar el push solo si la rama remota sigue donde la vimos en el último fetch.
+    pub force_with_lease: bool,
+}
+
+/// Resultado de un push aceptado por el servidor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PushOutcome {
+    pub branch: String,
+    pub remote: String,
+    /// Ref remota actualizada, p. ej. `refs/heads/main`.
+    pub remote_ref: String,
+    /// True si este push configuró el upstream de la rama.
+    pub upstream_set: bool,
+}
+
+impl std::fmt::Display for PushOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let target = self.remote_ref.strip_prefix("refs/heads/").unwrap_or(&self.remote_ref);
+        write!(f, "Pushed {} to {}/{}", self.branch, self.remote, target)?;
+        if self.upstream_set {
+            write!(f, " (upstream set)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Push de la rama actual a su upstream (`branch.<rama>.remote` / `branch.<rama>.merge`).
+/// Sin upstream devuelve `NoUpstream` salvo con `set_upstream`. Las refs rechazadas por el servidor
+/// (non-fast-forward, hooks, ramas protegidas…) se devuelven en `PushRejected`.
+pub fn push(repo: &GitRepo, options: PushOptions, sink: ProgressSink<'_>) -> Result<PushOutcome, GitError> {
+    let r = &repo.0;
+    if r.head_detached()? {
+        return Err(git2::Error::from_str("cannot push from a detached HEAD").into());
+    }
+    let branch = current_branch(repo)?.unwrap_or_else(|| "main".to_string());
+    let local_ref = format!("refs/heads/{}", branch);
+    let upstream_remote = r.branch_upstream_remote(&local_ref).ok().and_then(|b| b.as_str().map(String::from));
+    let upstream_merge = r.config()?.get_string(&format!("branch.{}.merge", branch)).ok();
+    let (remote_name, remote_ref, upstream_set) = match (upstream_remote, upstream_merge) {
+        (Some(remote), Some(merge)) => (remote, merge, false),
+        _ if options.set_upstream => ("origin".to_string(), local_ref.clone(), true),
+        _ => return Err(GitError::NoUpstream(branch)),
+    };
+    let mut remote = r.find_remote(&remote_name)?;
+
+    // Para el lease: dónde estaba la rama remota en el último fetch (ref de seguimiento).
+    let tracking_ref = r
+        .branch_upstream_name(&local_ref)
+        .ok()
+        .and_then(|b| b.as_str().map(String::from))
+        .unwrap_or_else(|| {
+            let short = remote_ref.strip_prefix("refs/heads/").unwrap_or(&remote_ref);
+            format!("refs/remotes/{}/{}", remote_name, short)
+        });
+    let expected = r.refname_to_id(&tracking_ref).unwrap_or_else(|_| git2::Oid::zero());
+
+    let rejected = std::cell::RefCell::new(Vec::new());
+    let mut callbacks = remote_callbacks(r.config()?, sink);
+    callbacks.push_update_reference(|refname, status| {
+        if let Some(msg) = status {
+            rejected.borrow_mut().push(format!("{} ({})", refname, msg));
+        }
+        Ok(())
+    });
+    // libgit2 no conserva el mensaje de error de este callback, así que el lease roto se anota aparte.
+    let stale_lease = std::cell::Cell::new(false);
+    callbacks.push_negotiation(|updates| {
+        if sink.is_cancelled() {
+            return Err(git2::Error::from_str("push cancelled"));
+        }
+        if options.force_with_lease
+            && updates
+                .iter()
+                .any(|u| u.dst_refname() == Some(remote_ref.as_str()) && u.src() != expected)
+        {
+            stale_lease.set(true);
+            return Err(git2::Error::from_str("stale info"));
+        }
+        Ok(())
+    });
+    let force = if options.force_with_lease { "+" } else { "" };
+    let refspec = format!("{}{}:{}", force, local_ref, remote_ref);
+    let mut opts = git2::PushOptions::new();
+    opts.remote_callbacks(callbacks);
+    let pushed = remote.push(&[&refspec], Some(&mut opts));
+    drop(opts);
+    if stale_lease.get() {
+        return Err(GitError::PushRejected(vec![format!("{} (stale info)", remote_ref)]));
+    }
+    match pushed {
+        Err(e) if e.code() == git2::ErrorCode::NotFastForward => {
+            return Err(GitError::PushRejected(vec![format!("{} (non-fast-forward)", remote_ref)]));
+        }
+        Err(e) => return Err(sink.error(e)),
+        Ok(()) => {}
+    }
+    let rejected = rejected.into_inner();
+    if !rejected.is_empty() {
+        return Err(GitError::PushRejected(rejected));
+    }
+    if upstream_set {
+        let mut config = r.config()?;
+        config.set_str(&format!("branch.{}.remote", branch), &remote_name)?;
+        config.set_str(&format!("branch.{}.merge", branch), &remote_ref)?;
+    }
+    Ok(PushOutcome { branch, remote: remote_name, remote_ref, upstream_set })
+}
+
+/// Historial de commits (log) de la rama actual.
+pub fn log(repo: &GitRepo, max_count: usize) -> Result<Vec<CommitInfo>, GitError> {
+    let r = &repo.0;
+    let mut revwalk = r.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+    let mut out = Vec::new();
+    for oid in revwalk.take(max_count) {
+        let oid = oid?;
+        let c = r.find_commit(oid)?;
+        let msg = c.message().unwrap_or("").trim().lines().next().unwrap_or("").to_string();
+        let author = c.author().name().unwrap_or("").to_string();
+        let time = c.time();
+        let date = format_timestamp(time.seconds());
+        out.push(CommitInfo {
+            id_short: oid.to_string()[..7.min(oid.to_string().len())].to_string(),
+            message: msg,
+            author,
+            date,
+        });
+    }
+    Ok(out)
+}
+
+pub(crate) fn format_timestamp(secs: i64) -> String {
+    use chrono::TimeZone;
+    chrono::Utc
+        .timestamp_opt(secs, 0)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| format!("{}", secs))
+}
+
+/// Commit de `rev`: id completo o prefijo no ambiguo, rama, tag o cualquier revisión (`HEAD~2`...).
+pub(crate) fn find_commit<'r>(r: &'r git2::Repository, rev: &str) -> Result<git2::Commit<'r>, GitError> {
+    let rev = rev.trim();
+    r.revparse_single(rev)
+        .and_then(|o| o.peel_to_commit())
+        .map_err(|_| GitError::CommitNotFound(rev.to_string()))
+}
+
+/// Archivos cambiados en un commit respecto a su primer padre (todos si es el commit inicial), con
+/// líneas añadidas y quitadas.
+pub fn commit_changes(repo: &GitRepo, rev: &str) -> Result<Vec<CommitFileChange>, GitError> {
+    let r = &repo.0;
+    let commit = find_commit(r, rev)?;
+    let tree = commit.tree()?;
+    let parent_tree = commit.parent(0).ok().map(|p| p.tree()).transpose()?;
+    let mut diff = r.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+    diff.find_similar(None)?;
+    let mut out = Vec::new();
+    for (i, delta) in diff.deltas().enumerate() {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let status = match delta.status() {
+            git2::Delta::Added => "added",
+            git2::Delta::Deleted => "deleted",
+            git2::Delta::Modified => "modified",
+            git2::Delta::Renamed => "renamed",
+            git2::Delta::Copied => "copied",
+            _ => "changed",
+        };
+        let old_path = match delta.status() {
+            git2::Delta::Renamed | git2::Delta::Copied => {
+                delta.old_file().path().map(|p| p.to_string_lossy().into_owned())
+            }
+            _ => None,
+        };
+        let (insertions, deletions) = match git2::Patch::from_diff(&diff, i)? {
+            Some(patch) => {
+                let (_, added, removed) = patch.line_stats()?;
+                (added, removed)
+            }
+            None => (0, 0),
+        };
+        out.push(CommitFileChange {
+            path,
+            status: status.to_string(),
+            old_path,
+            insertions,
+            deletions,
+            binary: delta.flags().is_binary(),
+        });
+    }
+    Ok(out)
+}
+
+/// Tramo de líneas seguidas que vienen del mismo commit (`git blame`).
+#[derive(Debug, Clone, Serialize)]
+pub struct BlameHunk {
+    /// Primera línea del tramo (desde 1).
+    pub start_line: usize,
+    pub line_count: usize,
+    /// None si las líneas todavía no están en ningún commit (cambios locales).
+    pub commit_id: Option<String>,
+    pub id_short: String,
+    /// Primera línea del mensaje del commit.
+    pub summary: String,
+    pub author: String,
+    pub email: String,
+    pub date: String,
+    pub time: i64,
+    /// Ruta del archivo en ese commit, si era otra (renombrado después).
+    pub orig_path: Option<String>,
+    /// Texto de las líneas del tramo.
+    pub lines: Vec<String>,
+}
+
+/// Quién cambió por última vez cada línea de `path` (relativa a la raíz del repo). Con `rev` se mira
+/// el archivo en esa revisión; sin ella, el archivo del árbol de trabajo, y las líneas cambiadas y aún
+/// sin commit salen con `commit_id: None`.
+pub fn blame(repo: &GitRepo, path: &Path, rev: Option<&str>) -> Result<Vec<BlameHunk>, GitError> {
+    let r = &repo.0;
+    let rev = rev.map(str::trim).filter(|s| !s.is_empty());
+    let mut options = git2::BlameOptions::new();
+    let content = match rev {
+        Some(rev) => {
+            let commit = find_commit(r, rev)?;
+            options.newest_commit(commit.id());
+            let blob = commit.tree()?.get_path(path)?.to_object(r)?.peel_to_blob()?;
+            blob.content().to_vec()
+        }
+        None => {
+            let workdir = r.workdir().ok_or(GitError::NoRepo)?;
+            std::fs::read(workdir.join(path)).map_err(|e| git2::Error::from_str(&e.to_string()))?
+        }
+    };
+    let committed = r.blame_file(path, Some(&mut options))?;
+    let working;
+    let blame = if rev.is_some() {
+        &committed
+    } else {
+        working = committed.blame_buffer(&content)?;
+        &working
+    };
+    let text = String::from_utf8_lossy(&content);
+    let lines: Vec<&str> = text.lines().collect();
+    let mut commits: std::collections::HashMap<git2::Oid, String> = std::collections::HashMap::new();
+    let mut out = Vec::new();
+    for hunk in blame.iter() {
+        let start = hunk.final_start_line();
+        let count = hunk.lines_in_hunk();
+        let oid = hunk.final_commit_id();
+        let orig_path = hunk.path().filter(|p| *p != path).map(|p| p.to_string_lossy().into_owned());
+        let mut entry = BlameHunk {
+            start_line: start,
+            line_count: count,
+            commit_id: None,
+            id_short: String::new(),
+            summary: String::new(),
+            author: String::new(),
+            email: String::new(),
+            date: String::new(),
+            time: 0,
+            orig_path,
+            lines: lines
+                .iter()
+                .skip(start.saturating_sub(1))
+                .take(count)
+                .map(|l| l.to_string())
+                .collect(),
+        };
+        // Las líneas sin commit no tienen firma (libgit2 devuelve un puntero nulo).
+        if !oid.is_zero() {
+            let sig = hunk.final_signature();
+            entry.commit_id = Some(oid.to_string());
+            entry.id_short = short_id(oid);
+            entry.summary = commits
+                .entry(oid)
+                .or_insert_with(|| {
+                    r.find_commit(oid)
+                        .ok()
+                        .and_then(|c| c.summary().map(String::from))
+                        .unwrap_or_default()
+                })
+                .clone();
+            entry.author = sig.name().unwrap_or("").to_string();
+            entry.email = sig.email().unwrap_or("").to_string();
+            entry.time = sig.when().seconds();
+            entry.date = format_timestamp(entry.time);
+        }
+        out.push(entry);
+    }
+    Ok(out)
+}
+
+/// Archivos versionados: los del index o, con `rev`, los del árbol de esa revisión. Ordenados.
+pub fn tracked_files(repo: &GitRepo, rev: Option<&str>) -> Result<Vec<String>, GitError> {
+    let r = &repo.0;
+    let mut out = Vec::new();
+    match rev.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(rev) => {
+            let tree = find_commit(r, rev)?.tree()?;
+            tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+                if entry.kind() == Some(git2::ObjectType::Blob) {
+                    out.push(format!("{}{}", dir, entry.name().unwrap_or("")));
+                }
+                git2::TreeWalkResult::Ok
+            })?;
+        }
+        None => {
+            let index = r.index()?;
+            out.extend(index.iter().map(|e| String::from_utf8_lossy(&e.path).into_owned()));
+            out.dedup();
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// Lista los nombres de las ramas remotas (origin/xxx → xxx). Ejecutar fetch antes para tener refs actualizados.
+pub fn branches_remote(repo: &GitRepo) -> Result<Vec<String>, GitError> {
+    let r = &repo.0;
+    let mut names = Vec::new();
+    for name in r.branches(Some(git2::BranchType::Remote))? {
+        let (branch, _) = name?;
+        if let Ok(Some(s)) = branch.name() {
+            let s = s.to_string();
+            if let Some(short) = s.split('/').nth(1) {
+                if !short.is_empty() && !names.contains(&short.to_string()) {
+                    names.push(short.to_string());
+                }
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Actualiza las referencias remotas (fetch origin).
+pub fn fetch(repo: &GitRepo, sink: ProgressSink<'_>) -> Result<(), GitError> {
+    let r = &repo.0;
+    let mut remote = r.find_remote("origin").map_err(|_| GitError::NoRepo)?;
+    remote
+        .fetch(&[] as &[&str], Some(&mut fetch_options(r.config()?, sink)), None)
+        .map_err(|e| sink.error(e))?;
+    Ok(())
+}
+
+/// Cambia a una rama remota: si existe local la hace checkout; si no, crea la rama local desde origin/name
+/// (siguiéndola como upstream) y hace checkout.
+pub fn checkout_remote_branch(repo: &mut GitRepo, branch_name: &str, mode: CheckoutMode) -> Result<(), GitError> {
+    let local_ref = format!("refs/heads/{}", branch_name);
+    let remote_ref = format!("refs/remotes/origin/{}", branch_name);
+    if repo.0.find_reference(&local_ref).is_ok() {
+        return checkout_branch(repo, branch_name, mode);
+    }
+    let r = &mut repo.0;
+    let target = {
+        let commit = r.find_reference(&remote_ref)?.peel_to_commit()?;
+        let mut branch = r.branch(branch_name, &commit, false)?;
+        branch.set_upstream(Some(&format!("origin/{}", branch_name)))?;
+        commit.id()
+    };
+    if let Err(e) = checkout_with_mode(r, target, &local_ref, mode) {
+        if !matches!(e, GitError::StashNotReapplied) {
+            // La rama se creó solo para este checkout: no dejarla colgando si no se pudo cambiar.
+            if let Ok(mut b) = r.find_branch(branch_name, git2::BranchType::Local) {
+                let _ = b.delete();
+            }
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Guarda los cambios actuales en el stash (git stash push).
+pub fn stash(repo: &mut GitRepo, options: &StashOptions) -> Result<(), GitError> {
+    let r = &mut repo.0;
+    let sig = r.signature()?;
+    let mut flags = git2::StashFlags::DEFAULT;
+    if options.include_untracked {
+        flags |= git2::StashFlags::INCLUDE_UNTRACKED;
+    }
+    if options.keep_index {
+        flags |= git2::StashFlags::KEEP_INDEX;
+    }
+    let message = Some(options.message.trim()).filter(|m| !m.is_empty());
+    match r.stash_save2(&sig, message, Some(flags)) {
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Err(GitError::NothingToStash),
+        Err(e) => Err(e.into()),
+        Ok(_) => Ok(()),
+    }
+}
+
+/// Rama de un mensaje de stash ("On main: ..." / "WIP on main: ...").
+fn stash_branch(message: &str) -> Option<String> {
+    let rest = message.strip_prefix("WIP on ").or_else(|| message.strip_prefix("On "))?;
+    let (branch, _) = rest.split_once(':')?;
+    (branch != "(no branch)").then(|| branch.to_string())
+}
+
+/// Lista de stashes, del más reciente (`stash@{0}`) al más antiguo.
+pub fn stash_list(repo: &mut GitRepo) -> Result<Vec<StashEntry>, GitError> {
+    let mut found: Vec<(usize, String, git2::Oid)> = Vec::new();
+    repo.0.stash_foreach(|index, message, oid| {
+        found.push((index, message.to_string(), *oid));
+        true
+    })?;
+    let mut out = Vec::with_capacity(found.len());
+    for (index, message, oid) in found {
+        let date = match repo.0.find_commit(oid) {
+            Ok(c) => format_timestamp(c.time().seconds()),
+            Err(_) => String::new(),
+        };
+        out.push(StashEntry {
+            index,
+            id_short: oid.to_string()[..7].to_string(),
+            branch: stash_branch(&message),
+            message,
+            date,
+        });
+    }
+    Ok(out)
+}
+
+fn check_stash_index(r: &mut git2::Repository, index: usize) -> Result<(), GitError> {
+    let mut count = 0;
+    r.stash_foreach(|_, _, _| {
+        count += 1;
+        true
+    })?;
+    if index >= count {
+        return Err(GitError::StashNotFound(index));
+    }
+    Ok(())
+}
+
+fn stash_apply_error(index: usize, e: git2::Error) -> GitError {
+    match e.code() {
+        git2::ErrorCode::Conflict | git2::ErrorCode::MergeConflict => GitError::StashConflicts(index),
+        _ => e.into(),
+    }
+}
+
+/// Aplica `stash@{index}`. Si choca con commits posteriores libgit2 lo aplica igual, con marcas de
+/// conflicto: entonces devuelve `StashAppliedWithConflicts`.
+fn apply_stash(r: &mut git2::Repository, index: usize) -> Result<(), GitError> {
+    check_stash_index(r, index)?;
+    r.stash_apply(index, None).map_err(|e| stash_apply_error(index, e))?;
+    let conflicts = index_conflicts(r)?;
+    if !conflicts.is_empty() {
+        return Err(GitError::StashAppliedWithConflicts(index, conflicts));
+    }
+    Ok(())
+}
+
+/// Aplica `stash@{index}` sin quitarlo de la lista (git stash apply).
+pub fn stash_apply(repo: &mut GitRepo, index: usize) -> Result<(), GitError> {
+    apply_stash(&mut repo.0, index)
+}
+
+/// Aplica `stash@{index}` y lo elimina de la lista (git stash pop). Si choca con los archivos
+/// actuales no se aplica nada y el stash se conserva; si se aplica con conflictos, también se conserva.
+pub fn stash_pop(repo: &mut GitRepo, index: usize) -> Result<(), GitError> {
+    let r = &mut repo.0;
+    apply_stash(r, index)?;
+    r.stash_drop(index)?;
+    Ok(())
+}
+
+/// Elimina `stash@{index}` sin aplicarlo (git stash drop).
+pub fn stash_drop(repo: &mut GitRepo, index: usize) -> Result<(), GitError> {
+    let r = &mut repo.0;
+    check_stash_index(r, index)?;
+    r.stash_drop(index)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempRepo;
+
+    /// main con a.txt de cinco líneas y b.txt; `other` cambia la primera línea de a.txt.
+    fn two_branches() -> TempRepo {
+        let repo = TempRepo::new();
+        repo.write("b.txt", "b\n");
+        repo.commit_file("a.txt", "1\n2\n3\n4\n5\n", "inicial");
+        repo.branch("other");
+        repo.checkout("other");
+        repo.commit_file("a.txt", "uno\n2\n3\n4\n5\n", "other");
+        repo.checkout("main");
+        repo
+    }
+
+    fn stash_count(repo: &mut TempRepo) -> usize {
+        stash_list(&mut repo.repo).unwrap().len()
+    }
+
+    #[test]
+    fn safe_checkout_refuses_to_overwrite_local_changes() {
+        let mut repo = two_branches();
+        repo.write("a.txt", "1\n2\n3\n4\ncinco\n");
+        match checkout_branch(&mut repo.repo, "other", CheckoutMode::Safe) {
+            Err(GitError::CheckoutConflicts(paths)) => assert_eq!(paths, ["a.txt"]),
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(repo.head_branch(), "main");
+        assert_eq!(repo.read("a.txt"), "1\n2\n3\n4\ncinco\n");
+
+        // Los cambios que no chocan viajan con el checkout.
+        repo.write("a.txt", "1\n2\n3\n4\n5\n");
+        repo.write("b.txt", "b local\n");
+        checkout_branch(&mut repo.repo, "other", CheckoutMode::Safe).unwrap();
+        assert_eq!(repo.head_branch(), "other");
+        assert_eq!(repo.read("a.txt"), "uno\n2\n3\n4\n5\n");
+        assert_eq!(repo.read("b.txt"), "b local\n");
+    }
+
+    #[test]
+    fn auto_stash_reapplies_local_changes_after_switching() {
+        let mut repo = two_branches();
+        repo.write("a.txt", "1\n2\n3\n4\ncinco\n");
+        repo.write("nuevo.txt", "sin seguimiento\n");
+        checkout_branch(&mut repo.repo, "other", CheckoutMode::AutoStash).unwrap();
+        assert_eq!(repo.head_branch(), "other");
+        assert_eq!(repo.read("a.txt"), "uno\n2\n3\n4\ncinco\n");
+        assert_eq!(repo.read("nuevo.txt"), "sin seguimiento\n");
+        assert_eq!(stash_count(&mut repo), 0);
+
+        // Sin cambios locales no se crea ningún stash.
+        checkout_branch(&mut repo.repo, "main", CheckoutMode::Force).unwrap();
+        checkout_branch(&mut repo.repo, "other", CheckoutMode::AutoStash).unwrap();
+        assert_eq!(stash_count(&mut repo), 0);
+    }
+
+    #[test]
+    fn auto_stash_keeps_the_stash_when_it_does_not_reapply() {
+        let mut repo = two_branches();
+        repo.write("a.txt", "UNO\n2\n3\n4\n5\n");
+        let result = checkout_branch(&mut repo.repo, "other", CheckoutMode::AutoStash);
+        assert!(matches!(result, Err(GitError::StashNotReapplied)), "{result:?}");
+        assert_eq!(repo.head_branch(), "other");
+        assert_eq!(status(&repo.repo).unwrap().conflicted, ["a.txt"]);
+        let stashes = stash_list(&mut repo.repo).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert!(stashes[0].message.contains("auto-stash antes de checkout refs/heads/other"), "{}", stashes[0].message);
+    }
+
+    #[test]
+    fn force_checkout_discards_conflicting_changes() {
+        let mut repo = two_branches();
+        repo.write("a.txt", "UNO\n2\n3\n4\n5\n");
+        checkout_branch(&mut repo.repo, "other", CheckoutMode::Force).unwrap();
+        assert_eq!(repo.head_branch(), "other");
+        assert_eq!(repo.read("a.txt"), "uno\n2\n3\n4\n5\n");
+        assert!(status(&repo.repo).unwrap().is_clean);
+    }
+
+    /// origin con a.txt y c.txt y un clon; los dos cambian la primera línea de a.txt (conflicto) y origin
+    /// añade además b.txt.
+    fn diverged_clone() -> (TempRepo, TempRepo) {
+        let origin = TempRepo::new();
+        origin.write("c.txt", "c\n");
+        origin.commit_file("a.txt", "1\n2\n3\n", "inicial");
+        let clone = TempRepo::clone_of(&origin);
+        origin.write("b.txt", "b\n");
+        origin.commit_file("a.txt", "origin\n2\n3\n", "en origin");
+        clone.commit_file("a.txt", "local\n2\n3\n", "en local");
+        (origin, clone)
+    }
+
+    fn parent_count(repo: &TempRepo, rev: &str) -> usize {
+        repo.git().revparse_single(rev).unwrap().peel_to_commit().unwrap().parent_count()
+    }
+
+    #[test]
+    fn pull_fast_forwards_or_merges() {
+        let origin = TempRepo::new();
+        origin.commit_file("a.txt", "1\n2\n3\n", "inicial");
+        let clone = TempRepo::clone_of(&origin);
+        assert_eq!(pull(&clone.repo, ProgressSink::default()).unwrap(), PullOutcome::UpToDate);
+
+        origin.commit_file("a.txt", "uno\n2\n3\n", "en origin");
+        assert_eq!(pull(&clone.repo, ProgressSink::default()).unwrap(), PullOutcome::FastForward);
+        assert_eq!(clone.read("a.txt"), "uno\n2\n3\n");
+
+        origin.commit_file("a.txt", "uno\n2\ntres\n", "otra vez en origin");
+        clone.commit_file("b.txt", "b\n", "en local");
+        assert_eq!(pull(&clone.repo, ProgressSink::default()).unwrap(), PullOutcome::Merged);
+        assert_eq!(parent_count(&clone, "HEAD"), 2);
+        assert_eq!(clone.summary("HEAD"), "Merge remote-tracking branch 'origin/main' into main");
+        assert_eq!(clone.read("a.txt"), "uno\n2\ntres\n");
+        assert!(!merge_in_progress(&clone.repo));
+    }
+
+    #[test]
+    fn conflicting_pull_is_finished_with_merge_continue() {
+        let (_origin, clone) = diverged_clone();
+        let outcome = pull(&clone.repo, ProgressSink::default()).unwrap();
+        assert_eq!(outcome, PullOutcome::Conflicts(vec!["a.txt".to_string()]));
+        assert!(merge_in_progress(&clone.repo));
+        assert_eq!(conflicted_paths(&clone.repo).unwrap(), ["a.txt"]);
+
+        match merge_continue(&clone.repo) {
+            Err(GitError::UnresolvedConflicts(paths)) => assert_eq!(paths, ["a.txt"]),
+            other => panic!("{other:?}"),
+        }
+        clone.write("a.txt", "ambos\n2\n3\n");
+        merge_continue(&clone.repo).unwrap();
+        assert!(!merge_in_progress(&clone.repo));
+        assert_eq!(parent_count(&clone, "HEAD"), 2);
+        assert_eq!(clone.summary("HEAD"), "Merge remote-tracking branch 'origin/main' into main");
+        assert!(status(&clone.repo).unwrap().is_clean);
+        assert!(matches!(merge_continue(&clone.repo), Err(GitError::Git(_))));
+    }
+
+    #[test]
+    fn merge_abort_restores_merge_files_and_keeps_other_changes() {
+        let (_origin, clone) = diverged_clone();
+        let local = clone.git().head().unwrap().target().unwrap();
+        pull(&clone.repo, ProgressSink::default()).unwrap();
+        clone.write("c.txt", "c local\n");
+        merge_abort(&clone.repo).unwrap();
+        assert!(!merge_in_progress(&clone.repo));
+        assert_eq!(clone.git().head().unwrap().target(), Some(local));
+        assert_eq!(clone.read("a.txt"), "local\n2\n3\n");
+        assert!(!clone.path().join("b.txt").exists());
+        assert_eq!(clone.read("c.txt"), "c local\n");
+        assert_eq!(status(&clone.repo).unwrap().modified, ["c.txt"]);
+    }
+
+    #[test]
+    fn merge_abort_refuses_to_discard_edits_on_merged_files() {
+        let (_origin, clone) = diverged_clone();
+        pull(&clone.repo, ProgressSink::default()).unwrap();
+        clone.write("b.txt", "b editado\n");
+        match merge_abort(&clone.repo) {
+            Err(GitError::AbortWouldDiscard(paths)) => assert_eq!(paths, ["b.txt"]),
+            other => panic!("{other:?}"),
+        }
+        assert!(merge_in_progress(&clone.repo));
+        assert_eq!(clone.read("b.txt"), "b editado\n");
+    }
+
+    fn head_id(repo: &TempRepo) -> git2::Oid {
+        repo.git().head().unwrap().target().unwrap()
+    }
+
+    fn upstream_id(repo: &TempRepo) -> git2::Oid {
+        repo.git().refname_to_id("refs/remotes/origin/main").unwrap()
+    }
+
+    #[test]
+    fn pull_rebase_replays_local_commits_on_the_upstream() {
+        let origin = TempRepo::new();
+        origin.commit_file("a.txt", "1\n", "inicial");
+        let clone = TempRepo::clone_of(&origin);
+        origin.commit_file("a.txt", "uno\n", "en origin");
+        clone.commit_file("b.txt", "b\n", "en local");
+
+        assert_eq!(pull_rebase(&clone.repo, ProgressSink::default()).unwrap(), PullOutcome::Rebased);
+        assert!(!rebase_in_progress(&clone.repo));
+        assert_eq!(clone.head_branch(), "main");
+        assert_eq!(clone.summary("HEAD"), "en local");
+        assert_eq!(clone.git().find_commit(head_id(&clone)).unwrap().parent_ids().collect::<Vec<_>>(), [upstream_id(&clone)]);
+        assert_eq!((clone.read("a.txt"), clone.read("b.txt")), ("uno\n".to_string(), "b\n".to_string()));
+        assert_eq!(pull_rebase(&clone.repo, ProgressSink::default()).unwrap(), PullOutcome::UpToDate);
+    }
+
+    #[test]
+    fn conflicting_pull_rebase_is_finished_with_rebase_continue() {
+        let (_origin, clone) = diverged_clone();
+        let outcome = pull_rebase(&clone.repo, ProgressSink::default()).unwrap();
+        assert_eq!(outcome, PullOutcome::Conflicts(vec!["a.txt".to_string()]));
+        assert!(rebase_in_progress(&clone.repo));
+
+        match rebase_continue(&clone.repo, ProgressSink::default()) {
+            Err(GitError::UnresolvedConflicts(paths)) => assert_eq!(paths, ["a.txt"]),
+            other => panic!("{other:?}"),
+        }
+        clone.write("a.txt", "ambos\n2\n3\n");
+        assert_eq!(rebase_continue(&clone.repo, ProgressSink::default()).unwrap(), PullOutcome::Rebased);
+        assert!(!rebase_in_progress(&clone.repo));
+        assert_eq!(clone.head_branch(), "main");
+        assert_eq!(clone.summary("HEAD"), "en local");
+        assert_eq!(clone.summary("HEAD~1"), "en origin");
+        assert_eq!(clone.read("a.txt"), "ambos\n2\n3\n");
+        assert!(status(&clone.repo).unwrap().is_clean);
+    }
+
+    #[test]
+    fn rebase_skip_drops_the_conflicting_commit() {
+        let (_origin, clone) = diverged_clone();
+        clone.commit_file("d.txt", "d\n", "otro local");
+        assert!(matches!(pull_rebase(&clone.repo, ProgressSink::default()).unwrap(), PullOutcome::Conflicts(_)));
+        assert_eq!(rebase_skip(&clone.repo, ProgressSink::default()).unwrap(), PullOutcome::Rebased);
+        assert_eq!(clone.summary("HEAD"), "otro local");
+        assert_eq!(clone.summary("HEAD~1"), "en origin");
+        assert_eq!(clone.read("a.txt"), "origin\n2\n3\n");
+        assert_eq!(clone.read("d.txt"), "d\n");
+        assert!(status(&clone.repo).unwrap().is_clean);
+    }
+
+    #[test]
+    fn rebase_abort_restores_the_branch() {
+        let (_origin, clone) = diverged_clone();
+        let local = head_id(&clone);
+        pull_rebase(&clone.repo, ProgressSink::default()).unwrap();
+        rebase_abort(&clone.repo).unwrap();
+        assert!(!rebase_in_progress(&clone.repo));
+        assert_eq!(clone.head_branch(), "main");
+        assert_eq!(head_id(&clone), local);
+        assert_eq!(clone.read("a.txt"), "local\n2\n3\n");
+        assert!(status(&clone.repo).unwrap().is_clean);
+    }
+
+    #[test]
+    fn progress_fraction_counts_received_and_indexed_objects() {
+        let transfer = |received_objects, indexed_objects, total_objects| GitProgress::Transfer {
+            received_objects,
+            indexed_objects,
+            total_objects,
+            received_bytes: 0,
+        };
+        assert_eq!(transfer(0, 0, 0).fraction(), 0.0);
+        assert_eq!(transfer(10, 0, 10).fraction(), 0.5);
+        assert_eq!(transfer(10, 10, 10).fraction(), 1.0);
+        assert_eq!(GitProgress::Push { current: 1, total: 4, bytes: 100 }.fraction(), 0.25);
+        assert_eq!(GitProgress::Rebase { current: 0, total: 0 }.fraction(), 0.0);
+    }
+
+    #[test]
+    fn pull_reports_progress_and_rebase_can_be_cancelled_between_steps() {
+        let (origin, clone) = diverged_clone();
+        origin.commit_file("e.txt", "e\n", "más en origin");
+        clone.commit_file("d.txt", "d\n", "otro local");
+        let events = std::cell::RefCell::new(Vec::new());
+        let on_progress = |p: GitProgress| events.borrow_mut().push(p);
+        let cancel = CancelToken::new();
+
+        let outcome = pull_rebase(&clone.repo, ProgressSink::new(&on_progress, &cancel)).unwrap();
+        assert!(matches!(outcome, PullOutcome::Conflicts(_)));
+        assert!(events.borrow().iter().any(|p| matches!(p, GitProgress::Transfer { total_objects, .. } if *total_objects > 0)));
+        assert_eq!(events.borrow().last(), Some(&GitProgress::Rebase { current: 1, total: 2 }));
+
+        // Cancelado: se hace commit del paso resuelto y se para antes del siguiente.
+        clone.write("a.txt", "ambos\n2\n3\n");
+        cancel.cancel();
+        assert!(matches!(rebase_continue(&clone.repo, ProgressSink::new(&on_progress, &cancel)), Err(GitError::Cancelled)));
+        assert!(rebase_in_progress(&clone.repo));
+        assert!(!clone.path().join("d.txt").exists());
+
+        let resumed = CancelToken::new();
+        events.borrow_mut().clear();
+        let outcome = rebase_continue(&clone.repo, ProgressSink::new(&on_progress, &resumed)).unwrap();
+        assert_eq!(outcome, PullOutcome::Rebased);
+        assert_eq!(*events.borrow(), [GitProgress::Rebase { current: 2, total: 2 }]);
+        assert_eq!(clone.summary("HEAD"), "otro local");
+        assert_eq!(clone.summary("HEAD~1"), "en local");
+    }
+
+    #[test]
+    fn cancelled_fetch_returns_cancelled() {
+        let origin = TempRepo::new();
+        origin.commit_file("a.txt", "1\n", "inicial");
+        let clone = TempRepo::clone_of(&origin);
+        origin.commit_file("a.txt", "uno\n", "en origin");
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let on_progress = |_| {};
+        assert!(matches!(fetch(&clone.repo, ProgressSink::new(&on_progress, &cancel)), Err(GitError::Cancelled)));
+        assert_ne!(upstream_id(&clone), origin.git().head().unwrap().target().unwrap());
+    }
+
+    fn push_main(repo: &TempRepo, options: PushOptions) -> Result<PushOutcome, GitError> {
+        push(&repo.repo, options, ProgressSink::default())
+    }
+
+    /// origin (bare) con un commit y dos clones, `ours` y `theirs`.
+    fn two_clones() -> (TempRepo, TempRepo, TempRepo) {
+        let seed = TempRepo::new();
+        seed.commit_file("a.txt", "1\n", "inicial");
+        let origin = TempRepo::bare_clone_of(&seed);
+        let ours = TempRepo::clone_of(&origin);
+        let theirs = TempRepo::clone_of(&origin);
+        (origin, ours, theirs)
+    }
+
+    fn origin_main(origin: &TempRepo) -> git2::Oid {
+        origin.git().refname_to_id("refs/heads/main").unwrap()
+    }
+
+    #[test]
+    fn push_sets_the_upstream_only_when_asked() {
+        let (origin, ours, _theirs) = two_clones();
+        ours.branch("feature");
+        ours.checkout("feature");
+        ours.commit_file("b.txt", "b\n", "feature");
+        match push_main(&ours, PushOptions::default()) {
+            Err(GitError::NoUpstream(branch)) => assert_eq!(branch, "feature"),
+            other => panic!("{other:?}"),
+        }
+
+        let outcome = push_main(&ours, PushOptions { set_upstream: true, ..PushOptions::default() }).unwrap();
+        assert!(outcome.upstream_set);
+        assert_eq!(outcome.to_string(), "Pushed feature to origin/feature (upstream set)");
+        assert_eq!(origin.git().refname_to_id("refs/heads/feature").unwrap(), head_id(&ours));
+        let config = ours.git().config().unwrap();
+        assert_eq!(config.get_string("branch.feature.remote").unwrap(), "origin");
+        assert_eq!(config.get_string("branch.feature.merge").unwrap(), "refs/heads/feature");
+
+        ours.commit_file("b.txt", "bb\n", "feature 2");
+        let outcome = push_main(&ours, PushOptions::default()).unwrap();
+        assert!(!outcome.upstream_set);
+        assert_eq!(origin.git().refname_to_id("refs/heads/feature").unwrap(), head_id(&ours));
+    }
+
+    #[test]
+    fn push_behind_the_remote_is_rejected() {
+        let (origin, ours, theirs) = two_clones();
+        theirs.commit_file("a.txt", "theirs\n", "theirs");
+        push_main(&theirs, PushOptions::default()).unwrap();
+        ours.commit_file("a.txt", "ours\n", "ours");
+        match push_main(&ours, PushOptions::default()) {
+            Err(GitError::PushRejected(refs)) => assert_eq!(refs, ["refs/heads/main (non-fast-forward)"]),
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(origin_main(&origin), head_id(&theirs));
+    }
+
+    #[test]
+    fn force_with_lease_is_rejected_after_the_remote_moved() {
+        let (origin, ours, theirs) = two_clones();
+        let lease = PushOptions { force_with_lease: true, ..PushOptions::default() };
+        theirs.commit_file("a.txt", "theirs\n", "theirs");
+        push_main(&theirs, PushOptions::default()).unwrap();
+
+        // Nuestro origin/main sigue en el commit inicial: el lease no se cumple.
+        ours.commit_file("a.txt", "ours\n", "ours");
+        match push_main(&ours, lease) {
+            Err(GitError::PushRejected(refs)) => assert_eq!(refs, ["refs/heads/main (stale info)"]),
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(origin_main(&origin), head_id(&theirs));
+
+        // Tras el fetch ya hemos visto su commit y el push forzado lo sustituye.
+        fetch(&ours.repo, ProgressSink::default()).unwrap();
+        push_main(&ours, lease).unwrap();
+        assert_eq!(origin_main(&origin), head_id(&ours));
+    }
+
+    #[test]
+    fn status_counts_commits_ahead_and_behind_the_upstream() {
+        let (origin, ours, theirs) = two_clones();
+        let st = status(&ours.repo).unwrap();
+        assert_eq!((st.upstream.as_deref(), st.ahead, st.behind), (Some("origin/main"), 0, 0));
+        assert_eq!(st.ahead_behind_label(), "");
+
+        theirs.commit_file("a.txt", "theirs\n", "theirs");
+        push_main(&theirs, PushOptions::default()).unwrap();
+        ours.commit_file("b.txt", "1\n", "ours 1");
+        ours.commit_file("b.txt", "2\n", "ours 2");
+        // Hasta el fetch no se sabe que origin avanzó.
+        assert_eq!(status(&ours.repo).unwrap().ahead_behind_label(), "↑2");
+        fetch(&ours.repo, ProgressSink::default()).unwrap();
+        let st = status(&ours.repo).unwrap();
+        assert_eq!((st.ahead, st.behind), (2, 1));
+        assert_eq!(st.ahead_behind_label(), "↑2 ↓1");
+        assert_eq!(status(&theirs.repo).unwrap().ahead_behind_label(), "");
+
+        ours.branch("local-only");
+        ours.checkout("local-only");
+        let st = status(&ours.repo).unwrap();
+        assert_eq!((st.upstream, st.ahead, st.behind), (None, 0, 0));
+
+        ours.git().set_head_detached(origin_main(&origin)).unwrap();
+        let st = status(&ours.repo).unwrap();
+        assert!(st.detached);
+        assert_eq!((st.branch.as_str(), st.upstream), ("HEAD", None));
+    }
+
+    #[test]
+    fn status_flags_merges_and_rebases_in_progress() {
+        let (_origin, clone) = diverged_clone();
+        let st = status(&clone.repo).unwrap();
+        assert!(!st.merging && !st.rebasing && !st.cherry_picking && !st.reverting);
+        pull(&clone.repo, ProgressSink::default()).unwrap();
+        let st = status(&clone.repo).unwrap();
+        assert!(st.merging && !st.rebasing);
+        assert_eq!(st.conflicted, ["a.txt"]);
+        merge_abort(&clone.repo).unwrap();
+
+        pull_rebase(&clone.repo, ProgressSink::default()).unwrap();
+        let st = status(&clone.repo).unwrap();
+        assert!(st.rebasing && !st.merging);
+        assert_eq!(st.conflicted, ["a.txt"]);
+    }
+
+    fn change(path: &str, kind: FileChangeKind) -> FileChange {
+        FileChange { path: path.to_string(), kind, old_path: None }
+    }
+
+    #[test]
+    fn status_splits_staged_unstaged_and_untracked() {
+        let repo = TempRepo::new();
+        repo.write("a.txt", "a\n");
+        repo.write("b.txt", "b\n");
+        repo.commit_file("viejo.txt", "contenido que se renombra\n", "inicial");
+
+        repo.write("a.txt", "a staged\n");
+        stage(&repo.repo, &[Path::new("a.txt")]).unwrap();
+        repo.write("a.txt", "a staged y más\n");
+        std::fs::remove_file(repo.path().join("b.txt")).unwrap();
+        std::fs::rename(repo.path().join("viejo.txt"), repo.path().join("nuevo.txt")).unwrap();
+        stage(&repo.repo, &[Path::new("viejo.txt"), Path::new("nuevo.txt")]).unwrap();
+        repo.write("dir/suelto.txt", "x\n");
+
+        let st = status(&repo.repo).unwrap();
+        assert!(!st.is_clean);
+        assert_eq!(
+            st.staged,
+            [
+                change("a.txt", FileChangeKind::Modified),
+                FileChange { old_path: Some("viejo.txt".to_string()), ..change("nuevo.txt", FileChangeKind::Renamed) },
+            ]
+        );
+        assert_eq!(st.unstaged, [change("a.txt", FileChangeKind::Modified), change("b.txt", FileChangeKind::Deleted)]);
+        assert_eq!(st.untracked, ["dir/suelto.txt"]);
+        assert_eq!(st.modified, ["a.txt", "nuevo.txt", "b.txt"]);
+        assert_eq!(FileChangeKind::Renamed.letter(), "R");
+    }
+
+    #[test]
+    fn stage_unstage_and_discard_move_changes_between_areas() {
+        let repo = TempRepo::new();
+        repo.write("b.txt", "b\n");
+        repo.commit_file("a.txt", "a\n", "inicial");
+        repo.write("a.txt", "a cambiado\n");
+        std::fs::remove_file(repo.path().join("b.txt")).unwrap();
+        repo.write("nuevo.txt", "n\n");
+
+        stage(&repo.repo, &[Path::new("a.txt"), Path::new("b.txt"), Path::new("nuevo.txt")]).unwrap();
+        let st = status(&repo.repo).unwrap();
+        assert_eq!(
+            st.staged,
+            [
+                change("a.txt", FileChangeKind::Modified),
+                change("b.txt", FileChangeKind::Deleted),
+                change("nuevo.txt", FileChangeKind::Added),
+            ]
+        );
+        assert!(st.unstaged.is_empty() && st.untracked.is_empty());
+
+        unstage(&repo.repo, &[Path::new("a.txt"), Path::new("b.txt"), Path::new("nuevo.txt")]).unwrap();
+        let st = status(&repo.repo).unwrap();
+        assert!(st.staged.is_empty());
+        assert_eq!(st.unstaged, [change("a.txt", FileChangeKind::Modified), change("b.txt", FileChangeKind::Deleted)]);
+        assert_eq!(st.untracked, ["nuevo.txt"]);
+
+        discard(&repo.repo, &[Path::new("a.txt"), Path::new("b.txt"), Path::new("nuevo.txt")]).unwrap();
+        assert!(status(&repo.repo).unwrap().is_clean);
+        assert_eq!((repo.read("a.txt"), repo.read("b.txt")), ("a\n".to_string(), "b\n".to_string()));
+        assert!(!repo.path().join("nuevo.txt").exists());
+    }
+
+    #[test]
+    fn unstage_works_before_the_first_commit() {
+        let repo = TempRepo::new();
+        repo.write("a.txt", "a\n");
+        stage(&repo.repo, &[Path::new("a.txt")]).unwrap();
+        assert_eq!(status(&repo.repo).unwrap().staged, [change("a.txt", FileChangeKind::Added)]);
+        unstage(&repo.repo, &[Path::new("a.txt")]).unwrap();
+        let st = status(&repo.repo).unwrap();
+        assert!(st.staged.is_empty());
+        assert_eq!(st.untracked, ["a.txt"]);
+    }
+
+    fn stash_options(message: &str, include_untracked: bool, keep_index: bool) -> StashOptions {
+        StashOptions { message: message.to_string(), include_untracked, keep_index }
+    }
+
+    #[test]
+    fn stash_saves_with_options_and_lists_newest_first() {
+        let mut repo = TempRepo::new();
+        repo.commit_file("a.txt", "a\n", "inicial");
+        assert!(matches!(stash(&mut repo.repo, &StashOptions::default()), Err(GitError::NothingToStash)));
+
+        repo.write("a.txt", "a 1\n");
+        repo.write("suelto.txt", "s\n");
+        stash(&mut repo.repo, &stash_options("", false, false)).unwrap();
+        // Sin include_untracked el archivo sin seguimiento se queda en el disco.
+        assert_eq!(repo.read("a.txt"), "a\n");
+        assert!(repo.path().join("suelto.txt").exists());
+
+        stash(&mut repo.repo, &stash_options("  con sueltos ", true, false)).unwrap();
+        assert!(!repo.path().join("suelto.txt").exists());
+
+        repo.write("a.txt", "a preparado\n");
+        stage(&repo.repo, &[Path::new("a.txt")]).unwrap();
+        stash(&mut repo.repo, &stash_options("keep", false, true)).unwrap();
+        assert_eq!(repo.read("a.txt"), "a preparado\n");
+        assert_eq!(status(&repo.repo).unwrap().staged, [change("a.txt", FileChangeKind::Modified)]);
+
+        let list = stash_list(&mut repo.repo).unwrap();
+        let messages: Vec<&str> = list.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(list.iter().map(|e| e.index).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(&messages[..2], ["On main: keep", "On main: con sueltos"]);
+        assert!(messages[2].starts_with("WIP on main: "), "{}", messages[2]);
+        assert!(list.iter().all(|e| e.branch.as_deref() == Some("main") && e.id_short.len() == 7));
+        assert_eq!(stash_branch("WIP on (no branch): abc1234 x"), None);
+    }
+
+    #[test]
+    fn stash_apply_pop_and_drop_by_index() {
+        let mut repo = TempRepo::new();
+        repo.commit_file("a.txt", "a\n", "inicial");
+        repo.write("a.txt", "primero\n");
+        stash(&mut repo.repo, &stash_options("primero", false, false)).unwrap();
+        repo.write("b.txt", "segundo\n");
+        stash(&mut repo.repo, &stash_options("segundo", true, false)).unwrap();
+
+        stash_apply(&mut repo.repo, 1).unwrap();
+        assert_eq!(repo.read("a.txt"), "primero\n");
+        assert_eq!(stash_count(&mut repo), 2);
+        discard(&repo.repo, &[Path::new("a.txt")]).unwrap();
+
+        stash_pop(&mut repo.repo, 1).unwrap();
+        assert_eq!(repo.read("a.txt"), "primero\n");
+        let list = stash_list(&mut repo.repo).unwrap();
+        assert_eq!(list.iter().map(|e| e.message.as_str()).collect::<Vec<_>>(), ["On main: segundo"]);
+
+        assert!(matches!(stash_pop(&mut repo.repo, 1), Err(GitError::StashNotFound(1))));
+        assert!(matches!(stash_drop(&mut repo.repo, 5), Err(GitError::StashNotFound(5))));
+        stash_drop(&mut repo.repo, 0).unwrap();
+        assert_eq!(stash_count(&mut repo), 0);
+        assert!(!repo.path().join("b.txt").exists());
+    }
+
+    #[test]
+    fn stash_pop_over_local_edits_applies_nothing() {
+        let mut repo = TempRepo::new();
+        repo.commit_file("a.txt", "a\n", "inicial");
+        repo.write("a.txt", "guardado\n");
+        stash(&mut repo.repo, &StashOptions::default()).unwrap();
+        repo.write("a.txt", "local\n");
+        assert!(matches!(stash_pop(&mut repo.repo, 0), Err(GitError::StashConflicts(0))));
+        assert_eq!(repo.read("a.txt"), "local\n");
+        assert_eq!(stash_count(&mut repo), 1);
+    }
+
+    #[test]
+    fn stash_pop_with_conflicts_keeps_the_stash() {
+        let mut repo = TempRepo::new();
+        repo.commit_file("a.txt", "a\n", "inicial");
+        repo.write("a.txt", "guardado\n");
+        stash(&mut repo.repo, &StashOptions::default()).unwrap();
+        repo.commit_file("a.txt", "commit posterior\n", "cambia a.txt");
+
+        match stash_pop(&mut repo.repo, 0) {
+            Err(GitError::StashAppliedWithConflicts(0, paths)) => assert_eq!(paths, ["a.txt"]),
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(status(&repo.repo).unwrap().conflicted, ["a.txt"]);
+        assert_eq!(stash_count(&mut repo), 1);
+    }
+
+    #[test]
+    fn cherry_pick_keeps_the_original_author_and_message() {
+        let repo = TempRepo::new();
+        repo.commit_file("a.txt", "1\n2\n3\n", "inicial");
+        repo.branch("feature");
+        repo.checkout("feature");
+        repo.write("b.txt", "b\n");
+        let r = repo.git();
+        let mut index = r.index().unwrap();
+        index.add_path(Path::new("b.txt")).unwrap();
+        index.write().unwrap();
+        let tree = r.find_tree(index.write_tree().unwrap()).unwrap();
+        let ana = git2::Signature::now("Ana", "ana@example.com").unwrap();
+        let parent = r.head().unwrap().peel_to_commit().unwrap();
+        let picked = r.commit(Some("HEAD"), &ana, &ana, "Añade b\n\nDetalle.\n", &tree, &[&parent]).unwrap();
+        repo.checkout("main");
+
+        let outcome = cherry_pick(&repo.repo, &picked.to_string()[..8]).unwrap();
+        assert_eq!(outcome, PickOutcome::Committed(short_id(head_id(&repo))));
+        let head = r.head().unwrap().peel_to_commit().unwrap();
+        assert_ne!(head.id(), picked);
+        assert_eq!(head.message(), Some("Añade b\n\nDetalle.\n"));
+        assert_eq!((head.author().name(), head.committer().name()), (Some("Ana"), Some("Tester")));
+        assert_eq!(repo.read("b.txt"), "b\n");
+        assert!(!pick_in_progress(&repo.repo));
+        // Ya aplicado: no queda nada que commitear.
+        assert!(matches!(cherry_pick(&repo.repo, &picked.to_string()), Err(GitError::NothingToCommit)));
+        assert!(matches!(cherry_pick(&repo.repo, "no-existe"), Err(GitError::CommitNotFound(_))));
+    }
+
+    #[test]
+    fn revert_creates_the_inverse_commit() {
+        let repo = TempRepo::new();
+        repo.commit_file("a.txt", "1\n2\n3\n", "inicial");
+        let change = repo.commit_file("a.txt", "1\ndos\n3\n", "cambia la segunda línea");
+        repo.commit_file("b.txt", "b\n", "otro");
+
+        assert!(matches!(revert(&repo.repo, &change.to_string()).unwrap(), PickOutcome::Committed(_)));
+        assert_eq!(repo.summary("HEAD"), "Revert \"cambia la segunda línea\"");
+        assert_eq!(repo.read("a.txt"), "1\n2\n3\n");
+        assert_eq!(repo.read("b.txt"), "b\n");
+        assert!(status(&repo.repo).unwrap().is_clean);
+    }
+
+    #[test]
+    fn conflicting_pick_is_continued_or_aborted() {
+        let repo = TempRepo::new();
+        repo.commit_file("a.txt", "1\n2\n3\n", "inicial");
+        repo.branch("feature");
+        repo.checkout("feature");
+        let picked = repo.commit_file("a.txt", "feature\n2\n3\n", "en feature");
+        repo.checkout("main");
+        let main = repo.commit_file("a.txt", "main\n2\n3\n", "en main");
+
+        let outcome = cherry_pick(&repo.repo, &picked.to_string()).unwrap();
+        assert_eq!(outcome, PickOutcome::Conflicts(vec!["a.txt".to_string()]));
+        assert!(pick_in_progress(&repo.repo));
+        assert!(status(&repo.repo).unwrap().cherry_picking);
+        assert!(matches!(cherry_pick(&repo.repo, "HEAD"), Err(GitError::Git(_))));
+        pick_abort(&repo.repo).unwrap();
+        assert!(!pick_in_progress(&repo.repo));
+        assert_eq!(head_id(&repo), main);
+        assert_eq!(repo.read("a.txt"), "main\n2\n3\n");
+
+        cherry_pick(&repo.repo, &picked.to_string()).unwrap();
+        assert!(matches!(pick_continue(&repo.repo), Err(GitError::UnresolvedConflicts(_))));
+        repo.write("a.txt", "ambos\n2\n3\n");
+        let id = pick_continue(&repo.repo).unwrap();
+        assert_eq!(id, short_id(head_id(&repo)));
+        assert_eq!(repo.git().head().unwrap().peel_to_commit().unwrap().message(), Some("en feature"));
+        assert!(!pick_in_progress(&repo.repo));
+        assert!(status(&repo.repo).unwrap().is_clean);
+        assert!(matches!(pick_continue(&repo.repo), Err(GitError::Git(_))));
+    }
+
+    #[test]
+    fn merge_continue_drops_the_conflict_comments_of_merge_msg() {
+        let repo = TempRepo::new();
+        repo.commit_file("a.txt", "1\n", "inicial");
+        repo.branch("feature");
+        repo.checkout("feature");
+        repo.commit_file("a.txt", "feature\n", "en feature");
+        repo.checkout("main");
+        repo.commit_file("a.txt", "main\n", "en main");
+        // Como un `git merge feature` lanzado desde la terminal.
+        let r = repo.git();
+        let feature = r.reference_to_annotated_commit(&r.find_reference("refs/heads/feature").unwrap()).unwrap();
+        r.merge(&[&feature], None, None).unwrap();
+        assert!(r.message().unwrap().contains("#Conflicts:"));
+
+        repo.write("a.txt", "ambos\n");
+        merge_continue(&repo.repo).unwrap();
+        assert_eq!(r.head().unwrap().peel_to_commit().unwrap().message(), Some("Merge branch 'feature'"));
+    }
+}
@@ -112,18 +112,3 @@ pub fn list_branches(base_url: &str, token: &str, project_id: u64) -> Result<Vec
         .map(|b| GitLabBranch { name: b.name })
         .collect())
 }
-
-/// URL de clonación HTTPS con token incrustado (para git clone).
-pub fn clone_url_with_token(http_url: &str, token: &str) -> String {
-    let token = token.trim();
-    if token.is_empty() {
-        return http_url.to_string();
-    }
-    if let Some(rest) = http_url.strip_prefix("https://") {
-        return format!("https://oauth2:{}@{}", token, rest);
-    }
-    if let Some(rest) = http_url.strip_prefix("http://") {
-        return format!("http://oauth2:{}@{}", token, rest);
-    }
-    http_url.to_string()
-}
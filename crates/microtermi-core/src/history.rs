@@ -0,0 +1,604 @@
+//! Historial paginado con filtros y disposición en carriles para dibujar el grafo de commits.
+
+use crate::git::{commit_changes, find_commit, format_timestamp, CommitFileChange, GitError, GitRepo};
+use crate::signing::{verify_commit_signature, SignatureCheck};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Filtros del historial. Los textos se comparan sin distinguir mayúsculas; vacío = sin filtro.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogQuery {
+    /// Todas las ramas locales y remotas (y tags) en lugar de solo HEAD.
+    pub all_branches: bool,
+    /// Parte del nombre o del email del autor.
+    pub author: String,
+    /// Solo commits que cambian esta ruta (archivo o carpeta).
+    pub path: String,
+    /// Parte del mensaje (completo, no solo la primera línea).
+    pub message: String,
+    /// Fecha mínima `YYYY-MM-DD` (incluida, UTC).
+    pub since: String,
+    /// Fecha máxima `YYYY-MM-DD` (incluida, UTC).
+    pub until: String,
+}
+
+impl LogQuery {
+    /// Hay algún filtro de contenido activo (además de la elección de ramas).
+    pub fn is_filtered(&self) -> bool {
+        [&self.author, &self.path, &self.message, &self.since, &self.until]
+            .iter()
+            .any(|s| !s.trim().is_empty())
+    }
+}
+
+/// Dónde seguir el historial: la frontera del recorrido (commits aún sin recorrer cuyos hijos ya salieron,
+/// más las puntas de rama sin recorrer) y los carriles abiertos al final de la página anterior. La página
+/// siguiente recorre desde la frontera en vez de saltarse otra vez todo lo ya visto.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogCursor {
+    pub pending: Vec<String>,
+    pub lanes: Vec<Option<String>>,
+}
+
+/// Fila del grafo. Cada segmento va de un carril a otro: `up` desde el borde superior de la fila hasta
+/// el punto del commit (o de largo), `down` desde ahí hasta el borde inferior.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GraphRow {
+    /// Carril del commit.
+    pub column: usize,
+    pub up: Vec<(usize, usize)>,
+    pub down: Vec<(usize, usize)>,
+    /// Carriles ocupados en la fila (ancho a reservar).
+    pub width: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    pub id: String,
+    pub id_short: String,
+    pub parents: Vec<String>,
+    /// Primera línea del mensaje.
+    pub summary: String,
+    pub message: String,
+    pub author: String,
+    pub email: String,
+    pub date: String,
+    pub time: i64,
+    /// Ramas y tags que apuntan al commit (`main`, `origin/main`, `tag: v1.0`).
+    pub refs: Vec<String>,
+    /// Tiene firma (gpg, ssh o x509); se verifica aparte con `verify_commit_signature`.
+    pub signed: bool,
+    /// Solo sin filtros de contenido: con commits ocultos las líneas del grafo no tendrían sentido.
+    pub graph: Option<GraphRow>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogPage {
+    pub entries: Vec<LogEntry>,
+    /// None cuando ya no quedan commits.
+    pub next: Option<LogCursor>,
+}
+
+fn parse_day(s: &str, end_of_day: bool) -> Result<Option<i64>, GitError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    let day = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| GitError::InvalidDate(s.to_string()))?;
+    let time = if end_of_day { day.and_hms_opt(23, 59, 59) } else { day.and_hms_opt(0, 0, 0) };
+    Ok(time.map(|t| t.and_utc().timestamp()))
+}
+
+/// Nombres de ramas y tags por commit, para decorar el historial.
+fn ref_names(r: &git2::Repository) -> HashMap<git2::Oid, Vec<String>> {
+    let mut out: HashMap<git2::Oid, Vec<String>> = HashMap::new();
+    let Ok(refs) = r.references() else { return out };
+    for reference in refs.flatten() {
+        let Some(name) = reference.name() else { continue };
+        let label = if let Some(b) = name.strip_prefix("refs/heads/") {
+            b.to_string()
+        } else if let Some(b) = name.strip_prefix("refs/remotes/") {
+            if b.ends_with("/HEAD") {
+                continue;
+            }
+            b.to_string()
+        } else if let Some(t) = name.strip_prefix("refs/tags/") {
+            format!("tag: {}", t)
+        } else {
+            continue;
+        };
+        if let Ok(commit) = reference.peel_to_commit() {
+            out.entry(commit.id()).or_default().push(label);
+        }
+    }
+    out
+}
+
+/// Como `git log -- <ruta>`: el commit cambia la ruta respecto a su padre; un merge solo si difiere de
+/// todos sus padres (si coincide con alguno, el cambio ya viene de esa rama).
+fn touches_path(r: &git2::Repository, commit: &git2::Commit<'_>, path: &str) -> Result<bool, GitError> {
+    let tree = commit.tree()?;
+    let mut opts = git2::DiffOptions::new();
+    opts.pathspec(path);
+    if commit.parent_count() == 0 {
+        let diff = r.diff_tree_to_tree(None, Some(&tree), Some(&mut opts))?;
+        return Ok(diff.deltas().len() > 0);
+    }
+    for parent in commit.parents() {
+        let diff = r.diff_tree_to_tree(Some(&parent.tree()?), Some(&tree), Some(&mut opts))?;
+        if diff.deltas().len() == 0 {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Coloca el commit en su carril y actualiza los carriles abiertos con sus padres.
+fn layout_row(lanes: &mut Vec<Option<git2::Oid>>, id: git2::Oid, parents: &[git2::Oid]) -> GraphRow {
+    let column = match lanes.iter().position(|l| *l == Some(id)) {
+        Some(c) => c,
+        None => match lanes.iter().position(Option::is_none) {
+            Some(free) => free,
+            None => {
+                lanes.push(None);
+                lanes.len() - 1
+            }
+        },
+    };
+    let mut up = Vec::new();
+    for (i, lane) in lanes.iter().enumerate() {
+        match lane {
+            Some(l) if *l == id => up.push((i, column)),
+            Some(_) => up.push((i, i)),
+            None => {}
+        }
+    }
+    // Los demás carriles que esperaban este commit terminan aquí.
+    for lane in lanes.iter_mut() {
+        if *lane == Some(id) {
+            *lane = None;
+        }
+    }
+    lanes[column] = parents.first().copied();
+    let mut down = Vec::new();
+    if !parents.is_empty() {
+        down.push((column, column));
+    }
+    for parent in parents.iter().skip(1) {
+        let target = match lanes.iter().position(|l| *l == Some(*parent)) {
+            Some(existing) => existing,
+            None => match lanes.iter().position(Option::is_none) {
+                Some(free) => {
+                    lanes[free] = Some(*parent);
+                    free
+                }
+                None => {
+                    lanes.push(Some(*parent));
+                    lanes.len() - 1
+                }
+            },
+        };
+        down.push((column, target));
+    }
+    for (i, lane) in lanes.iter().enumerate() {
+        if lane.is_some() && i != column && !down.iter().any(|&(_, to)| to == i) {
+            down.push((i, i));
+        }
+    }
+    while lanes.last() == Some(&None) {
+        lanes.pop();
+    }
+    let width = up
+        .iter()
+        .chain(down.iter())
+        .map(|&(a, b)| a.max(b) + 1)
+        .max()
+        .unwrap_or(0)
+        .max(column + 1);
+    GraphRow { column, up, down, width }
+}
+
+/// Commits desde los que empieza el historial: HEAD y, con `all_branches`, las puntas de ramas, ramas
+/// remotas y tags.
+fn log_tips(r: &git2::Repository, all_branches: bool) -> Vec<git2::Oid> {
+    let mut tips: Vec<git2::Oid> = r.head().ok().and_then(|h| h.target()).into_iter().collect();
+    if all_branches {
+        for reference in r.references().into_iter().flatten().flatten() {
+            let name = reference.name().unwrap_or("");
+            if !["refs/heads/", "refs/remotes/", "refs/tags/"].iter().any(|p| name.starts_with(p)) {
+                continue;
+            }
+            if let Ok(commit) = reference.peel_to_commit() {
+                if !tips.contains(&commit.id()) {
+                    tips.push(commit.id());
+                }
+            }
+        }
+    }
+    tips
+}
+
+/// Página del historial: hasta `limit` commits que cumplen `query`, a partir de `cursor` (None = desde el
+/// principio). Orden topológico (hijos antes que padres) y por fecha.
+pub fn log_page(
+    repo: &GitRepo,
+    query: &LogQuery,
+    cursor: Option<&LogCursor>,
+    limit: usize,
+) -> Result<LogPage, GitError> {
+    let r = &repo.0;
+    let since = parse_day(&query.since, false)?;
+    let until = parse_day(&query.until, true)?;
+    let author = query.author.trim().to_lowercase();
+    let message = query.message.trim().to_lowercase();
+    let path = query.path.trim().trim_start_matches("./").to_string();
+    let with_graph = !query.is_filtered();
+
+    // Todo lo que queda por recorrer es ancestro de la frontera, y ningún commit ya recorrido lo es
+    // (en orden topológico los hijos salen antes que los padres).
+    let mut pending = match cursor {
+        Some(c) => c.pending.iter().map(|id| git2::Oid::from_str(id)).collect::<Result<Vec<_>, _>>()?,
+        None => log_tips(r, query.all_branches),
+    };
+    if pending.is_empty() {
+        // Repo sin commits.
+        return Ok(LogPage { entries: Vec::new(), next: None });
+    }
+    let mut revwalk = r.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    for oid in &pending {
+        revwalk.push(*oid)?;
+    }
+
+    let mut lanes: Vec<Option<git2::Oid>> = cursor
+        .map(|c| c.lanes.iter().map(|l| l.as_deref().and_then(|s| git2::Oid::from_str(s).ok())).collect())
+        .unwrap_or_default();
+    let refs = ref_names(r);
+    let mut entries = Vec::new();
+    let mut exhausted = true;
+    for oid in revwalk.by_ref() {
+        let oid = oid?;
+        let commit = r.find_commit(oid)?;
+        let parents: Vec<git2::Oid> = commit.parent_ids().collect();
+        pending.retain(|p| *p != oid);
+        for parent in &parents {
+            if !pending.contains(parent) {
+                pending.push(*parent);
+            }
+        }
+        let graph = with_graph.then(|| layout_row(&mut lanes, oid, &parents));
+        let time = commit.time().seconds();
+        if since.is_some_and(|s| time < s) || until.is_some_and(|u| time > u) {
+            continue;
+        }
+        let sig = commit.author();
+        let name = sig.name().unwrap_or("").to_string();
+        let email = sig.email().unwrap_or("").to_string();
+        if !author.is_empty() && !name.to_lowercase().contains(&author) && !email.to_lowercase().contains(&author) {
+            continue;
+        }
+        let full_message = commit.message().unwrap_or("").trim().to_string();
+        if !message.is_empty() && !full_message.to_lowercase().contains(&message) {
+            continue;
+        }
+        if !path.is_empty() && !touches_path(r, &commit, &path)? {
+            continue;
+        }
+        let id = oid.to_string();
+        entries.push(LogEntry {
+            id_short: id[..7].to_string(),
+            id,
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            summary: full_message.lines().next().unwrap_or("").to_string(),
+            message: full_message,
+            author: name,
+            email,
+            date: format_timestamp(time),
+            time,
+            refs: refs.get(&oid).cloned().unwrap_or_default(),
+            signed: commit.header_field_bytes("gpgsig").is_ok(),
+            graph,
+        });
+        if entries.len() >= limit {
+            exhausted = false;
+            break;
+        }
+    }
+    // La página se llenó justo con el último commit: comprobar si queda alguno.
+    if !exhausted && revwalk.next().is_none() {
+        exhausted = true;
+    }
+    let next = (!exhausted).then(|| LogCursor {
+        pending: pending.iter().map(|o| o.to_string()).collect(),
+        lanes: lanes.iter().map(|l| l.map(|o| o.to_string())).collect(),
+    });
+    Ok(LogPage { entries, next })
+}
+
+/// Autor o committer de un commit, con la fecha en su zona horaria.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitPerson {
+    pub name: String,
+    pub email: String,
+    /// `YYYY-MM-DD HH:MM +ZZZZ` en la zona horaria de la firma.
+    pub date: String,
+    pub time: i64,
+    /// Desplazamiento respecto a UTC en minutos.
+    pub offset_minutes: i32,
+}
+
+impl CommitPerson {
+    fn from_signature(sig: &git2::Signature<'_>) -> Self {
+        let when = sig.when();
+        let offset = chrono::FixedOffset::east_opt(when.offset_minutes() * 60)
+            .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).expect("zero offset"));
+        let date = chrono::DateTime::from_timestamp(when.seconds(), 0)
+            .map(|dt| dt.with_timezone(&offset).format("%Y-%m-%d %H:%M %z").to_string())
+            .unwrap_or_else(|| when.seconds().to_string());
+        CommitPerson {
+            name: sig.name().unwrap_or("").to_string(),
+            email: sig.email().unwrap_or("").to_string(),
+            date,
+            time: when.seconds(),
+            offset_minutes: when.offset_minutes(),
+        }
+    }
+}
+
+/// Detalle completo de un commit para el panel del historial.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitDetail {
+    pub id: String,
+    pub id_short: String,
+    pub parents: Vec<String>,
+    pub summary: String,
+    pub message: String,
+    pub author: CommitPerson,
+    pub committer: CommitPerson,
+    /// Ramas y tags que apuntan al commit.
+    pub refs: Vec<String>,
+    /// Archivos cambiados respecto al primer padre.
+    pub files: Vec<CommitFileChange>,
+    /// Verificación de la firma; None si el commit no está firmado.
+    pub signature: Option<SignatureCheck>,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Detalle de `rev` (id completo o prefijo no ambiguo, rama, tag o cualquier revisión).
+pub fn commit_detail(repo: &GitRepo, rev: &str) -> Result<CommitDetail, GitError> {
+    let r = &repo.0;
+    let commit = find_commit(r, rev)?;
+    let id = commit.id().to_string();
+    let files = commit_changes(repo, &id)?;
+    let message = commit.message().unwrap_or("").trim().to_string();
+    let author = CommitPerson::from_signature(&commit.author());
+    let committer = CommitPerson::from_signature(&commit.committer());
+    Ok(CommitDetail {
+        id_short: id[..7].to_string(),
+        parents: commit.parent_ids().map(|p| p.to_string()).collect(),
+        summary: message.lines().next().unwrap_or("").to_string(),
+        message,
+        author,
+        committer,
+        refs: ref_names(r).remove(&commit.id()).unwrap_or_default(),
+        insertions: files.iter().map(|f| f.insertions).sum(),
+        deletions: files.iter().map(|f| f.deletions).sum(),
+        signature: verify_commit_signature(repo, &id)?,
+        files,
+        id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempRepo;
+    use std::collections::HashSet;
+
+    /// Commit en `refs/heads/<branch>` con el árbol del primer padre más `file`, autor y fecha fijos
+    /// (fechas distintas: con empates el orden de libgit2 depende de por dónde empieza el recorrido).
+    fn commit_at(
+        repo: &TempRepo,
+        branch: &str,
+        parents: &[git2::Oid],
+        file: &str,
+        author: &str,
+        time: i64,
+    ) -> git2::Oid {
+        let r = repo.git();
+        let parents: Vec<git2::Commit> = parents.iter().map(|p| r.find_commit(*p).unwrap()).collect();
+        let base = parents.first().map(|p| p.tree().unwrap());
+        let mut builder = r.treebuilder(base.as_ref()).unwrap();
+        let blob = r.blob(format!("{branch} {time}\n").as_bytes()).unwrap();
+        builder.insert(file, blob, 0o100644).unwrap();
+        let tree = r.find_tree(builder.write().unwrap()).unwrap();
+        let sig = git2::Signature::new(author, &format!("{}@example.com", author.to_lowercase()), &git2::Time::new(time, 0))
+            .unwrap();
+        let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+        let message = format!("{file} en {branch} ({time})");
+        r.commit(Some(&format!("refs/heads/{branch}")), &sig, &sig, &message, &tree, &parent_refs).unwrap()
+    }
+
+    /// main: m1 - m2 - m3 - merge(feature); feature: m1 - f1 - f2; topic (sin fusionar): m2 - t1 - t2,
+    /// con el tag v1 en t1 y old en m1.
+    fn branchy_repo() -> TempRepo {
+        let repo = TempRepo::new();
+        let t = 1_700_000_000;
+        let m1 = commit_at(&repo, "main", &[], "a.txt", "Ana", t);
+        let f1 = commit_at(&repo, "feature", &[m1], "b.txt", "Beto", t + 60);
+        let m2 = commit_at(&repo, "main", &[m1], "a.txt", "Ana", t + 120);
+        let f2 = commit_at(&repo, "feature", &[f1], "b.txt", "Beto", t + 180);
+        let t1 = commit_at(&repo, "topic", &[m2], "c.txt", "Beto", t + 240);
+        let m3 = commit_at(&repo, "main", &[m2], "a.txt", "Ana", t + 300);
+        {
+            // El merge trae b.txt de feature.
+            let r = repo.git();
+            let b = r.find_commit(f2).unwrap().tree().unwrap().get_name("b.txt").unwrap().id();
+            let mut builder = r.treebuilder(Some(&r.find_commit(m3).unwrap().tree().unwrap())).unwrap();
+            builder.insert("b.txt", b, 0o100644).unwrap();
+            let tree = r.find_tree(builder.write().unwrap()).unwrap();
+            let sig = git2::Signature::new("Ana", "ana@example.com", &git2::Time::new(t + 360, 0)).unwrap();
+            let (c3, c2) = (r.find_commit(m3).unwrap(), r.find_commit(f2).unwrap());
+            r.commit(Some("refs/heads/main"), &sig, &sig, "Merge feature", &tree, &[&c3, &c2]).unwrap();
+            commit_at(&repo, "topic", &[t1], "b.txt", "Ana", t + 420);
+            r.reference("refs/tags/v1", t1, false, "").unwrap();
+            r.reference("refs/heads/old", m1, false, "").unwrap();
+            r.set_head("refs/heads/main").unwrap();
+        }
+        repo
+    }
+
+    /// El recorrido completo de libgit2 desde las mismas puntas, sin paginar.
+    fn full_walk(repo: &TempRepo, all_branches: bool) -> Vec<git2::Oid> {
+        let r = repo.git();
+        let mut revwalk = r.revwalk().unwrap();
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME).unwrap();
+        for tip in log_tips(r, all_branches) {
+            revwalk.push(tip).unwrap();
+        }
+        revwalk.map(|oid| oid.unwrap()).collect()
+    }
+
+    /// Todas las páginas de `size` commits seguidas, comprobando que el cursor solo falta en la última.
+    fn paged(repo: &TempRepo, query: &LogQuery, size: usize) -> Vec<LogEntry> {
+        let mut out = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = log_page(&repo.repo, query, cursor.as_ref(), size).unwrap();
+            assert!(page.entries.len() <= size);
+            out.extend(page.entries);
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => return out,
+            }
+        }
+    }
+
+    fn ids(entries: &[LogEntry]) -> Vec<String> {
+        entries.iter().map(|e| e.id.clone()).collect()
+    }
+
+    #[test]
+    fn pages_join_into_the_full_walk() {
+        let repo = branchy_repo();
+        for all_branches in [false, true] {
+            let query = LogQuery { all_branches, ..LogQuery::default() };
+            let expected: Vec<String> = full_walk(&repo, all_branches).iter().map(|o| o.to_string()).collect();
+            assert_eq!(expected.len(), if all_branches { 8 } else { 6 });
+            let full = log_page(&repo.repo, &query, None, 10_000).unwrap();
+            assert!(full.next.is_none());
+            assert_eq!(ids(&full.entries), expected);
+            for size in [1, 2, 3, 5] {
+                let entries = paged(&repo, &query, size);
+                let joined = ids(&entries);
+                assert_eq!(joined, expected, "all_branches={all_branches} size={size}");
+                assert_eq!(joined.iter().collect::<HashSet<_>>().len(), joined.len());
+                // Los carriles siguen de una página a otra: el grafo es el mismo que sin paginar.
+                let graph: Vec<_> = entries.iter().map(|e| e.graph.clone()).collect();
+                let full_graph: Vec<_> = full.entries.iter().map(|e| e.graph.clone()).collect();
+                assert_eq!(graph, full_graph, "all_branches={all_branches} size={size}");
+            }
+        }
+    }
+
+    #[test]
+    fn filtered_pages_join_into_the_filtered_walk() {
+        let repo = branchy_repo();
+        let r = repo.git();
+        let queries = [
+            LogQuery { author: "beto".into(), ..LogQuery::default() },
+            LogQuery { author: "beto".into(), all_branches: true, ..LogQuery::default() },
+            LogQuery { path: "b.txt".into(), ..LogQuery::default() },
+            LogQuery { path: "./b.txt".into(), all_branches: true, ..LogQuery::default() },
+        ];
+        for query in queries {
+            let expected: Vec<String> = full_walk(&repo, query.all_branches)
+                .into_iter()
+                .filter(|oid| {
+                    let commit = r.find_commit(*oid).unwrap();
+                    if query.author.is_empty() {
+                        touches_path(r, &commit, "b.txt").unwrap()
+                    } else {
+                        commit.author().name() == Some("Beto")
+                    }
+                })
+                .map(|o| o.to_string())
+                .collect();
+            assert!(!expected.is_empty());
+            let full = log_page(&repo.repo, &query, None, 10_000).unwrap();
+            assert_eq!(ids(&full.entries), expected, "{query:?}");
+            assert!(full.entries.iter().all(|e| e.graph.is_none()));
+            for size in [1, 2, 3, 5] {
+                let joined = ids(&paged(&repo, &query, size));
+                assert_eq!(joined, expected, "{query:?} size={size}");
+                assert_eq!(joined.iter().collect::<HashSet<_>>().len(), joined.len());
+            }
+        }
+    }
+
+    #[test]
+    fn empty_repo_has_no_pages() {
+        let repo = TempRepo::new();
+        let page = log_page(&repo.repo, &LogQuery { all_branches: true, ..LogQuery::default() }, None, 5).unwrap();
+        assert!(page.entries.is_empty());
+        assert!(page.next.is_none());
+    }
+
+    #[test]
+    fn commit_detail_sums_file_stats_and_resolves_revisions() {
+        let repo = TempRepo::new();
+        repo.write("borrar.txt", "x\n");
+        repo.write("mover.txt", "una línea bastante larga para detectar el renombrado\n".repeat(5).as_str());
+        repo.commit_file("a.txt", "1\n2\n3\n", "inicial");
+        std::fs::remove_file(repo.path().join("borrar.txt")).unwrap();
+        std::fs::rename(repo.path().join("mover.txt"), repo.path().join("movido.txt")).unwrap();
+        repo.write("a.txt", "1\ndos\n3\n4\n");
+        repo.write("logo.bin", "\0\x01\x02binario");
+        let r = repo.git();
+        let mut index = r.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        let tree = r.find_tree(index.write_tree().unwrap()).unwrap();
+        index.write().unwrap();
+        let author = git2::Signature::new("Ana", "ana@example.com", &git2::Time::new(1_700_000_000, 120)).unwrap();
+        let head = r.head().unwrap().peel_to_commit().unwrap();
+        let id = r.commit(Some("HEAD"), &author, &author, "Cambios varios\n\nCon cuerpo.\n", &tree, &[&head]).unwrap();
+        r.reference("refs/tags/v1", id, false, "").unwrap();
+
+        let detail = commit_detail(&repo.repo, &id.to_string()).unwrap();
+        assert_eq!((detail.id.as_str(), detail.id_short.as_str()), (id.to_string().as_str(), &id.to_string()[..7]));
+        assert_eq!(detail.parents, [head.id().to_string()]);
+        assert_eq!((detail.summary.as_str(), detail.message.as_str()), ("Cambios varios", "Cambios varios\n\nCon cuerpo."));
+        assert_eq!((detail.author.name.as_str(), detail.author.offset_minutes), ("Ana", 120));
+        assert_eq!(detail.author.date, "2023-11-15 00:13 +0200");
+        assert_eq!(detail.refs, ["main", "tag: v1"]);
+        assert!(detail.signature.is_none());
+
+        let files: Vec<(&str, &str, usize, usize, bool)> = detail
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status.as_str(), f.insertions, f.deletions, f.binary))
+            .collect();
+        assert_eq!(
+            files,
+            [
+                ("a.txt", "modified", 2, 1, false),
+                ("borrar.txt", "deleted", 0, 1, false),
+                ("logo.bin", "added", 0, 0, true),
+                ("movido.txt", "renamed", 0, 0, false),
+            ]
+        );
+        assert_eq!(detail.files[3].old_path.as_deref(), Some("mover.txt"));
+        assert_eq!((detail.insertions, detail.deletions), (2, 2));
+
+        // Prefijo, rama, tag y revisión relativa llevan al mismo commit.
+        for rev in [&id.to_string()[..10], "main", "v1", " HEAD "] {
+            assert_eq!(commit_detail(&repo.repo, rev).unwrap().id, id.to_string(), "{rev}");
+        }
+        assert_eq!(commit_detail(&repo.repo, "HEAD~1").unwrap().summary, "inicial");
+        assert_eq!(commit_detail(&repo.repo, "HEAD~1").unwrap().files.len(), 3);
+        assert!(matches!(commit_detail(&repo.repo, "no-existe"), Err(GitError::CommitNotFound(rev)) if rev == "no-existe"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Hooks de git que corren al hacer commit (`pre-commit`, `prepare-commit-msg`, `commit-msg`,
+//! `post-commit`): carpeta de hooks (respetando `core.hooksPath`, como husky) y ejecución con la
+//! salida línea a línea y cancelable.
+
+use crate::git::{CancelToken, GitError, GitRepo};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Hooks que ejecuta `git commit`, en orden. Con `--no-verify` se saltan `pre-commit` y `commit-msg`.
+pub const COMMIT_HOOKS: &[&str] = &["pre-commit", "prepare-commit-msg", "commit-msg", "post-commit"];
+
+/// Tiempo que se sigue leyendo la salida de un hook después de que termine.
+const HOOK_OUTPUT_GRACE: Duration = Duration::from_millis(200);
+
+/// Hooks que `--no-verify` salta.
+pub(crate) const VERIFY_HOOKS: &[&str] = &["pre-commit", "commit-msg"];
+
+/// Destino de la salida de los hooks y token para cancelarlos.
+#[derive(Clone, Copy)]
+pub struct HookSink<'a> {
+    on_output: &'a dyn Fn(&str),
+    cancel: &'a CancelToken,
+}
+
+impl<'a> HookSink<'a> {
+    pub fn new(on_output: &'a dyn Fn(&str), cancel: &'a CancelToken) -> Self {
+        HookSink { on_output, cancel }
+    }
+
+    pub(crate) fn output(&self, line: &str) {
+        (self.on_output)(line);
+    }
+}
+
+/// Carpeta de hooks: `core.hooksPath` (relativa a la raíz del repo) o `.git/hooks`.
+pub fn hooks_dir(repo: &GitRepo) -> Result<PathBuf, GitError> {
+    let r = &repo.0;
+    match r.config()?.get_path("core.hooksPath") {
+        Ok(path) if path.is_relative() => Ok(r.workdir().unwrap_or_else(|| r.path()).join(path)),
+        Ok(path) => Ok(path),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(common_dir(r).join("hooks")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `.git` compartido: en un worktree enlazado, el del repo principal (lo indica su archivo `commondir`).
+fn common_dir(r: &git2::Repository) -> PathBuf {
+    let dir = r.path();
+    match std::fs::read_to_string(dir.join("commondir")) {
+        Ok(common) if r.is_worktree() => dir.join(common.trim()),
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// Ruta del hook `name` si existe y es ejecutable (git ignora los que no lo son).
+pub fn find_hook(repo: &GitRepo, name: &str) -> Result<Option<PathBuf>, GitError> {
+    let path = hooks_dir(repo)?.join(name);
+    Ok(is_executable(&path).then_some(path))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Hooks de commit que se ejecutarían con estas opciones (los que existen en el repo).
+pub fn commit_hooks(repo: &GitRepo, no_verify: bool) -> Result<Vec<String>, GitError> {
+    let mut hooks = Vec::new();
+    for name in COMMIT_HOOKS {
+        if no_verify && VERIFY_HOOKS.contains(name) {
+            continue;
+        }
+        if find_hook(repo, name)?.is_some() {
+            hooks.push(name.to_string());
+        }
+    }
+    Ok(hooks)
+}
+
+/// Ejecuta el hook `name` con `args` desde la raíz del repo, mandando stdout y stderr a `sink`.
+/// Devuelve `false` si el repo no tiene ese hook y `HookFailed` si termina con error.
+pub(crate) fn run_hook(repo: &GitRepo, name: &str, args: &[&str], sink: HookSink<'_>) -> Result<bool, GitError> {
+    let Some(path) = find_hook(repo, name)? else {
+        return Ok(false);
+    };
+    let r = &repo.0;
+    let workdir = r.workdir().unwrap_or_else(|| r.path());
+    sink.output(format!("> {} {}", name, args.join(" ")).trim_end());
+    // En Windows los hooks son scripts sh: se lanzan con el `sh` de Git for Windows.
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("sh");
+        cmd.arg(&path);
+        cmd
+    } else {
+        Command::new(&path)
+    };
+    cmd.args(args)
+        .current_dir(workdir)
+        .env("GIT_INDEX_FILE", r.path().join("index"))
+        .env("GIT_EDITOR", ":")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let failed = |e: std::io::Error| GitError::HookFailed(name.to_string(), e.to_string());
+    let mut child = cmd.spawn().map_err(failed)?;
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                let _ = tx.send(line);
+            }
+        });
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                let _ = tx.send(line);
+            }
+        });
+    }
+    drop(tx);
+    let status = loop {
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(line) => sink.output(&line),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            // Salida cerrada: ya solo se espera a que el hook termine (sondeando, para poder cancelar).
+            Err(mpsc::RecvTimeoutError::Disconnected) => std::thread::sleep(Duration::from_millis(100)),
+        }
+        if sink.cancel.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(GitError::Cancelled);
+        }
+        if let Some(status) = child.try_wait().map_err(failed)? {
+            // Un proceso que el hook dejó en segundo plano puede heredar sus pipes y no cerrarlos nunca:
+            // se recoge lo que quede por un momento y no se espera a que se cierren.
+            let deadline = Instant::now() + HOOK_OUTPUT_GRACE;
+            while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+                match rx.recv_timeout(left) {
+                    Ok(line) => sink.output(&line),
+                    Err(_) => break,
+                }
+            }
+            break status;
+        }
+    };
+    if status.success() {
+        return Ok(true);
+    }
+    let reason = match status.code() {
+        Some(code) => format!("exit code {}", code),
+        None => "killed by a signal".to_string(),
+    };
+    Err(GitError::HookFailed(name.to_string(), reason))
+}
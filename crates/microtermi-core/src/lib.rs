@@ -1,18 +1,59 @@
+pub mod branch;
+pub mod commit_msg;
+pub mod credentials;
+pub mod diff;
 pub mod discovery;
 pub mod env;
 pub mod git;
 pub mod gitlab;
+pub mod history;
+pub mod hooks;
 pub mod scripts;
+pub mod signing;
+pub mod tag;
+pub mod workspace;
 
+#[cfg(test)]
+mod test_support;
+
+pub use branch::{branch_upstream, create_branch, delete_branch, rename_branch, set_upstream};
+pub use commit_msg::{
+    commit_scopes, commit_template, lint_commit_message, CommitLintRules, ConventionalHeader, LintLevel, LintProblem,
+    LintRule, CONVENTIONAL_TYPES,
+};
+pub use credentials::{
+    delete_gitlab_token, load_gitlab_token, resolve_gitlab_token, save_gitlab_token, set_session_gitlab_token,
+    set_session_gitlab_url, store_gitlab_token, CredentialError, GitlabTokenSource,
+};
+pub use diff::{diff, stage_hunk, unstage_hunk, DiffHunk, DiffLine, DiffLineKind, DiffTarget, FileDiff};
 pub use discovery::{scan_projects, Project};
-pub use env::{load_env, save_env, Environment};
+pub use env::{
+    age_key_file, encrypt_env, env_encryption, export_env, import_env, is_secret_key, load_env,
+    load_env_schema, redact_secrets, save_env, secret_values, validate_env, validate_env_value,
+    EnvEncryption, EnvFormat, EnvSchema, EnvVarIssue, EnvVarSpec, EnvVarType, Environment,
+    DEFAULT_SECRET_PATTERNS,
+};
 pub use git::{
-    branches, branches_remote, checkout_branch, checkout_remote_branch, clone_repo, commit,
-    commit_changes, fetch, log, open_repo, push, pull, stash, stash_pop, status, CommitFileChange,
-    CommitInfo, GitRepo, GitStatus,
+    blame, branches, branches_remote, checkout_branch, checkout_remote_branch, cherry_pick, clone_repo, commit,
+    commit_changes, conflicted_paths, current_branch, discard, fetch, log, merge_abort,
+    merge_continue, merge_in_progress, open_repo, pick_abort, pick_continue, pick_in_progress, pull, pull_rebase, push, rebase_abort,
+    rebase_continue, rebase_in_progress, rebase_skip, revert, stage, stash, stash_apply, stash_drop,
+    stash_list, stash_pop, status, tracked_files, unstage, BlameHunk, CancelToken, CheckoutMode, CommitFileChange, CommitOptions,
+    CommitInfo, FileChange, FileChangeKind, GitError, GitProgress, GitRepo, GitStatus,
+    PickOutcome, ProgressSink, PullOutcome, PushOptions, PushOutcome, StashEntry, StashOptions,
 };
-pub use gitlab::{clone_url_with_token, list_branches, list_projects, GitLabBranch, GitLabError, GitLabProject};
+pub use gitlab::{list_branches, list_projects, GitLabBranch, GitLabError, GitLabProject};
+pub use hooks::{commit_hooks, find_hook, hooks_dir, HookSink, COMMIT_HOOKS};
+pub use history::{commit_detail, log_page, CommitDetail, CommitPerson, GraphRow, LogCursor, LogEntry, LogPage, LogQuery};
 pub use scripts::{
     detect_package_manager, run_script, run_script_captured, run_scripts, PackageManager,
     ScriptRunMode,
 };
+pub use signing::{verify_commit_signature, SignatureCheck, SignatureFormat, SignatureStatus};
+pub use tag::{
+    create_tag, delete_tag, list_tags, next_release_tag, push_tags, ReleaseTag, TagInfo, VersionBump,
+};
+pub use workspace::{
+    for_each_repo, repo_root, repo_roots, repo_summaries, switch_branch_in_repos,
+    BranchSwitchOutcome, BranchSwitchResult, RepoSummary,
+};
@@ -0,0 +1,476 @@
+//! Firma de commits como `git commit -S`: según `commit.gpgsign`, `gpg.format` (openpgp, ssh o x509)
+//! y `user.signingkey`, con el mismo programa que usaría git (`gpg`, `ssh-keygen`, `gpgsm`). También
+//! verifica la firma de un commit para mostrarla en el historial.
+
+use crate::git::{find_commit, GitError, GitRepo};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Formato de firma (`gpg.format`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureFormat {
+    OpenPgp,
+    Ssh,
+    X509,
+}
+
+impl SignatureFormat {
+    /// Formato de una firma ya hecha, por su cabecera ASCII armor.
+    fn detect(signature: &str) -> Option<SignatureFormat> {
+        let first = signature.lines().next()?.trim();
+        match first {
+            "-----BEGIN PGP SIGNATURE-----" => Some(SignatureFormat::OpenPgp),
+            "-----BEGIN SSH SIGNATURE-----" => Some(SignatureFormat::Ssh),
+            "-----BEGIN SIGNED MESSAGE-----" => Some(SignatureFormat::X509),
+            _ => None,
+        }
+    }
+
+    fn config_name(self) -> &'static str {
+        match self {
+            SignatureFormat::OpenPgp => "openpgp",
+            SignatureFormat::Ssh => "ssh",
+            SignatureFormat::X509 => "x509",
+        }
+    }
+
+    /// Programa configurado (`gpg.<formato>.program`, o `gpg.program` para openpgp) o el de por defecto.
+    fn program(self, config: &git2::Config) -> String {
+        let configured = config.get_string(&format!("gpg.{}.program", self.config_name())).ok();
+        let legacy = (self == SignatureFormat::OpenPgp).then(|| config.get_string("gpg.program").ok()).flatten();
+        configured.or(legacy).unwrap_or_else(|| {
+            match self {
+                SignatureFormat::OpenPgp => "gpg",
+                SignatureFormat::Ssh => "ssh-keygen",
+                SignatureFormat::X509 => "gpgsm",
+            }
+            .to_string()
+        })
+    }
+}
+
+/// Cómo firmar los commits del repo, leído de su configuración.
+pub(crate) struct SigningConfig {
+    format: SignatureFormat,
+    program: String,
+    key: Option<String>,
+}
+
+/// Configuración de firma si el repo tiene `commit.gpgsign = true`; `None` si los commits van sin firmar.
+pub(crate) fn signing_config(r: &git2::Repository) -> Result<Option<SigningConfig>, GitError> {
+    let config = r.config()?;
+    if !config.get_bool("commit.gpgsign").unwrap_or(false) {
+        return Ok(None);
+    }
+    let format = match config.get_string("gpg.format").as_deref() {
+        Ok("ssh") => SignatureFormat::Ssh,
+        Ok("x509") => SignatureFormat::X509,
+        Ok("openpgp") | Err(_) => SignatureFormat::OpenPgp,
+        Ok(other) => return Err(GitError::Signing(format!("unsupported gpg.format '{}'", other))),
+    };
+    Ok(Some(SigningConfig {
+        format,
+        program: format.program(&config),
+        key: config.get_string("user.signingkey").ok().filter(|k| !k.trim().is_empty()),
+    }))
+}
+
+/// Archivo temporal que se borra al soltarlo (las firmas SSH van por archivo, no por stdin).
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(label: &str, content: &[u8]) -> Result<TempFile, GitError> {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+        let path = std::env::temp_dir().join(format!("microtermi-{}-{}-{}", label, std::process::id(), nanos));
+        let failed = |e: std::io::Error| GitError::Signing(format!("{}: {}", path.display(), e));
+        // `create_new` no sigue un enlace que otro usuario haya dejado en esa ruta, y en unix solo el
+        // dueño puede leer el contenido del commit o la clave.
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path).map_err(failed)?;
+        let temp = TempFile(path.clone());
+        file.write_all(content).map_err(failed)?;
+        Ok(temp)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+        let _ = std::fs::remove_file(self.0.with_extension("sig"));
+    }
+}
+
+/// Salida de un programa de firma/verificación: si terminó bien, stdout y stderr.
+struct ToolOutput {
+    success: bool,
+    stdout: String,
+    stderr: String,
+}
+
+fn run_tool(program: &str, args: &[&str], input: &[u8]) -> Result<ToolOutput, GitError> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| GitError::Signing(format!("{}: {}", program, e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Si el programa no lee la entrada (ej. ssh-keygen con archivo) el pipe roto no es un error.
+        let _ = stdin.write_all(input);
+    }
+    let output = child.wait_with_output().map_err(|e| GitError::Signing(format!("{}: {}", program, e)))?;
+    Ok(ToolOutput {
+        success: output.status.success(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    })
+}
+
+/// Firma el contenido de un commit; `committer` ("Nombre <email>") es la clave si no hay `user.signingkey`
+/// (como en git, solo para openpgp y x509).
+pub(crate) fn sign_buffer(signing: &SigningConfig, committer: &str, buffer: &str) -> Result<String, GitError> {
+    let program = signing.program.as_str();
+    let output = match signing.format {
+        SignatureFormat::OpenPgp | SignatureFormat::X509 => {
+            let key = signing.key.as_deref().unwrap_or(committer);
+            run_tool(program, &["--status-fd=2", "-bsau", key], buffer.as_bytes())?
+        }
+        SignatureFormat::Ssh => {
+            let key = signing
+                .key
+                .as_deref()
+                .ok_or_else(|| GitError::Signing("user.signingkey is not set (required with gpg.format=ssh)".to_string()))?;
+            let payload = TempFile::new("commit", buffer.as_bytes())?;
+            let payload_path = payload.0.to_string_lossy().to_string();
+            // Una clave pública literal ("ssh-ed25519 AAAA…" o "key::…") firma con la clave del agente.
+            let literal = key.strip_prefix("key::").or_else(|| key.starts_with("ssh-").then_some(key));
+            let key_file = literal.map(|k| TempFile::new("signingkey", k.as_bytes())).transpose()?;
+            let key_path = match &key_file {
+                Some(file) => file.0.to_string_lossy().to_string(),
+                None => expand_home(key).to_string_lossy().to_string(),
+            };
+            let mut args = vec!["-Y", "sign", "-n", "git", "-f", key_path.as_str()];
+            if key_file.is_some() {
+                args.push("-U");
+            }
+            args.push(&payload_path);
+            let mut output = run_tool(program, &args, &[])?;
+            if output.success {
+                output.stdout = std::fs::read_to_string(payload.0.with_extension("sig"))
+                    .map_err(|e| GitError::Signing(format!("{}: {}", program, e)))?;
+            }
+            output
+        }
+    };
+    if !output.success || output.stdout.trim().is_empty() {
+        let detail = if output.stderr.is_empty() { "no signature produced".to_string() } else { output.stderr };
+        return Err(GitError::Signing(format!("{}: {}", program, detail)));
+    }
+    Ok(output.stdout)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Resultado de verificar una firma.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// Firma correcta de una clave conocida.
+    Good,
+    /// La firma no corresponde al contenido del commit.
+    Bad,
+    /// Firma válida en su formato, pero de una clave que no se puede comprobar (no importada o fuera
+    /// de `gpg.ssh.allowedSignersFile`).
+    UnknownKey,
+    /// No se pudo verificar (falta el programa, formato desconocido…).
+    Error,
+}
+
+/// Verificación de la firma de un commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SignatureCheck {
+    pub format: Option<SignatureFormat>,
+    pub status: SignatureStatus,
+    /// Firmante (uid de gpg o principal SSH) si se conoce.
+    pub signer: Option<String>,
+    /// Id o huella de la clave.
+    pub key: Option<String>,
+    /// Salida del programa de verificación.
+    pub detail: String,
+}
+
+/// Verifica la firma del commit `rev`; `None` si no está firmado.
+pub fn verify_commit_signature(repo: &GitRepo, rev: &str) -> Result<Option<SignatureCheck>, GitError> {
+    let r = &repo.0;
+    let commit = find_commit(r, rev)?;
+    let (signature, payload) = match r.extract_signature(&commit.id(), None) {
+        Ok(parts) => parts,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let signature = String::from_utf8_lossy(&signature).into_owned();
+    let config = r.config()?;
+    let check = match SignatureFormat::detect(&signature) {
+        Some(format @ (SignatureFormat::OpenPgp | SignatureFormat::X509)) => verify_gpg(format, &format.program(&config), &signature, &payload),
+        Some(SignatureFormat::Ssh) => verify_ssh(&SignatureFormat::Ssh.program(&config), &config, &signature, &payload),
+        None => Ok(SignatureCheck {
+            format: None,
+            status: SignatureStatus::Error,
+            signer: None,
+            key: None,
+            detail: "unknown signature format".to_string(),
+        }),
+    };
+    // Un programa que falta o falla se muestra como firma sin verificar, no como error del historial.
+    Ok(Some(check.unwrap_or_else(|e| SignatureCheck {
+        format: SignatureFormat::detect(&signature),
+        status: SignatureStatus::Error,
+        signer: None,
+        key: None,
+        detail: e.to_string(),
+    })))
+}
+
+/// `gpg`/`gpgsm --verify` leyendo las líneas `[GNUPG:]` de `--status-fd`.
+fn verify_gpg(format: SignatureFormat, program: &str, signature: &str, payload: &[u8]) -> Result<SignatureCheck, GitError> {
+    let sig_file = TempFile::new("signature", signature.as_bytes())?;
+    let sig_path = sig_file.0.to_string_lossy().to_string();
+    let output = run_tool(program, &["--status-fd=1", "--verify", &sig_path, "-"], payload)?;
+    Ok(parse_gpg_status(format, &output.stdout, output.stderr))
+}
+
+/// Resultado de la verificación a partir de las líneas `[GNUPG:]` que `gpg`/`gpgsm` escriben en
+/// `--status-fd`; `detail` es su salida legible.
+fn parse_gpg_status(format: SignatureFormat, status_output: &str, detail: String) -> SignatureCheck {
+    let mut check = SignatureCheck { format: Some(format), status: SignatureStatus::Error, signer: None, key: None, detail };
+    for line in status_output.lines() {
+        let Some(status) = line.strip_prefix("[GNUPG:] ") else { continue };
+        let mut parts = status.splitn(3, ' ');
+        let (keyword, key, rest) = (parts.next().unwrap_or_default(), parts.next(), parts.next());
+        let status = match keyword {
+            "GOODSIG" => SignatureStatus::Good,
+            "BADSIG" => SignatureStatus::Bad,
+            "EXPSIG" | "EXPKEYSIG" | "REVKEYSIG" | "ERRSIG" | "NO_PUBKEY" => SignatureStatus::UnknownKey,
+            "VALIDSIG" => {
+                check.key = key.map(String::from);
+                continue;
+            }
+            _ => continue,
+        };
+        // BADSIG manda sobre lo demás; el resto solo si aún no había resultado.
+        if check.status == SignatureStatus::Error || status == SignatureStatus::Bad {
+            check.status = status;
+        }
+        if check.key.is_none() {
+            check.key = key.map(String::from);
+        }
+        if let Some(uid) = rest.filter(|_| keyword != "ERRSIG") {
+            check.signer = Some(uid.to_string());
+        }
+    }
+    check
+}
+
+/// `ssh-keygen -Y verify` contra `gpg.ssh.allowedSignersFile`; sin él (o si la clave no está) la firma
+/// solo se comprueba con `check-novalidate` y queda como clave desconocida.
+fn verify_ssh(program: &str, config: &git2::Config, signature: &str, payload: &[u8]) -> Result<SignatureCheck, GitError> {
+    let sig_file = TempFile::new("signature", signature.as_bytes())?;
+    let sig_path = sig_file.0.to_string_lossy().to_string();
+    let mut check = SignatureCheck { format: Some(SignatureFormat::Ssh), status: SignatureStatus::Error, signer: None, key: None, detail: String::new() };
+    let allowed = config.get_path("gpg.ssh.allowedSignersFile").ok().map(|p| p.to_string_lossy().to_string());
+    if let Some(allowed) = &allowed {
+        let principals = run_tool(program, &["-Y", "find-principals", "-f", allowed, "-s", &sig_path], &[])?;
+        if let Some(principal) = principals.stdout.lines().next().filter(|_| principals.success) {
+            let output = run_tool(program, &["-Y", "verify", "-f", allowed, "-I", principal, "-n", "git", "-s", &sig_path], payload)?;
+            check.signer = Some(principal.to_string());
+            check.status = if output.success { SignatureStatus::Good } else { SignatureStatus::Bad };
+            check.key = ssh_key_fingerprint(&output.stdout);
+            check.detail = format!("{}{}", output.stdout.trim(), output.stderr);
+            return Ok(check);
+        }
+    }
+    let output = run_tool(program, &["-Y", "check-novalidate", "-n", "git", "-s", &sig_path], payload)?;
+    check.status = if output.success { SignatureStatus::UnknownKey } else { SignatureStatus::Bad };
+    check.key = ssh_key_fingerprint(&output.stdout);
+    check.detail = match allowed {
+        Some(_) => format!("{}{}", output.stdout.trim(), output.stderr),
+        None => "gpg.ssh.allowedSignersFile is not configured".to_string(),
+    };
+    Ok(check)
+}
+
+/// Huella `SHA256:…` de la salida de `ssh-keygen -Y verify/check-novalidate`.
+fn ssh_key_fingerprint(output: &str) -> Option<String> {
+    output.split_whitespace().find(|w| w.starts_with("SHA256:")).map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: &str = "D83C48D6ECFB34A4F7B281AB24AAF6B231862C27";
+
+    fn parse(status_output: &str) -> SignatureCheck {
+        parse_gpg_status(SignatureFormat::OpenPgp, status_output, "gpg: detail".to_string())
+    }
+
+    #[test]
+    fn detect_reads_the_armor_header() {
+        let detect = SignatureFormat::detect;
+        assert_eq!(detect("-----BEGIN PGP SIGNATURE-----\n\niHUEABYK\n-----END PGP SIGNATURE-----\n"), Some(SignatureFormat::OpenPgp));
+        assert_eq!(detect("-----BEGIN SSH SIGNATURE-----\nU1NIU0lH\n-----END SSH SIGNATURE-----\n"), Some(SignatureFormat::Ssh));
+        assert_eq!(detect("-----BEGIN SIGNED MESSAGE-----\nMIAGCSqG\n-----END SIGNED MESSAGE-----\n"), Some(SignatureFormat::X509));
+        assert_eq!(detect("  -----BEGIN PGP SIGNATURE-----  \r\n"), Some(SignatureFormat::OpenPgp));
+        assert_eq!(detect("-----BEGIN PGP MESSAGE-----\n"), None);
+        assert_eq!(detect("iHUEABYK\n-----BEGIN PGP SIGNATURE-----\n"), None);
+        assert_eq!(detect(""), None);
+    }
+
+    #[test]
+    fn good_signature_takes_signer_and_full_fingerprint() {
+        let check = parse(&format!(
+            "[GNUPG:] NEWSIG t@e.st\n\
+             [GNUPG:] KEY_CONSIDERED {fp} 0\n\
+             [GNUPG:] SIG_ID 3oQ8mVbC3S1n 2026-10-18 1792361987\n\
+             [GNUPG:] GOODSIG 24AAF6B231862C27 Tester <t@e.st>\n\
+             [GNUPG:] VALIDSIG {fp} 2026-10-18 1792361987 0 4 0 22 10 00 {fp}\n\
+             [GNUPG:] TRUST_ULTIMATE 0 pgp\n",
+            fp = FINGERPRINT
+        ));
+        assert_eq!(check.status, SignatureStatus::Good);
+        assert_eq!(check.signer.as_deref(), Some("Tester <t@e.st>"));
+        assert_eq!(check.key.as_deref(), Some(FINGERPRINT));
+        assert_eq!(check.format, Some(SignatureFormat::OpenPgp));
+        assert_eq!(check.detail, "gpg: detail");
+    }
+
+    #[test]
+    fn bad_signature() {
+        let check = parse("[GNUPG:] NEWSIG\n[GNUPG:] BADSIG 24AAF6B231862C27 Tester <t@e.st>\n");
+        assert_eq!(check.status, SignatureStatus::Bad);
+        assert_eq!(check.signer.as_deref(), Some("Tester <t@e.st>"));
+        assert_eq!(check.key.as_deref(), Some("24AAF6B231862C27"));
+    }
+
+    #[test]
+    fn bad_signature_wins_over_earlier_results() {
+        let check = parse("[GNUPG:] EXPKEYSIG 24AAF6B231862C27 Tester <t@e.st>\n[GNUPG:] BADSIG 24AAF6B231862C27 Tester <t@e.st>\n");
+        assert_eq!(check.status, SignatureStatus::Bad);
+        let check = parse("[GNUPG:] BADSIG 24AAF6B231862C27 Tester <t@e.st>\n[GNUPG:] EXPKEYSIG 24AAF6B231862C27 Tester <t@e.st>\n");
+        assert_eq!(check.status, SignatureStatus::Bad);
+    }
+
+    #[test]
+    fn missing_public_key_is_unknown_without_signer() {
+        let check = parse(&format!(
+            "[GNUPG:] NEWSIG\n\
+             [GNUPG:] ERRSIG 24AAF6B231862C27 22 10 00 1792361987 9 {}\n\
+             [GNUPG:] NO_PUBKEY 24AAF6B231862C27\n",
+            FINGERPRINT
+        ));
+        assert_eq!(check.status, SignatureStatus::UnknownKey);
+        assert_eq!(check.signer, None);
+        assert_eq!(check.key.as_deref(), Some("24AAF6B231862C27"));
+    }
+
+    #[test]
+    fn expired_key_is_unknown_with_signer() {
+        let check = parse(&format!(
+            "[GNUPG:] EXPKEYSIG 24AAF6B231862C27 Tester <t@e.st>\n\
+             [GNUPG:] VALIDSIG {fp} 2026-10-18 1792361987 0 4 0 22 10 00 {fp}\n",
+            fp = FINGERPRINT
+        ));
+        assert_eq!(check.status, SignatureStatus::UnknownKey);
+        assert_eq!(check.signer.as_deref(), Some("Tester <t@e.st>"));
+        assert_eq!(check.key.as_deref(), Some(FINGERPRINT));
+    }
+
+    #[test]
+    fn no_status_lines_is_an_error() {
+        let check = parse("gpg: verify signatures failed: Unexpected error\n");
+        assert_eq!(check.status, SignatureStatus::Error);
+        assert_eq!((check.signer, check.key), (None, None));
+        assert_eq!(check.detail, "gpg: detail");
+    }
+
+    /// Firma de los commits que crea el rebase (con un `gpg` falso, sin claves reales).
+    #[cfg(unix)]
+    mod rebase {
+        use super::*;
+        use crate::git::{ProgressSink, PullOutcome};
+        use crate::test_support::TempRepo;
+
+        /// Un `gpg` falso que firma cualquier cosa, para probar la firma sin claves reales.
+        fn fake_gpg(repo: &TempRepo) -> tempfile::TempDir {
+            use std::os::unix::fs::PermissionsExt;
+            let dir = tempfile::tempdir().unwrap();
+            let program = dir.path().join("gpg");
+            std::fs::write(
+                &program,
+                "#!/bin/sh\ncat > /dev/null\necho '-----BEGIN PGP SIGNATURE-----'\necho 'ZmFrZQ=='\necho '-----END PGP SIGNATURE-----'\n",
+            )
+            .unwrap();
+            std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+            repo.set_config("commit.gpgsign", "true");
+            repo.set_config("gpg.program", program.to_str().unwrap());
+            dir
+        }
+
+        fn is_signed(repo: &TempRepo, rev: &str) -> bool {
+            let r = repo.git();
+            let id = r.revparse_single(rev).unwrap().id();
+            r.extract_signature(&id, None).is_ok_and(|(sig, _)| SignatureFormat::detect(&String::from_utf8_lossy(&sig)) == Some(SignatureFormat::OpenPgp))
+        }
+
+        #[test]
+        fn pull_rebase_signs_the_rebased_commits() {
+            let origin = TempRepo::new();
+            origin.commit_file("a.txt", "a\n", "init");
+            let local = TempRepo::clone_of(&origin);
+            origin.commit_file("b.txt", "b\n", "upstream");
+            local.commit_file("c.txt", "c\n", "local 1");
+            local.commit_file("d.txt", "d\n", "local 2");
+            let _gpg = fake_gpg(&local);
+            let outcome = crate::git::pull_rebase(&local.repo, ProgressSink::default()).unwrap();
+            assert_eq!(outcome, PullOutcome::Rebased);
+            assert_eq!(local.summary("HEAD"), "local 2");
+            assert_eq!(local.summary("HEAD~1"), "local 1");
+            assert_eq!(local.summary("HEAD~2"), "upstream");
+            assert!(is_signed(&local, "HEAD") && is_signed(&local, "HEAD~1"));
+            assert!(!is_signed(&local, "HEAD~2"));
+            assert_eq!(local.git().head().unwrap().shorthand(), Some("main"));
+            assert_eq!(local.git().state(), git2::RepositoryState::Clean);
+        }
+
+        #[test]
+        fn rebase_continue_signs_the_resolved_step() {
+            let origin = TempRepo::new();
+            origin.commit_file("a.txt", "a\n", "init");
+            let local = TempRepo::clone_of(&origin);
+            origin.commit_file("a.txt", "upstream\n", "upstream");
+            local.commit_file("a.txt", "local\n", "local");
+            let _gpg = fake_gpg(&local);
+            let outcome = crate::git::pull_rebase(&local.repo, ProgressSink::default()).unwrap();
+            assert_eq!(outcome, PullOutcome::Conflicts(vec!["a.txt".to_string()]));
+            local.write("a.txt", "resolved\n");
+            let outcome = crate::git::rebase_continue(&local.repo, ProgressSink::default()).unwrap();
+            assert_eq!(outcome, PullOutcome::Rebased);
+            assert_eq!(local.summary("HEAD"), "local");
+            assert_eq!(local.read("a.txt"), "resolved\n");
+            assert!(is_signed(&local, "HEAD"));
+            assert_eq!(local.git().state(), git2::RepositoryState::Clean);
+        }
+    }
+}
@@ -0,0 +1,371 @@
+//! Tags: listar, crear (ligeros o anotados), borrar y subir a origin, y proponer el siguiente tag de
+//! release `<paquete>@x.y.z` de un proyecto a partir de sus tags y de la versión de su `package.json`.
+
+use crate::git::{find_commit, format_timestamp, remote_callbacks, GitError, GitRepo, ProgressSink};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Tag del repo.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagInfo {
+    pub name: String,
+    /// Commit al que apunta (id completo y corto).
+    pub target: String,
+    pub target_short: String,
+    /// Anotado (objeto tag con mensaje y autor) o ligero (solo una ref).
+    pub annotated: bool,
+    pub message: Option<String>,
+    pub tagger: Option<String>,
+    /// Fecha del tag si es anotado; si no, la del commit.
+    pub date: String,
+    pub time: i64,
+}
+
+/// Tags del repo, del más reciente al más antiguo.
+pub fn list_tags(repo: &GitRepo) -> Result<Vec<TagInfo>, GitError> {
+    let r = &repo.0;
+    let mut out = Vec::new();
+    for name in r.tag_names(None)?.iter().flatten() {
+        let Ok(reference) = r.find_reference(&format!("refs/tags/{}", name)) else { continue };
+        let Ok(commit) = reference.peel_to_commit() else { continue };
+        let tag = reference.peel_to_tag().ok();
+        let tagger = tag.as_ref().and_then(|t| t.tagger());
+        let time = tagger.as_ref().map_or(commit.time().seconds(), |s| s.when().seconds());
+        let target = commit.id().to_string();
+        out.push(TagInfo {
+            name: name.to_string(),
+            target_short: target[..7].to_string(),
+            target,
+            annotated: tag.is_some(),
+            message: tag.as_ref().and_then(|t| t.message()).map(|m| m.trim().to_string()),
+            tagger: tagger.as_ref().and_then(|s| s.name()).map(String::from),
+            date: format_timestamp(time),
+            time,
+        });
+    }
+    out.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| b.name.cmp(&a.name)));
+    Ok(out)
+}
+
+/// Crea el tag `name` en `target` (cualquier revisión; vacío = HEAD). Con `message` no vacío es anotado.
+pub fn create_tag(repo: &GitRepo, name: &str, target: &str, message: Option<&str>) -> Result<(), GitError> {
+    let r = &repo.0;
+    let name = name.trim();
+    if !git2::Tag::is_valid_name(name) {
+        return Err(GitError::InvalidTagName(name.to_string()));
+    }
+    if r.find_reference(&format!("refs/tags/{}", name)).is_ok() {
+        return Err(GitError::TagExists(name.to_string()));
+    }
+    let target = target.trim();
+    let commit = find_commit(r, if target.is_empty() { "HEAD" } else { target })?;
+    match message.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(message) => r.tag(name, commit.as_object(), &r.signature()?, message, false)?,
+        None => r.tag_lightweight(name, commit.as_object(), false)?,
+    };
+    Ok(())
+}
+
+/// Borra el tag local `name` (no el de origin).
+pub fn delete_tag(repo: &GitRepo, name: &str) -> Result<(), GitError> {
+    let r = &repo.0;
+    if r.find_reference(&format!("refs/tags/{}", name)).is_err() {
+        return Err(GitError::TagNotFound(name.to_string()));
+    }
+    r.tag_delete(name)?;
+    Ok(())
+}
+
+/// Sube los tags `names` a origin. Como `git push`, cada tag se trata por separado: los que ya existen en
+/// origin con otro objeto y los que rechace el servidor (hooks…) se devuelven en `PushRejected`, y el resto
+/// se sube igualmente.
+pub fn push_tags(repo: &GitRepo, names: &[String], sink: ProgressSink<'_>) -> Result<(), GitError> {
+    let r = &repo.0;
+    let mut local = Vec::new();
+    for name in names {
+        let refname = format!("refs/tags/{}", name);
+        let oid = r.refname_to_id(&refname).map_err(|_| GitError::TagNotFound(name.clone()))?;
+        local.push((refname, oid));
+    }
+    if local.is_empty() {
+        return Ok(());
+    }
+    let mut remote = r.find_remote("origin")?;
+    // libgit2 aborta el push entero si un solo tag ya existe en origin con otro objeto, así que esos se
+    // separan antes mirando las refs del remoto.
+    let remote_refs: HashMap<String, git2::Oid> = {
+        let connection = remote
+            .connect_auth(git2::Direction::Push, Some(remote_callbacks(r.config()?, sink)), None)
+            .map_err(|e| sink.error(e))?;
+        connection.list()?.iter().map(|head| (head.name().to_string(), head.oid())).collect()
+    };
+    let mut refspecs = Vec::new();
+    let mut already_exists = Vec::new();
+    for (refname, oid) in &local {
+        match remote_refs.get(refname) {
+            // Ya está en origin tal cual.
+            Some(remote_oid) if remote_oid == oid => {}
+            Some(_) => already_exists.push(format!("{} (already exists)", refname)),
+            None => refspecs.push(format!("{0}:{0}", refname)),
+        }
+    }
+    let rejected = std::cell::RefCell::new(already_exists);
+    if !refspecs.is_empty() {
+        let mut callbacks = remote_callbacks(r.config()?, sink);
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(msg) = status {
+                rejected.borrow_mut().push(format!("{} ({})", refname, msg));
+            }
+            Ok(())
+        });
+        let mut opts = git2::PushOptions::new();
+        opts.remote_callbacks(callbacks);
+        remote.push(&refspecs, Some(&mut opts)).map_err(|e| sink.error(e))?;
+    }
+    let rejected = rejected.into_inner();
+    if !rejected.is_empty() {
+        return Err(GitError::PushRejected(rejected));
+    }
+    Ok(())
+}
+
+/// Parte de la versión a subir en el siguiente release.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionBump {
+    Major,
+    Minor,
+    #[default]
+    Patch,
+}
+
+/// Versión semver `x.y.z[-pre]` (se ignora el `+build`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    pre: Option<String>,
+}
+
+impl Version {
+    fn parse(s: &str) -> Option<Version> {
+        let s = s.trim().trim_start_matches('v');
+        let s = s.split('+').next().unwrap_or(s);
+        let (core, pre) = match s.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (s, None),
+        };
+        let mut nums = core.split('.').map(|n| n.parse::<u64>().ok());
+        let version = Version {
+            major: nums.next()??,
+            minor: nums.next()??,
+            patch: nums.next()??,
+            pre,
+        };
+        nums.next().is_none().then_some(version)
+    }
+
+    /// Siguiente versión. Una pre-release pasa a su versión final si el salto no la supera
+    /// (`1.2.0-beta.1` + minor = `1.2.0`).
+    fn bump(&self, bump: VersionBump) -> Version {
+        let (major, minor, patch) = (self.major, self.minor, self.patch);
+        let pre = self.pre.is_some();
+        let (major, minor, patch) = match bump {
+            VersionBump::Major if pre && minor == 0 && patch == 0 => (major, 0, 0),
+            VersionBump::Major => (major + 1, 0, 0),
+            VersionBump::Minor if pre && patch == 0 => (major, minor, 0),
+            VersionBump::Minor => (major, minor + 1, 0),
+            VersionBump::Patch if pre => (major, minor, patch),
+            VersionBump::Patch => (major, minor, patch + 1),
+        };
+        Version { major, minor, patch, pre: None }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                // Una pre-release va antes que la versión final.
+                (None, None) => std::cmp::Ordering::Equal,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (Some(a), Some(b)) => compare_prerelease(a, b),
+            })
+    }
+}
+
+/// Orden semver de dos pre-releases: identificador a identificador (separados por `.`), los numéricos
+/// como números y antes que los alfanuméricos; si uno es prefijo del otro, va antes el más corto.
+fn compare_prerelease(a: &str, b: &str) -> std::cmp::Ordering {
+    let identifier = |id: &str| match id.parse::<u64>() {
+        Ok(n) if id.bytes().all(|c| c.is_ascii_digit()) => (0, n, String::new()),
+        _ => (1, 0, id.to_string()),
+    };
+    a.split('.').map(identifier).cmp(b.split('.').map(identifier))
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tag de release propuesto para un proyecto.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleaseTag {
+    /// `<paquete>@x.y.z`.
+    pub tag: String,
+    pub package: String,
+    pub version: String,
+    /// Último tag de release del paquete, si hay.
+    pub previous: Option<String>,
+    /// Versión del `package.json`.
+    pub package_version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PackageJson {
+    name: Option<String>,
+    version: Option<String>,
+}
+
+/// Propone el siguiente tag `<paquete>@x.y.z` del proyecto en `project_dir` (carpeta con `package.json`).
+/// Si el `package.json` ya tiene una versión mayor que la del último tag (se subió a mano y falta
+/// taggearla) se propone esa; si no, el último tag (o la versión del `package.json`) más `bump`.
+pub fn next_release_tag(repo: &GitRepo, project_dir: &Path, bump: VersionBump) -> Result<ReleaseTag, GitError> {
+    let manifest = project_dir.join("package.json");
+    let package: PackageJson = std::fs::read_to_string(&manifest)
+        .map_err(|e| e.to_string())
+        .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+        .map_err(|e| GitError::PackageJson(format!("{}: {}", manifest.display(), e)))?;
+    let name = package
+        .name
+        .filter(|n| !n.trim().is_empty())
+        .ok_or_else(|| GitError::PackageJson(format!("{}: missing \"name\"", manifest.display())))?;
+    let prefix = format!("{}@", name);
+    let package_version = package.version.as_deref().and_then(Version::parse);
+    let latest = repo
+        .0
+        .tag_names(Some(&format!("{}*", prefix)))?
+        .iter()
+        .flatten()
+        .filter_map(|t| t.strip_prefix(&prefix).and_then(Version::parse))
+        .max();
+    let next = match (&latest, &package_version) {
+        (Some(latest), Some(pkg)) if pkg > latest => pkg.clone(),
+        (Some(latest), _) => latest.bump(bump),
+        (None, Some(pkg)) => pkg.clone(),
+        (None, None) => Version { major: 0, minor: 1, patch: 0, pre: None },
+    };
+    Ok(ReleaseTag {
+        tag: format!("{}{}", prefix, next),
+        package: name,
+        version: next.to_string(),
+        previous: latest.map(|v| format!("{}{}", prefix, v)),
+        package_version: package.version,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempRepo;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!(v("1.2.3"), Version { major: 1, minor: 2, patch: 3, pre: None });
+        assert_eq!(v(" v1.2.3+build.7 "), Version { major: 1, minor: 2, patch: 3, pre: None });
+        assert_eq!(v("1.0.0-beta.2").pre.as_deref(), Some("beta.2"));
+        assert_eq!(v("1.0.0-rc-1+sha.abc").pre.as_deref(), Some("rc-1"));
+        for invalid in ["", "1", "1.2", "1.2.3.4", "1.x.3", "a.b.c", "1.2.-3"] {
+            assert_eq!(Version::parse(invalid), None, "{}", invalid);
+        }
+        assert_eq!(v("2.0.0-alpha.1").to_string(), "2.0.0-alpha.1");
+    }
+
+    #[test]
+    fn bump_versions() {
+        assert_eq!(v("1.2.3").bump(VersionBump::Patch).to_string(), "1.2.4");
+        assert_eq!(v("1.2.3").bump(VersionBump::Minor).to_string(), "1.3.0");
+        assert_eq!(v("1.2.3").bump(VersionBump::Major).to_string(), "2.0.0");
+        // Una pre-release pasa a su versión final si el salto no la supera.
+        assert_eq!(v("1.2.3-rc.1").bump(VersionBump::Patch).to_string(), "1.2.3");
+        assert_eq!(v("1.2.0-beta.1").bump(VersionBump::Minor).to_string(), "1.2.0");
+        assert_eq!(v("1.2.3-beta.1").bump(VersionBump::Minor).to_string(), "1.3.0");
+        assert_eq!(v("2.0.0-alpha.1").bump(VersionBump::Major).to_string(), "2.0.0");
+        assert_eq!(v("2.1.0-alpha.1").bump(VersionBump::Major).to_string(), "3.0.0");
+    }
+
+    #[test]
+    fn semver_ordering() {
+        // El ejemplo de la especificación semver, de menor a mayor.
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1-0",
+            "1.0.1",
+            "1.10.0",
+            "2.0.0",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert!(v("1.0.0-beta.10") > v("1.0.0-beta.2"));
+        assert!(v("1.0.0-2") < v("1.0.0-10"));
+        assert!(v("1.0.0-+1") > v("1.0.0-2"), "+1 no es numérico");
+        assert_eq!(v("1.0.0-rc.1").cmp(&v("v1.0.0-rc.1+build")), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn next_release_tag_starts_from_the_highest_prerelease() {
+        let repo = TempRepo::new();
+        repo.commit_file("pkg/package.json", r#"{ "name": "@acme/ui", "version": "1.0.0-beta.1" }"#, "init");
+        let head = repo.git().head().unwrap().peel_to_commit().unwrap();
+        for tag in ["@acme/ui@1.0.0-beta.2", "@acme/ui@1.0.0-beta.10", "@acme/ui@1.0.0-beta.9", "other@9.0.0"] {
+            repo.git().tag_lightweight(tag, head.as_object(), false).unwrap();
+        }
+        let release = next_release_tag(&repo.repo, &repo.path().join("pkg"), VersionBump::Patch).unwrap();
+        assert_eq!(release.previous.as_deref(), Some("@acme/ui@1.0.0-beta.10"));
+        assert_eq!(release.tag, "@acme/ui@1.0.0");
+        assert_eq!(release.package, "@acme/ui");
+    }
+
+    #[test]
+    fn next_release_tag_prefers_a_manual_package_version() {
+        let repo = TempRepo::new();
+        repo.commit_file("package.json", r#"{ "name": "app", "version": "2.0.0" }"#, "init");
+        let release = next_release_tag(&repo.repo, repo.path(), VersionBump::Minor).unwrap();
+        assert_eq!((release.tag.as_str(), release.previous), ("app@2.0.0", None));
+        let head = repo.git().head().unwrap().peel_to_commit().unwrap();
+        repo.git().tag_lightweight("app@1.4.0", head.as_object(), false).unwrap();
+        assert_eq!(next_release_tag(&repo.repo, repo.path(), VersionBump::Minor).unwrap().tag, "app@2.0.0");
+        repo.git().tag_lightweight("app@2.0.0", head.as_object(), false).unwrap();
+        assert_eq!(next_release_tag(&repo.repo, repo.path(), VersionBump::Minor).unwrap().tag, "app@2.1.0");
+        std::fs::write(repo.path().join("package.json"), "{}").unwrap();
+        assert!(matches!(next_release_tag(&repo.repo, repo.path(), VersionBump::Patch), Err(GitError::PackageJson(_))));
+    }
+}
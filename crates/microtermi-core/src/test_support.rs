@@ -0,0 +1,117 @@
+//! Utilidades de los tests: repos git temporales (con `git2`, sin depender del `git` instalado).
+
+use crate::git::{open_repo, GitRepo};
+use std::path::Path;
+
+/// Repo temporal con la rama `main` y un usuario configurado; se borra al soltarlo.
+pub(crate) struct TempRepo {
+    pub(crate) dir: tempfile::TempDir,
+    pub(crate) repo: GitRepo,
+}
+
+impl TempRepo {
+    pub(crate) fn new() -> TempRepo {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        TempRepo { dir, repo }
+    }
+
+    /// Clona `origin` (con el remoto `origin` apuntando a él) en otra carpeta temporal.
+    pub(crate) fn clone_of(origin: &TempRepo) -> TempRepo {
+        let dir = tempfile::tempdir().unwrap();
+        git2::Repository::clone(origin.path().to_str().unwrap(), dir.path()).unwrap();
+        configure_user(dir.path());
+        let repo = open_repo(dir.path()).unwrap();
+        TempRepo { dir, repo }
+    }
+
+    /// Clon bare de `origin`, para recibir pushes (libgit2 no hace push local a repos con árbol de trabajo).
+    pub(crate) fn bare_clone_of(origin: &TempRepo) -> TempRepo {
+        let dir = tempfile::tempdir().unwrap();
+        git2::build::RepoBuilder::new().bare(true).clone(origin.path().to_str().unwrap(), dir.path()).unwrap();
+        let repo = open_repo(dir.path()).unwrap();
+        TempRepo { dir, repo }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub(crate) fn git(&self) -> &git2::Repository {
+        &self.repo.0
+    }
+
+    pub(crate) fn write(&self, file: &str, content: &str) {
+        let path = self.path().join(file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(path, content).unwrap();
+    }
+
+    pub(crate) fn read(&self, file: &str) -> String {
+        std::fs::read_to_string(self.path().join(file)).unwrap()
+    }
+
+    /// Prepara todo el árbol de trabajo (incluidos borrados) y hace commit en HEAD.
+    pub(crate) fn commit_all(&self, message: &str) -> git2::Oid {
+        let r = self.git();
+        let mut index = r.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        let tree = r.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = r.signature().unwrap();
+        let parent = r.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        r.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap()
+    }
+
+    /// Escribe `file` y hace commit con ese mensaje.
+    pub(crate) fn commit_file(&self, file: &str, content: &str, message: &str) -> git2::Oid {
+        self.write(file, content);
+        self.commit_all(message)
+    }
+
+    /// Crea la rama `name` en HEAD, sin cambiar a ella.
+    pub(crate) fn branch(&self, name: &str) {
+        let r = self.git();
+        r.branch(name, &r.head().unwrap().peel_to_commit().unwrap(), false).unwrap();
+    }
+
+    /// Cambia a la rama `name` descartando los cambios locales.
+    pub(crate) fn checkout(&self, name: &str) {
+        let r = self.git();
+        r.set_head(&format!("refs/heads/{name}")).unwrap();
+        r.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    }
+
+    /// Nombre corto de la rama de HEAD.
+    pub(crate) fn head_branch(&self) -> String {
+        self.git().head().unwrap().shorthand().unwrap().to_string()
+    }
+
+    pub(crate) fn set_config(&self, key: &str, value: &str) {
+        self.git().config().unwrap().set_str(key, value).unwrap();
+    }
+
+    /// Resumen (primera línea) del commit `rev`.
+    pub(crate) fn summary(&self, rev: &str) -> String {
+        self.git().revparse_single(rev).unwrap().peel_to_commit().unwrap().summary().unwrap_or_default().to_string()
+    }
+}
+
+fn init_repo(path: &Path) -> GitRepo {
+    let mut options = git2::RepositoryInitOptions::new();
+    options.initial_head("main");
+    git2::Repository::init_opts(path, &options).unwrap();
+    configure_user(path);
+    open_repo(path).unwrap()
+}
+
+fn configure_user(path: &Path) {
+    let r = git2::Repository::open(path).unwrap();
+    let mut config = r.config().unwrap();
+    config.set_str("user.name", "Tester").unwrap();
+    config.set_str("user.email", "tester@example.com").unwrap();
+}
@@ -0,0 +1,321 @@
+//! Operaciones sobre varios repos a la vez (carpeta raíz con un repo independiente por proyecto).
+
+use crate::git::{checkout_branch, checkout_remote_branch, open_repo, status, CheckoutMode, GitError, GitStatus};
+use serde::Serialize;
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// Carpeta de trabajo del repo que contiene `path` (el propio `path` o un padre con `.git`).
+pub fn repo_root(path: &Path) -> Option<PathBuf> {
+    let repo = git2::Repository::discover(path).ok()?;
+    // `components` quita la barra final de workdir para que las rutas se puedan comparar.
+    repo.workdir().map(|w| w.components().collect())
+}
+
+/// Repos que contienen las rutas dadas, sin repetir y ordenados. Las rutas fuera de un repo se ignoran.
+pub fn repo_roots<P: AsRef<Path>>(paths: &[P]) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = paths.iter().filter_map(|p| repo_root(p.as_ref())).collect();
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+/// Hilos como mucho para las operaciones en lote (fetch/pull de muchos repos a la vez satura red y disco).
+const MAX_REPO_WORKERS: usize = 8;
+
+/// Ejecuta `op` en cada repo con un máximo de `MAX_REPO_WORKERS` hilos que van tomando repos de una cola
+/// compartida, y devuelve los resultados en el orden de `paths`. Un pánico en un repo queda como su error.
+pub fn for_each_repo<T, F>(paths: &[PathBuf], op: F) -> Vec<(PathBuf, Result<T, GitError>)>
+where
+    T: Send,
+    F: Fn(&Path) -> Result<T, GitError> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<Result<T, GitError>>>> = paths.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for _ in 0..paths.len().min(MAX_REPO_WORKERS) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = paths.get(i) else {
+                    break;
+                };
+                let result = std::panic::catch_unwind(AssertUnwindSafe(|| op(path)))
+                    .unwrap_or_else(|payload| Err(GitError::WorkerPanicked(panic_message(payload.as_ref()))));
+                *slots[i].lock().unwrap_or_else(PoisonError::into_inner) = Some(result);
+            });
+        }
+    });
+    paths
+        .iter()
+        .cloned()
+        .zip(slots.into_iter().map(|slot| {
+            let result = slot.into_inner().unwrap_or_else(PoisonError::into_inner);
+            result.unwrap_or_else(|| Err(GitError::WorkerPanicked("no result".to_string())))
+        }))
+        .collect()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(msg), _) => msg.to_string(),
+        (_, Some(msg)) => msg.clone(),
+        _ => "unknown panic".to_string(),
+    }
+}
+
+/// Estado de un repo para el panel multi-repo; `error` si no se pudo abrir o leer.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoSummary {
+    pub path: PathBuf,
+    /// Nombre de la carpeta del repo.
+    pub name: String,
+    pub status: Option<GitStatus>,
+    pub error: Option<String>,
+}
+
+/// Estado (rama, cambios, ↑/↓) de cada repo, leído en paralelo.
+pub fn repo_summaries(paths: &[PathBuf]) -> Vec<RepoSummary> {
+    for_each_repo(paths, |p| open_repo(p).and_then(|repo| status(&repo)))
+        .into_iter()
+        .map(|(path, result)| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string());
+            let (status, error) = match result {
+                Ok(st) => (Some(st), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            RepoSummary { path, name, status, error }
+        })
+        .collect()
+}
+
+/// Resultado de cambiar de rama en un repo dentro de `switch_branch_in_repos`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BranchSwitchOutcome {
+    /// Ya estaba en la rama.
+    AlreadyOn,
+    /// Cambió a la rama local existente.
+    Switched,
+    /// Creó la rama local desde `origin/<rama>` (siguiéndola) y cambió a ella.
+    CreatedFromRemote,
+    /// Tiene cambios locales y no se pidió auto-stash: no se tocó.
+    SkippedDirty { changes: usize },
+    /// La rama no existe ni en local ni en origin.
+    NotFound,
+    /// Cambió de rama, pero el auto-stash no se pudo reaplicar (sigue en stash@{0}).
+    StashNotReapplied,
+    Failed { error: String },
+}
+
+impl BranchSwitchOutcome {
+    /// El repo quedó en la rama pedida.
+    pub fn is_on_branch(&self) -> bool {
+        matches!(
+            self,
+            BranchSwitchOutcome::AlreadyOn
+                | BranchSwitchOutcome::Switched
+                | BranchSwitchOutcome::CreatedFromRemote
+                | BranchSwitchOutcome::StashNotReapplied
+        )
+    }
+}
+
+impl std::fmt::Display for BranchSwitchOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BranchSwitchOutcome::AlreadyOn => write!(f, "Already on the branch."),
+            BranchSwitchOutcome::Switched => write!(f, "Switched."),
+            BranchSwitchOutcome::CreatedFromRemote => write!(f, "Created from origin and switched."),
+            BranchSwitchOutcome::SkippedDirty { changes } => {
+                write!(f, "Skipped: {} local change(s); use auto-stash to switch anyway.", changes)
+            }
+            BranchSwitchOutcome::NotFound => write!(f, "Branch not found locally or on origin."),
+            BranchSwitchOutcome::StashNotReapplied => {
+                write!(f, "Switched, but the auto-stash could not be reapplied; it was kept as stash@{{0}}.")
+            }
+            BranchSwitchOutcome::Failed { error } => write!(f, "{}", error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchSwitchResult {
+    pub path: PathBuf,
+    pub outcome: BranchSwitchOutcome,
+}
+
+fn switch_branch(path: &Path, branch: &str, auto_stash: bool) -> Result<BranchSwitchOutcome, GitError> {
+    let mut repo = open_repo(path)?;
+    let st = status(&repo)?;
+    if !st.detached && st.branch == branch {
+        return Ok(BranchSwitchOutcome::AlreadyOn);
+    }
+    if !st.is_clean && !auto_stash {
+        return Ok(BranchSwitchOutcome::SkippedDirty { changes: st.modified.len() + st.untracked.len() });
+    }
+    let mode = if auto_stash { CheckoutMode::AutoStash } else { CheckoutMode::Safe };
+    let r = &repo.0;
+    let (result, outcome) = if r.find_branch(branch, git2::BranchType::Local).is_ok() {
+        (checkout_branch(&mut repo, branch, mode), BranchSwitchOutcome::Switched)
+    } else if r.find_branch(&format!("origin/{}", branch), git2::BranchType::Remote).is_ok() {
+        (checkout_remote_branch(&mut repo, branch, mode), BranchSwitchOutcome::CreatedFromRemote)
+    } else {
+        return Ok(BranchSwitchOutcome::NotFound);
+    };
+    match result {
+        Ok(()) => Ok(outcome),
+        Err(GitError::StashNotReapplied) => Ok(BranchSwitchOutcome::StashNotReapplied),
+        Err(e) => Err(e),
+    }
+}
+
+/// Cambia cada repo a `branch` en paralelo: a la rama local si existe o, si solo existe en remoto,
+/// creándola desde `origin/<branch>` como `checkout_remote_branch`. Los repos con cambios locales se
+/// saltan salvo con `auto_stash`, que los guarda en el stash y los reaplica tras el cambio.
+pub fn switch_branch_in_repos(paths: &[PathBuf], branch: &str, auto_stash: bool) -> Vec<BranchSwitchResult> {
+    let branch = branch.trim();
+    for_each_repo(paths, |p| switch_branch(p, branch, auto_stash))
+        .into_iter()
+        .map(|(path, result)| BranchSwitchResult {
+            path,
+            outcome: result.unwrap_or_else(|e| BranchSwitchOutcome::Failed { error: e.to_string() }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempRepo;
+    use std::time::Duration;
+
+    #[test]
+    fn for_each_repo_keeps_order_bounds_workers_and_catches_panics() {
+        let paths: Vec<PathBuf> = (0..20).map(|i| PathBuf::from(format!("repo-{i}"))).collect();
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let results = for_each_repo(&paths, |p| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(5));
+            running.fetch_sub(1, Ordering::SeqCst);
+            let i: usize = p.to_str().unwrap().trim_start_matches("repo-").parse().unwrap();
+            match i {
+                3 => panic!("falla el repo {i}"),
+                7 => Err(GitError::NoRepo),
+                _ => Ok(i),
+            }
+        });
+        assert_eq!(results.iter().map(|(p, _)| p).collect::<Vec<_>>(), paths.iter().collect::<Vec<_>>());
+        for (i, (_, result)) in results.iter().enumerate() {
+            match (i, result) {
+                (3, Err(GitError::WorkerPanicked(msg))) => assert_eq!(msg, "falla el repo 3"),
+                (7, Err(GitError::NoRepo)) => {}
+                (_, Ok(n)) => assert_eq!(*n, i),
+                other => panic!("{other:?}"),
+            }
+        }
+        assert!(peak.load(Ordering::SeqCst) <= MAX_REPO_WORKERS);
+        assert!(for_each_repo(&[], |_| Ok(())).is_empty());
+    }
+
+    #[test]
+    fn repo_roots_dedups_and_skips_paths_outside_repos() {
+        let a = TempRepo::new();
+        let b = TempRepo::new();
+        a.write("src/main.rs", "");
+        let outside = tempfile::tempdir().unwrap();
+        let roots = repo_roots(&[a.path().join("src/main.rs"), b.path().to_path_buf(), a.path().join("src"), outside.path().to_path_buf()]);
+        let mut expected = vec![repo_root(a.path()).unwrap(), repo_root(b.path()).unwrap()];
+        expected.sort();
+        assert_eq!(roots, expected);
+        // Sin la barra final de `workdir`: se compara igual que la ruta del proyecto.
+        assert_eq!(repo_root(a.path()).unwrap(), a.path().canonicalize().unwrap());
+    }
+
+    #[test]
+    fn repo_summaries_report_errors_per_repo() {
+        let repo = TempRepo::new();
+        repo.commit_file("a.txt", "a\n", "inicial");
+        repo.write("a.txt", "cambiado\n");
+        let not_a_repo = tempfile::tempdir().unwrap();
+        let summaries = repo_summaries(&[repo.path().to_path_buf(), not_a_repo.path().to_path_buf()]);
+        let status = summaries[0].status.as_ref().unwrap();
+        assert_eq!((status.branch.as_str(), status.modified.clone()), ("main", vec!["a.txt".to_string()]));
+        assert_eq!(summaries[0].name, repo.path().file_name().unwrap().to_string_lossy());
+        assert!(summaries[0].error.is_none());
+        assert!(summaries[1].status.is_none());
+        assert_eq!(summaries[1].error.as_deref(), Some("No repository found"));
+    }
+
+    #[test]
+    fn switch_branch_in_repos_reports_each_repo_and_keeps_going() {
+        // Tiene `feature` en local; los clones solo como origin/feature.
+        let local = TempRepo::new();
+        local.commit_file("a.txt", "1\n2\n3\n", "inicial");
+        local.branch("feature");
+        local.checkout("feature");
+        local.commit_file("a.txt", "feature\n2\n3\n", "feature");
+        local.checkout("main");
+        let remote_only = TempRepo::clone_of(&local);
+        let dirty = TempRepo::clone_of(&local);
+        dirty.write("a.txt", "local\n2\n3\n");
+        let already = TempRepo::clone_of(&local);
+        crate::branch::create_branch(&already.repo, "feature", "origin/feature").unwrap();
+        already.checkout("feature");
+        let without = TempRepo::new();
+        without.commit_file("a.txt", "x\n", "otro proyecto");
+        let broken = tempfile::tempdir().unwrap();
+
+        let paths = [local.path(), remote_only.path(), dirty.path(), already.path(), without.path(), broken.path()]
+            .map(Path::to_path_buf);
+        let outcomes: Vec<BranchSwitchOutcome> =
+            switch_branch_in_repos(&paths, " feature ", false).into_iter().map(|r| r.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [
+                BranchSwitchOutcome::Switched,
+                BranchSwitchOutcome::CreatedFromRemote,
+                BranchSwitchOutcome::SkippedDirty { changes: 1 },
+                BranchSwitchOutcome::AlreadyOn,
+                BranchSwitchOutcome::NotFound,
+                BranchSwitchOutcome::Failed { error: "No repository found".to_string() },
+            ]
+        );
+        assert_eq!(outcomes.iter().map(|o| o.is_on_branch()).collect::<Vec<_>>(), [true, true, false, true, false, false]);
+        assert_eq!((local.head_branch(), remote_only.head_branch(), dirty.head_branch()), ("feature".into(), "feature".into(), "main".into()));
+        assert_eq!(dirty.read("a.txt"), "local\n2\n3\n");
+        let config = remote_only.git().config().unwrap();
+        assert_eq!(config.get_string("branch.feature.merge").unwrap(), "refs/heads/feature");
+    }
+
+    #[test]
+    fn switch_branch_in_repos_with_auto_stash_moves_dirty_repos() {
+        let origin = TempRepo::new();
+        origin.write("b.txt", "b\n");
+        origin.commit_file("a.txt", "1\n2\n3\n", "inicial");
+        origin.branch("feature");
+        origin.checkout("feature");
+        origin.commit_file("a.txt", "feature\n2\n3\n", "feature");
+        origin.checkout("main");
+        let clean_stash = TempRepo::clone_of(&origin);
+        clean_stash.write("b.txt", "b local\n");
+        let mut conflicting = TempRepo::clone_of(&origin);
+        conflicting.write("a.txt", "local\n2\n3\n");
+
+        let paths = [clean_stash.path().to_path_buf(), conflicting.path().to_path_buf()];
+        let results = switch_branch_in_repos(&paths, "feature", true);
+        assert_eq!(results[0].outcome, BranchSwitchOutcome::CreatedFromRemote);
+        assert_eq!(results[1].outcome, BranchSwitchOutcome::StashNotReapplied);
+        assert!(results[1].outcome.is_on_branch());
+        assert_eq!(clean_stash.read("b.txt"), "b local\n");
+        assert_eq!(conflicting.head_branch(), "feature");
+        assert_eq!(crate::git::stash_list(&mut conflicting.repo).unwrap().len(), 1);
+    }
+}
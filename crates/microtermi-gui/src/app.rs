@@ -4,6 +4,9 @@ use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 
+/// Commits por página del historial de la pestaña Git.
+const GIT_LOG_PAGE: usize = 100;
+
 /// Pestaña principal de la aplicación.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum MainTab {
@@ -12,21 +15,185 @@ pub(crate) enum MainTab {
     Git,
     MultiRun,
     Coverage,
+    Repos,
 }
 
 /// Una pestaña de terminal: nombre, líneas de salida y proceso (si sigue corriendo).
 /// Si pending_project/pending_script están presentes y no hay child, es un panel placeholder.
 pub(crate) struct TerminalSession {
     pub(crate) name: String,
+    /// Carpeta del proyecto donde corre el script; las rutas `archivo:línea` de la salida son relativas a ella.
+    pub(crate) cwd: Option<PathBuf>,
     pub(crate) lines: Vec<String>,
     pub(crate) child: Option<std::process::Child>,
     pub(crate) receiver: Option<mpsc::Receiver<String>>,
+    /// Valores secretos del entorno con el que se lanzó el proceso; se redactan de su salida.
+    pub(crate) secrets: Vec<String>,
     /// Índice de proyecto para placeholder (panel vacío donde elegir proyecto + script y ejecutar).
     pub(crate) pending_project: Option<usize>,
     /// Script elegido para placeholder.
     pub(crate) pending_script: Option<String>,
 }
 
+/// Cambio de rama en espera de decisión: hay cambios locales que chocan con la rama destino.
+pub(crate) struct PendingCheckout {
+    pub(crate) repo_path: PathBuf,
+    pub(crate) branch: String,
+    /// True si la rama destino es origin/<branch> (checkout_remote_branch).
+    pub(crate) remote: bool,
+    pub(crate) conflicts: Vec<String>,
+    /// El usuario pulsó «Forzar» y falta la confirmación.
+    pub(crate) confirm_force: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BranchFormKind {
+    Create,
+    Rename,
+    Delete,
+    Upstream,
+}
+
+/// Formulario de gestión de ramas abierto bajo el selector de ramas.
+pub(crate) struct BranchForm {
+    pub(crate) repo_path: PathBuf,
+    pub(crate) kind: BranchFormKind,
+    /// Rama a renombrar, borrar o cuyo upstream se cambia.
+    pub(crate) branch: String,
+    /// Nombre nuevo (crear, renombrar) o upstream `origin/rama` (vacío = quitarlo).
+    pub(crate) input: String,
+    /// Crear: rama local, rama remota (`origin/x`) o commit de partida.
+    pub(crate) start_point: String,
+    /// Crear: cambiar a la rama nueva al crearla.
+    pub(crate) checkout: bool,
+    /// Borrar: la rama no está fusionada y el borrado forzado espera confirmación.
+    pub(crate) unmerged: bool,
+}
+
+/// Estado del cuadro de commit, además del mensaje (`commit_message`, que en modo Conventional
+/// Commits es el cuerpo).
+#[derive(Default)]
+pub(crate) struct CommitForm {
+    /// Repo para el que se cargaron la plantilla y las reglas commitlint.
+    pub(crate) repo_path: Option<PathBuf>,
+    /// Raíz del repo, para situar los archivos preparados en los proyectos (scopes sugeridos).
+    pub(crate) workdir: Option<PathBuf>,
+    /// Plantilla de `commit.template`, con la que se rellena el mensaje vacío.
+    pub(crate) template: Option<String>,
+    /// Reglas commitlint del repo; sin configuración se usan las de config-conventional.
+    pub(crate) rules: microtermi_core::CommitLintRules,
+    pub(crate) conventional: bool,
+    pub(crate) kind: String,
+    /// Scope escrito; vacío = los proyectos afectados.
+    pub(crate) scope: String,
+    pub(crate) breaking: bool,
+    pub(crate) subject: String,
+    pub(crate) amend: bool,
+    /// Saltar los hooks `pre-commit` y `commit-msg` (`--no-verify`).
+    pub(crate) no_verify: bool,
+}
+
+/// Commit en segundo plano; si el repo tiene hooks, su salida va a una pestaña de terminal.
+pub(crate) struct CommitTask {
+    pub(crate) repo_path: PathBuf,
+    pub(crate) amend: bool,
+    /// Si corren hooks (solo entonces se puede cancelar).
+    pub(crate) hooks: bool,
+    pub(crate) cancel: microtermi_core::CancelToken,
+    receiver: mpsc::Receiver<Result<(), microtermi_core::GitError>>,
+}
+
+/// Operación en lote sobre los repos marcados en el panel multi-repo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RepoBulkOp {
+    Fetch,
+    Pull,
+    /// Cambiar todos a `branch` (se crea desde origin/<rama> donde solo existe en remoto). Sin
+    /// `auto_stash` los repos con cambios locales se saltan.
+    Checkout { branch: String, auto_stash: bool },
+}
+
+/// Operación en lote en curso; devuelve el resultado de cada repo al terminar.
+pub(crate) struct RepoBulkTask {
+    pub(crate) label: String,
+    pub(crate) cancel: microtermi_core::CancelToken,
+    receiver: mpsc::Receiver<Vec<(PathBuf, Result<String, String>)>>,
+}
+
+/// Operación git de red que corre en un hilo aparte.
+#[derive(Clone)]
+pub(crate) enum GitTaskKind {
+    Fetch,
+    Pull { rebase: bool },
+    Push(microtermi_core::PushOptions),
+    /// Subir estos tags a origin.
+    PushTags(Vec<String>),
+    /// Seguir con un rebase pausado (tras resolver conflictos) o saltar su commit actual.
+    RebaseContinue,
+    RebaseSkip,
+    /// Crear el commit de un merge con los conflictos resueltos.
+    MergeContinue,
+    /// Cherry-pick (o revert) del commit `id`, y terminar uno pausado; crean commits que pueden ir firmados.
+    Pick { id: String, revert: bool },
+    PickContinue { revert: bool },
+    /// Clonar `url` en la ruta de la tarea; con `to_root` el clon pasa a ser el repo de la pestaña Git.
+    Clone { url: String, to_root: bool },
+}
+
+enum GitTaskEvent {
+    Progress(microtermi_core::GitProgress),
+    Done(Result<String, microtermi_core::GitError>),
+}
+
+/// Fetch/pull/push/clone en curso: último progreso recibido y token para cancelarlo.
+pub(crate) struct GitTask {
+    pub(crate) label: String,
+    pub(crate) repo_path: PathBuf,
+    pub(crate) kind: GitTaskKind,
+    pub(crate) progress: Option<microtermi_core::GitProgress>,
+    pub(crate) cancel: microtermi_core::CancelToken,
+    receiver: mpsc::Receiver<GitTaskEvent>,
+}
+
+/// Push que necesita una decisión: publicar la rama sin upstream o forzar tras un rechazo.
+pub(crate) struct PendingPush {
+    pub(crate) repo_path: PathBuf,
+    pub(crate) branch: String,
+    /// Refs rechazadas por el servidor; vacío si el problema es que la rama no tiene upstream.
+    pub(crate) rejected: Vec<String>,
+    /// El usuario pulsó «Forzar con lease…» y falta la confirmación.
+    pub(crate) confirm_force: bool,
+}
+
+/// Ventana de blame: explorador de archivos del repo y quién cambió cada línea del elegido.
+pub(crate) struct BlameView {
+    pub(crate) repo_path: PathBuf,
+    /// Revisión mirada; None = árbol de trabajo (con los cambios sin commit).
+    pub(crate) rev: Option<String>,
+    /// Archivos versionados en `rev` (o en el index), para el explorador.
+    pub(crate) files: Vec<String>,
+    pub(crate) filter: String,
+    /// Archivo abierto (relativo a la raíz del repo).
+    pub(crate) path: Option<String>,
+    pub(crate) hunks: Vec<microtermi_core::BlameHunk>,
+    /// Línea a resaltar y a la que hacer scroll al abrir.
+    pub(crate) line: Option<usize>,
+    pub(crate) scroll_to_line: bool,
+    pub(crate) error: Option<String>,
+}
+
+/// Diff abierto en la ventana de diff: árbol de trabajo, index, un commit o un stash.
+pub(crate) struct DiffView {
+    pub(crate) repo_path: PathBuf,
+    /// Archivo mostrado; None = todos los archivos del diff.
+    pub(crate) path: Option<String>,
+    pub(crate) target: microtermi_core::DiffTarget,
+    pub(crate) files: Vec<microtermi_core::FileDiff>,
+    /// Líneas marcadas para preparar/quitar: (hunk, línea dentro del hunk).
+    pub(crate) selected: HashSet<(usize, usize)>,
+    pub(crate) error: Option<String>,
+}
+
 pub struct MicrotermiApp {
     pub(crate) root_path: Option<PathBuf>,
     pub(crate) projects: Vec<Project>,
@@ -38,13 +205,31 @@ pub struct MicrotermiApp {
     pub(crate) git_branch: Option<String>,
     pub(crate) git_clean: Option<bool>,
     pub(crate) git_modified: Vec<String>,
+    /// Último estado completo del repo de la pestaña Git (upstream, ↑/↓, merge/rebase en curso…).
+    pub(crate) git_status: Option<microtermi_core::GitStatus>,
     pub(crate) pending_run: Option<(usize, String)>,
     /// Env vars for current environment (editable)
     pub(crate) env_vars: HashMap<String, String>,
     /// New env key (for add row)
     pub(crate) env_new_key: String,
     pub(crate) env_new_val: String,
+    /// Esquema de variables (.env.schema / .env.example) de la carpeta raíz, si existe.
+    pub(crate) env_schema: Option<microtermi_core::EnvSchema>,
+    /// Cómo está guardado el fichero del ambiente actual (texto plano, age o SOPS).
+    pub(crate) env_encryption: microtermi_core::EnvEncryption,
+    /// Formato elegido para importar/exportar variables en el editor de env.
+    pub(crate) env_format: microtermi_core::EnvFormat,
+    /// Patrones de claves secretas (ej. `*_TOKEN`): se enmascaran en el editor y se redactan en la terminal.
+    pub(crate) secret_patterns: Vec<String>,
+    /// Claves secretas cuyo valor se muestra en claro en el editor (toggle «Mostrar»).
+    pub(crate) env_revealed: HashSet<String>,
+    /// Texto editable en Settings con los patrones de secretos separados por comas.
+    pub(crate) secret_patterns_input: String,
+    /// Repos (ruta) cuyo Pull se hace con rebase en lugar de merge.
+    pub(crate) pull_rebase_repos: HashSet<String>,
     pub(crate) commit_message: String,
+    pub(crate) commit_form: CommitForm,
+    pub(crate) commit_task: Option<CommitTask>,
     pub(crate) env_needs_refresh: bool,
     /// Una pestaña por proceso; cada una con su salida y su proceso (si sigue corriendo).
     pub(crate) terminal_sessions: Vec<TerminalSession>,
@@ -53,6 +238,10 @@ pub struct MicrotermiApp {
     /// GitLab: URL y token (guardados en config).
     pub(crate) gitlab_url: String,
     pub(crate) gitlab_token: String,
+    /// Opt-in explícito para guardar el token en config.json en lugar del llavero del sistema.
+    pub(crate) gitlab_token_plaintext: bool,
+    /// Token heredado de config.json que no se pudo migrar al llavero: se sigue guardando en claro para no perderlo.
+    pub(crate) gitlab_token_legacy: bool,
     /// Proyectos listados desde GitLab API.
     pub(crate) gitlab_projects: Vec<microtermi_core::GitLabProject>,
     /// Ramas del proyecto GitLab seleccionado.
@@ -75,18 +264,27 @@ pub struct MicrotermiApp {
     pub(crate) gitlab_loading: bool,
     /// Receptor del resultado de list_projects en segundo plano.
     pub(crate) gitlab_receiver: Option<mpsc::Receiver<Result<Vec<microtermi_core::GitLabProject>, microtermi_core::GitLabError>>>,
-    /// Historial de commits del repo local (log).
-    pub(crate) git_log: Vec<microtermi_core::CommitInfo>,
+    /// Historial de commits del repo local, cargado por páginas al hacer scroll.
+    pub(crate) git_log: Vec<microtermi_core::LogEntry>,
+    /// Filtros del historial (se aplican con «Aplicar» o Enter).
+    pub(crate) git_log_query: microtermi_core::LogQuery,
+    /// Dónde sigue el historial; None si ya está todo cargado.
+    pub(crate) git_log_next: Option<microtermi_core::LogCursor>,
+    pub(crate) git_log_error: Option<String>,
+    /// Verificación de las firmas de los commits del historial (por id), que se hace en otro hilo.
+    pub(crate) signature_checks: HashMap<String, microtermi_core::SignatureCheck>,
+    signature_receiver: Option<mpsc::Receiver<(String, microtermi_core::SignatureCheck)>>,
     /// Índice del commit seleccionado en el historial (para ver detalle).
     pub(crate) git_log_selected: Option<usize>,
-    /// Archivos cambiados en el commit seleccionado.
-    pub(crate) git_commit_detail: Vec<microtermi_core::CommitFileChange>,
+    /// Detalle (mensaje, autor, archivos con +/-) del commit seleccionado en el historial.
+    pub(crate) git_commit_detail: Option<microtermi_core::CommitDetail>,
     /// Carpeta usada para Git (Pull/Push/Commit). Si es None, se usa la carpeta raíz.
     pub(crate) git_repo_path: Option<PathBuf>,
     /// Git del proyecto seleccionado en Projects (rama, modificados, ramas locales, log).
     pub(crate) project_git_branch: Option<String>,
     pub(crate) project_git_clean: Option<bool>,
     pub(crate) project_git_modified: Vec<String>,
+    pub(crate) project_git_status: Option<microtermi_core::GitStatus>,
     pub(crate) project_git_local_branches: Vec<String>,
     pub(crate) project_git_remote_branches: Vec<String>,
     pub(crate) project_git_selected_branch: String,
@@ -96,6 +294,53 @@ pub struct MicrotermiApp {
     pub(crate) project_git_commit_detail: Vec<microtermi_core::CommitFileChange>,
     /// Índice del proyecto para el que se cargó project_git_* (para refrescar al cambiar de proyecto).
     pub(crate) project_git_refreshed_for: Option<usize>,
+    /// Checkout bloqueado por cambios locales, a la espera de elegir stash / forzar / cancelar.
+    pub(crate) pending_checkout: Option<PendingCheckout>,
+    /// Operación git de red en curso (una a la vez).
+    pub(crate) git_task: Option<GitTask>,
+    /// Push pendiente de publicar con upstream o de forzar con lease.
+    pub(crate) pending_push: Option<PendingPush>,
+    /// Archivos cuyo descarte de cambios espera confirmación (repo, rutas).
+    pub(crate) pending_discard: Option<(PathBuf, Vec<String>)>,
+    /// Panel multi-repo: estado de cada repo que contiene algún proyecto (sin repetir).
+    pub(crate) repos: Vec<microtermi_core::RepoSummary>,
+    /// Repos marcados para las operaciones en lote.
+    pub(crate) repos_selected: HashSet<PathBuf>,
+    /// Refresco del panel multi-repo en curso (en segundo plano).
+    pub(crate) repos_receiver: Option<mpsc::Receiver<Vec<microtermi_core::RepoSummary>>>,
+    pub(crate) repos_bulk: Option<RepoBulkTask>,
+    /// Resultado por repo de la última operación en lote.
+    pub(crate) repos_results: HashMap<PathBuf, Result<String, String>>,
+    /// Rama a la que cambiar los repos marcados.
+    pub(crate) repos_branch: String,
+    /// Al cambiar de rama en lote, guardar en el stash y reaplicar los cambios locales en vez de saltar el repo.
+    pub(crate) repos_auto_stash: bool,
+    /// Crear/renombrar/borrar rama o cambiar su upstream, a la espera de completar el formulario.
+    pub(crate) branch_form: Option<BranchForm>,
+    /// Stashes del repo de la pestaña Git (stash@{0} primero).
+    pub(crate) git_stashes: Vec<microtermi_core::StashEntry>,
+    /// Formulario «Guardar stash»: mensaje, incluir sin seguimiento y mantener el index.
+    pub(crate) stash_options: microtermi_core::StashOptions,
+    /// Stash cuyo borrado espera confirmación (repo, índice).
+    pub(crate) pending_stash_drop: Option<(PathBuf, usize)>,
+    /// Tags del repo `tags_repo` (se recargan al cambiar de repo o tras crear/borrar).
+    pub(crate) tags: Vec<microtermi_core::TagInfo>,
+    pub(crate) tags_repo: Option<PathBuf>,
+    /// Formulario de nuevo tag: nombre, revisión (vacío = HEAD) y mensaje (vacío = tag ligero).
+    pub(crate) tag_name: String,
+    pub(crate) tag_target: String,
+    pub(crate) tag_message: String,
+    /// Proyecto y salto de versión para proponer el tag de release.
+    pub(crate) release_project: Option<PathBuf>,
+    pub(crate) release_bump: microtermi_core::VersionBump,
+    /// Tag cuyo borrado espera confirmación (repo, nombre).
+    pub(crate) pending_tag_delete: Option<(PathBuf, String)>,
+    /// Ventana de diff abierta, si la hay.
+    pub(crate) diff_view: Option<DiffView>,
+    /// Diff lado a lado (true) o unificado (false).
+    pub(crate) diff_side_by_side: bool,
+    /// Ventana de blame abierta, si la hay.
+    pub(crate) blame_view: Option<BlameView>,
     /// Multi-run: proyectos seleccionados para "Ejecutar en seleccionados".
     pub(crate) multi_run_selected: HashSet<usize>,
     /// Multi-run: script/comando a ejecutar.
@@ -119,16 +364,28 @@ impl Default for MicrotermiApp {
             git_branch: None,
             git_clean: None,
             git_modified: Vec::new(),
+            git_status: None,
             pending_run: None,
             env_vars: HashMap::new(),
             env_new_key: String::new(),
             env_new_val: String::new(),
+            env_schema: None,
+            env_encryption: microtermi_core::EnvEncryption::Plain,
+            env_format: microtermi_core::EnvFormat::Dotenv,
+            secret_patterns: microtermi_core::DEFAULT_SECRET_PATTERNS.iter().map(|s| s.to_string()).collect(),
+            env_revealed: HashSet::new(),
+            secret_patterns_input: microtermi_core::DEFAULT_SECRET_PATTERNS.join(", "),
+            pull_rebase_repos: HashSet::new(),
             commit_message: String::new(),
+            commit_form: CommitForm::default(),
+            commit_task: None,
             env_needs_refresh: false,
             terminal_sessions: Vec::new(),
             selected_terminal_tab: 0,
             gitlab_url: String::new(),
             gitlab_token: String::new(),
+            gitlab_token_plaintext: false,
+            gitlab_token_legacy: false,
             gitlab_projects: Vec::new(),
             gitlab_branches: Vec::new(),
             selected_gitlab_project: None,
@@ -141,12 +398,18 @@ impl Default for MicrotermiApp {
             gitlab_loading: false,
             gitlab_receiver: None,
             git_log: Vec::new(),
+            git_log_query: microtermi_core::LogQuery::default(),
+            git_log_next: None,
+            git_log_error: None,
+            signature_checks: HashMap::new(),
+            signature_receiver: None,
             git_log_selected: None,
-            git_commit_detail: Vec::new(),
+            git_commit_detail: None,
             git_repo_path: None,
             project_git_branch: None,
             project_git_clean: None,
             project_git_modified: Vec::new(),
+            project_git_status: None,
             project_git_local_branches: Vec::new(),
             project_git_remote_branches: Vec::new(),
             project_git_selected_branch: String::new(),
@@ -155,6 +418,32 @@ impl Default for MicrotermiApp {
             project_git_log_selected: None,
             project_git_commit_detail: Vec::new(),
             project_git_refreshed_for: None,
+            pending_checkout: None,
+            git_task: None,
+            pending_push: None,
+            pending_discard: None,
+            repos: Vec::new(),
+            repos_selected: HashSet::new(),
+            repos_receiver: None,
+            repos_bulk: None,
+            repos_results: HashMap::new(),
+            repos_branch: String::new(),
+            repos_auto_stash: false,
+            branch_form: None,
+            git_stashes: Vec::new(),
+            stash_options: microtermi_core::StashOptions::default(),
+            pending_stash_drop: None,
+            tags: Vec::new(),
+            tags_repo: None,
+            tag_name: String::new(),
+            tag_target: String::new(),
+            tag_message: String::new(),
+            release_project: None,
+            release_bump: microtermi_core::VersionBump::Patch,
+            pending_tag_delete: None,
+            diff_view: None,
+            diff_side_by_side: false,
+            blame_view: None,
             multi_run_selected: HashSet::new(),
             multi_run_script: "dev".to_string(),
             multi_run_columns: 2,
@@ -167,6 +456,7 @@ impl MicrotermiApp {
     pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
         let mut app = Self::default();
         let config = crate::config::load_config_json();
+        let mut needs_persist = false;
         if let Some(root) = config.get("last_root").and_then(|v| v.as_str()).map(PathBuf::from) {
             if root.exists() && root.is_dir() {
                 app.root_path = Some(root);
@@ -179,9 +469,21 @@ impl MicrotermiApp {
         if let Some(url) = config.get("gitlab_url").and_then(|v| v.as_str()) {
             app.gitlab_url = url.to_string();
         }
-        if let Some(t) = config.get("gitlab_token").and_then(|v| v.as_str()) {
-            app.gitlab_token = t.to_string();
+        app.gitlab_token_plaintext = config.get("gitlab_token_plaintext").and_then(|v| v.as_bool()).unwrap_or(false);
+        let config_token = config.get("gitlab_token").and_then(|v| v.as_str());
+        // Migración incluida: un token en claro en config.json (sin opt-in) se mueve al llavero.
+        let source = microtermi_core::resolve_gitlab_token(config_token, app.gitlab_token_plaintext);
+        app.gitlab_token = source.token.unwrap_or_default();
+        app.gitlab_token_legacy = source.in_config && !app.gitlab_token_plaintext;
+        let migrated = !app.gitlab_token_plaintext && config_token.is_some_and(|t| !t.trim().is_empty());
+        match source.error {
+            Some(e) if migrated => app.message = format!("No se pudo mover el token de GitLab al llavero: {}", e),
+            Some(e) => app.message = format!("No se pudo leer el token de GitLab del llavero: {}", e),
+            None if migrated => needs_persist = true,
+            None => {}
         }
+        microtermi_core::set_session_gitlab_token(Some(&app.gitlab_token));
+        microtermi_core::set_session_gitlab_url(Some(&app.gitlab_url));
         if let Some(s) = config.get("multi_run_script").and_then(|v| v.as_str()) {
             app.multi_run_script = s.to_string();
         }
@@ -197,9 +499,27 @@ impl MicrotermiApp {
                 }
             }
         }
+        if let Some(arr) = config.get("secret_patterns").and_then(|v| v.as_array()) {
+            app.secret_patterns = arr
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.to_string())
+                .collect();
+            app.secret_patterns_input = app.secret_patterns.join(", ");
+        }
+        if let Some(arr) = config.get("pull_rebase_repos").and_then(|v| v.as_array()) {
+            app.pull_rebase_repos = arr
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.to_string())
+                .collect();
+        }
         if let Some(s) = config.get("run_all_script").and_then(|v| v.as_str()) {
             app.run_all_script = s.to_string();
         }
+        if let Some(b) = config.get("diff_side_by_side").and_then(|v| v.as_bool()) {
+            app.diff_side_by_side = b;
+        }
         if let Some(b) = config.get("run_mode_parallel").and_then(|v| v.as_bool()) {
             app.run_mode_parallel = b;
         }
@@ -219,9 +539,16 @@ impl MicrotermiApp {
                 "git" => MainTab::Git,
                 "multi_run" => MainTab::MultiRun,
                 "coverage" => MainTab::Coverage,
+                "repos" => MainTab::Repos,
                 _ => MainTab::Projects,
             };
         }
+        if app.main_tab == MainTab::Repos {
+            app.refresh_repos();
+        }
+        if needs_persist {
+            app.persist_app_config();
+        }
         app
     }
 
@@ -240,11 +567,13 @@ impl MicrotermiApp {
             MainTab::Git => "git",
             MainTab::MultiRun => "multi_run",
             MainTab::Coverage => "coverage",
+            MainTab::Repos => "repos",
         };
         let json = serde_json::json!({
             "last_root": self.root_path.as_ref().map(|p| p.to_string_lossy().to_string()).unwrap_or_default(),
             "gitlab_url": self.gitlab_url,
-            "gitlab_token": self.gitlab_token,
+            "gitlab_token": if self.gitlab_token_plaintext || self.gitlab_token_legacy { self.gitlab_token.as_str() } else { "" },
+            "gitlab_token_plaintext": self.gitlab_token_plaintext,
             "multi_run_script": self.multi_run_script,
             "multi_run_selected_paths": multi_run_paths,
             "run_all_script": self.run_all_script,
@@ -252,10 +581,32 @@ impl MicrotermiApp {
             "environment": self.environment.as_str(),
             "gitlab_repo_filter": self.gitlab_repo_filter,
             "main_tab": main_tab_str,
+            "secret_patterns": self.secret_patterns,
+            "pull_rebase_repos": self.pull_rebase_repos,
+            "diff_side_by_side": self.diff_side_by_side,
         });
         crate::config::save_config_write(&json);
     }
 
+    /// Guarda URL y token de GitLab: el token va al llavero del sistema salvo opt-in a texto plano.
+    pub(crate) fn save_gitlab_settings(&mut self) {
+        microtermi_core::set_session_gitlab_token(Some(&self.gitlab_token));
+        microtermi_core::set_session_gitlab_url(Some(&self.gitlab_url));
+        let saved = microtermi_core::save_gitlab_token(&self.gitlab_token, self.gitlab_token_plaintext);
+        self.gitlab_token_legacy = false;
+        self.message = match (self.gitlab_token_plaintext, saved) {
+            (true, Ok(())) => "GitLab guardado (token en texto plano en config.json).".to_string(),
+            (true, Err(e)) => format!("GitLab guardado en config.json, pero no se pudo borrar el token del llavero: {}", e),
+            (false, Ok(())) => "GitLab guardado (token en el llavero del sistema).".to_string(),
+            (false, Err(e)) => {
+                // Sin llavero el token se queda en config.json en vez de perderse.
+                self.gitlab_token_legacy = true;
+                format!("Error guardando el token en el llavero (se mantiene en config.json): {}", e)
+            }
+        };
+        self.persist_app_config();
+    }
+
     /// Ruta usada para operaciones Git (repo local). Prioridad: git_repo_path, luego root_path.
     pub(crate) fn git_root(&self) -> Option<PathBuf> {
         self.git_repo_path.clone().or_else(|| self.root_path.clone())
@@ -276,26 +627,30 @@ impl MicrotermiApp {
             None => return,
         };
         match microtermi_core::open_repo(&root) {
-            Ok(repo) => {
+            Ok(mut repo) => {
                 if let Ok(st) = microtermi_core::status(&repo) {
                     self.git_branch = Some(st.branch.clone());
                     self.git_clean = Some(st.is_clean);
-                    self.git_modified = st.modified;
-                    self.git_modified.extend(st.untracked);
+                    self.git_modified = st.modified.clone();
+                    self.git_modified.extend(st.untracked.iter().cloned());
+                    self.git_status = Some(st);
                 }
                 self.refresh_git_branches();
-                self.git_log = microtermi_core::log(&repo, 200).unwrap_or_default();
-                self.git_log_selected = None;
-                self.git_commit_detail.clear();
+                self.load_git_log(false);
+                self.git_stashes = microtermi_core::stash_list(&mut repo).unwrap_or_default();
             }
             Err(_) => {
                 self.git_branch = None;
                 self.git_clean = None;
                 self.git_modified.clear();
+                self.git_status = None;
                 self.git_local_branches.clear();
                 self.git_log.clear();
+                self.git_log_next = None;
+                self.git_log_error = None;
                 self.git_log_selected = None;
-                self.git_commit_detail.clear();
+                self.git_commit_detail = None;
+                self.git_stashes.clear();
             }
         }
     }
@@ -307,12 +662,14 @@ impl MicrotermiApp {
                 if let Ok(st) = microtermi_core::status(&repo) {
                     self.project_git_branch = Some(st.branch.clone());
                     self.project_git_clean = Some(st.is_clean);
-                    self.project_git_modified = st.modified;
-                    self.project_git_modified.extend(st.untracked);
+                    self.project_git_modified = st.modified.clone();
+                    self.project_git_modified.extend(st.untracked.iter().cloned());
+                    self.project_git_status = Some(st);
                 } else {
                     self.project_git_branch = None;
                     self.project_git_clean = None;
                     self.project_git_modified.clear();
+                    self.project_git_status = None;
                 }
                 self.project_git_local_branches = microtermi_core::branches(&repo).unwrap_or_default();
                 self.project_git_remote_branches = microtermi_core::branches_remote(&repo).unwrap_or_default();
@@ -335,6 +692,7 @@ impl MicrotermiApp {
                 self.project_git_branch = None;
                 self.project_git_clean = None;
                 self.project_git_modified.clear();
+                self.project_git_status = None;
                 self.project_git_local_branches.clear();
                 self.project_git_remote_branches.clear();
                 self.project_git_log.clear();
@@ -344,6 +702,89 @@ impl MicrotermiApp {
         }
     }
 
+    /// Carga la primera página del historial (descartando la actual) o, con `more`, la siguiente.
+    pub(crate) fn load_git_log(&mut self, more: bool) {
+        let cursor = if more {
+            match &self.git_log_next {
+                Some(c) => Some(c.clone()),
+                None => return,
+            }
+        } else {
+            None
+        };
+        let Some(root) = self.git_root() else { return };
+        let page = microtermi_core::open_repo(&root)
+            .and_then(|repo| microtermi_core::log_page(&repo, &self.git_log_query, cursor.as_ref(), GIT_LOG_PAGE));
+        if !more {
+            self.git_log.clear();
+            self.git_log_selected = None;
+            self.git_commit_detail = None;
+        }
+        match page {
+            Ok(page) => {
+                self.git_log.extend(page.entries);
+                self.git_log_next = page.next;
+                self.git_log_error = None;
+            }
+            Err(e) => {
+                self.git_log_next = None;
+                self.git_log_error = Some(e.to_string());
+            }
+        }
+        if !more {
+            // Las claves o allowed signers pueden haber cambiado: se vuelve a verificar todo.
+            self.signature_checks.clear();
+        }
+        self.verify_log_signatures(&root);
+    }
+
+    /// Verifica en otro hilo las firmas de los commits firmados del historial aún sin verificar
+    /// (gpg/ssh-keygen tardan demasiado para hacerlo al pintar).
+    fn verify_log_signatures(&mut self, root: &Path) {
+        let pending: Vec<String> = self
+            .git_log
+            .iter()
+            .filter(|c| c.signed && !self.signature_checks.contains_key(&c.id))
+            .map(|c| c.id.clone())
+            .collect();
+        if pending.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let root = root.to_path_buf();
+        std::thread::spawn(move || {
+            let Ok(repo) = microtermi_core::open_repo(&root) else { return };
+            for id in pending {
+                if let Ok(Some(check)) = microtermi_core::verify_commit_signature(&repo, &id) {
+                    // Si se cerró el canal es que se recargó el historial: sobra seguir.
+                    if tx.send((id, check)).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        self.signature_receiver = Some(rx);
+    }
+
+    fn signature_drain(&mut self, ctx: &egui::Context) {
+        let Some(rx) = &self.signature_receiver else { return };
+        loop {
+            match rx.try_recv() {
+                Ok((id, check)) => {
+                    self.signature_checks.insert(id, check);
+                }
+                Err(mpsc::TryRecvError::Empty) => {
+                    ctx.request_repaint_after(std::time::Duration::from_millis(200));
+                    return;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.signature_receiver = None;
+                    return;
+                }
+            }
+        }
+    }
+
     pub(crate) fn refresh_git_branches(&mut self) {
         self.git_local_branches.clear();
         let root = match self.git_root() {
@@ -368,9 +809,83 @@ impl MicrotermiApp {
 
     pub(crate) fn refresh_env(&mut self) {
         if let Some(ref root) = self.root_path {
+            self.env_encryption = microtermi_core::env_encryption(root, self.environment);
             match load_env(root, self.environment) {
                 Ok(vars) => self.env_vars = vars,
-                Err(_) => self.env_vars.clear(),
+                Err(e) => {
+                    self.env_vars.clear();
+                    if self.env_encryption != microtermi_core::EnvEncryption::Plain {
+                        self.message = format!("Error descifrando env: {}", e);
+                    }
+                }
+            }
+            match microtermi_core::load_env_schema(root) {
+                Ok(schema) => self.env_schema = schema,
+                Err(e) => {
+                    self.env_schema = None;
+                    self.message = format!("Error leyendo esquema de env: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Exporta las variables del ambiente actual al formato elegido (diálogo para elegir destino).
+    pub(crate) fn env_export(&mut self) {
+        let name = self
+            .root_path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "env".to_string());
+        let format = self.env_format;
+        let content = match microtermi_core::export_env(&self.env_vars, self.environment, format, &name) {
+            Ok(c) => c,
+            Err(e) => {
+                self.message = format!("Error exportando env: {}", e);
+                return;
+            }
+        };
+        let file_name = format!("{}-{}.{}", name, self.environment.as_str(), format.extension());
+        if let Some(path) = rfd::FileDialog::new().set_file_name(&file_name).save_file() {
+            match std::fs::write(&path, content) {
+                Ok(()) => self.message = format!("Variables exportadas a {}", path.display()),
+                Err(e) => self.message = format!("Error exportando env: {}", e),
+            }
+        }
+    }
+
+    /// Importa variables desde un fichero en el formato elegido y las mezcla con las actuales (sin guardar).
+    pub(crate) fn env_import(&mut self) {
+        let path = match rfd::FileDialog::new().pick_file() {
+            Some(p) => p,
+            None => return,
+        };
+        let result = std::fs::read_to_string(&path)
+            .map_err(microtermi_core::env::EnvError::from)
+            .and_then(|content| microtermi_core::import_env(&content, self.env_format));
+        match result {
+            Ok(vars) => {
+                let count = vars.len();
+                self.env_vars.extend(vars);
+                self.message = format!("{} variable(s) importadas. Pulsa «Guardar en disco» para persistirlas.", count);
+            }
+            Err(e) => self.message = format!("Error importando env: {}", e),
+        }
+    }
+
+    /// Variables a inyectar en un script: validadas contra el esquema (con defaults aplicados).
+    /// Si hay errores, deja la lista por clave en `message` y devuelve None para bloquear la ejecución.
+    pub(crate) fn env_for_run(&mut self) -> Option<HashMap<String, String>> {
+        let schema = match self.env_schema {
+            Some(ref s) => s,
+            None => return Some(self.env_vars.clone()),
+        };
+        match microtermi_core::validate_env(schema, &self.env_vars) {
+            Ok(vars) => Some(vars),
+            Err(issues) => {
+                let list: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+                self.message = format!("Variables de entorno inválidas ({}):\n{}", self.environment.as_str(), list.join("\n"));
+                None
             }
         }
     }
@@ -395,6 +910,11 @@ impl MicrotermiApp {
     }
 
     fn run_script_click(&mut self, project: &Project, script_name: &str) {
+        let env_vars = match self.env_for_run() {
+            Some(v) => v,
+            None => return,
+        };
+        let secrets = microtermi_core::secret_values(&env_vars, &self.secret_patterns);
         self.terminal_sessions.clear();
         self.selected_terminal_tab = 0;
         let header = crate::ansi::strip_ansi(&format!(
@@ -406,13 +926,15 @@ impl MicrotermiApp {
                 microtermi_core::PackageManager::Pnpm => format!("pnpm {}", script_name),
             }
         ));
-        match run_script_captured(project, script_name, &self.env_vars) {
+        match run_script_captured(project, script_name, &env_vars) {
             Ok((child, receiver)) => {
                 self.terminal_sessions.push(TerminalSession {
                     name: format!("{} » {}", project.name, script_name),
+                    cwd: Some(project.path.clone()),
                     lines: vec![header],
                     child: Some(child),
                     receiver: Some(receiver),
+                    secrets,
                     pending_project: None,
                     pending_script: None,
                 });
@@ -421,9 +943,11 @@ impl MicrotermiApp {
             Err(e) => {
                 self.terminal_sessions.push(TerminalSession {
                     name: format!("{} » {}", project.name, script_name),
+                    cwd: Some(project.path.clone()),
                     lines: vec![header, crate::ansi::strip_ansi(&format!("[error] {}", e))],
                     child: None,
                     receiver: None,
+                    secrets: Vec::new(),
                     pending_project: None,
                     pending_script: None,
                 });
@@ -436,7 +960,7 @@ impl MicrotermiApp {
         for session in self.terminal_sessions.iter_mut() {
             if let Some(ref rx) = session.receiver {
                 while let Ok(line) = rx.try_recv() {
-                    session.lines.push(line);
+                    session.lines.push(microtermi_core::redact_secrets(&line, &session.secrets));
                 }
             }
             if let Some(ref mut child) = session.child {
@@ -511,6 +1035,12 @@ impl MicrotermiApp {
             self.message = format!("Ningún proyecto tiene el script \"{}\".", script);
             return;
         }
+        let env_vars = match self.env_for_run() {
+            Some(v) => v,
+            None => return,
+        };
+        let secrets = microtermi_core::secret_values(&env_vars, &self.secret_patterns);
+        let script = self.run_all_script.trim();
         self.terminal_sessions.clear();
         self.selected_terminal_tab = 0;
         let script_owned = script.to_string();
@@ -526,13 +1056,15 @@ impl MicrotermiApp {
                     microtermi_core::PackageManager::Pnpm => format!("pnpm {}", script),
                 }
             );
-            match run_script_captured(project, script, &self.env_vars) {
+            match run_script_captured(project, script, &env_vars) {
                 Ok((child, receiver)) => {
                     self.terminal_sessions.push(TerminalSession {
                         name: format!("{} » {}", project.name, script),
+                        cwd: Some(project.path.clone()),
                         lines: vec![header],
                         child: Some(child),
                         receiver: Some(receiver),
+                        secrets: secrets.clone(),
                         pending_project: Some(proj_idx),
                         pending_script: Some(script_owned.clone()),
                     });
@@ -541,9 +1073,11 @@ impl MicrotermiApp {
                 Err(e) => {
                     self.terminal_sessions.push(TerminalSession {
                         name: format!("{} » {}", project.name, script),
+                        cwd: Some(project.path.clone()),
                         lines: vec![header, crate::ansi::strip_ansi(&format!("[error] {}", e))],
                         child: None,
                         receiver: None,
+                        secrets: Vec::new(),
                         pending_project: Some(proj_idx),
                         pending_script: Some(script_owned.clone()),
                     });
@@ -574,6 +1108,12 @@ impl MicrotermiApp {
             self.message = "Selecciona al menos un proyecto que tenga ese script.".to_string();
             return;
         }
+        let env_vars = match self.env_for_run() {
+            Some(v) => v,
+            None => return,
+        };
+        let secrets = microtermi_core::secret_values(&env_vars, &self.secret_patterns);
+        let script = self.multi_run_script.trim();
         let first_new_tab = self.terminal_sessions.len();
         let mut started = 0;
         for &idx in &selected {
@@ -591,13 +1131,15 @@ impl MicrotermiApp {
                 }
             );
             let script_owned = script.to_string();
-            match run_script_captured(&project, script, &self.env_vars) {
+            match run_script_captured(&project, script, &env_vars) {
                 Ok((child, receiver)) => {
                     self.terminal_sessions.push(TerminalSession {
                         name: format!("{} » {}", project.name, script),
+                        cwd: Some(project.path.clone()),
                         lines: vec![header],
                         child: Some(child),
                         receiver: Some(receiver),
+                        secrets: secrets.clone(),
                         pending_project: Some(idx),
                         pending_script: Some(script_owned),
                     });
@@ -606,9 +1148,11 @@ impl MicrotermiApp {
                 Err(e) => {
                     self.terminal_sessions.push(TerminalSession {
                         name: format!("{} » {}", project.name, script),
+                        cwd: Some(project.path.clone()),
                         lines: vec![header, crate::ansi::strip_ansi(&format!("[error] {}", e))],
                         child: None,
                         receiver: None,
+                        secrets: Vec::new(),
                         pending_project: Some(idx),
                         pending_script: Some(script_owned),
                     });
@@ -625,9 +1169,11 @@ impl MicrotermiApp {
     pub(crate) fn multi_run_add_placeholder(&mut self) {
         self.terminal_sessions.push(TerminalSession {
             name: "Nuevo…".to_string(),
+            cwd: None,
             lines: Vec::new(),
             child: None,
             receiver: None,
+            secrets: Vec::new(),
             pending_project: None,
             pending_script: None,
         });
@@ -660,6 +1206,11 @@ impl MicrotermiApp {
             self.message = format!("El proyecto no tiene el script \"{}\".", script_name);
             return;
         }
+        let env_vars = match self.env_for_run() {
+            Some(v) => v,
+            None => return,
+        };
+        let secrets = microtermi_core::secret_values(&env_vars, &self.secret_patterns);
         let header = format!(
             "> {} » {}",
             project.name,
@@ -669,13 +1220,15 @@ impl MicrotermiApp {
                 microtermi_core::PackageManager::Pnpm => format!("pnpm {}", script_name),
             }
         );
-        match run_script_captured(&project, &script_name, &self.env_vars) {
+        match run_script_captured(&project, &script_name, &env_vars) {
             Ok((child, receiver)) => {
                 self.terminal_sessions[index] = TerminalSession {
                     name: format!("{} » {}", project.name, script_name),
+                    cwd: Some(project.path.clone()),
                     lines: vec![header],
                     child: Some(child),
                     receiver: Some(receiver),
+                    secrets,
                     pending_project: Some(proj_idx),
                     pending_script: Some(script_name.clone()),
                 };
@@ -718,6 +1271,11 @@ impl MicrotermiApp {
             self.message = "El proyecto no tiene script \"test\".".to_string();
             return;
         }
+        let env_vars = match self.env_for_run() {
+            Some(v) => v,
+            None => return,
+        };
+        let secrets = microtermi_core::secret_values(&env_vars, &self.secret_patterns);
         let header = format!(
             "> {} » {}",
             project.name,
@@ -727,13 +1285,15 @@ impl MicrotermiApp {
                 microtermi_core::PackageManager::Pnpm => "pnpm test".to_string(),
             }
         );
-        match run_script_captured(&project, "test", &self.env_vars) {
+        match run_script_captured(&project, "test", &env_vars) {
             Ok((child, receiver)) => {
                 self.terminal_sessions.push(TerminalSession {
                     name: format!("{} » test", project.name),
+                    cwd: Some(project.path.clone()),
                     lines: vec![header],
                     child: Some(child),
                     receiver: Some(receiver),
+                    secrets,
                     pending_project: Some(project_idx),
                     pending_script: Some("test".to_string()),
                 });
@@ -757,6 +1317,7 @@ impl MicrotermiApp {
         let mut close_tab = None;
         let mut run_placeholder = None;
         let mut stop_at = None;
+        let mut open_ref = None;
         if idx >= self.terminal_sessions.len() {
             return (close_tab, run_placeholder, stop_at);
         }
@@ -819,24 +1380,17 @@ impl MicrotermiApp {
                     .stick_to_bottom(true)
                     .auto_shrink([false; 2])
                     .show(ui, |ui| {
-                        let default_color = ui.visuals().text_color();
                         for line in &session.lines {
-                            ui.horizontal_wrapped(|ui| {
-                                for seg in crate::ansi::parse_ansi_line(line) {
-                                    let color = seg.color.unwrap_or(default_color);
-                                    let mut rt = egui::RichText::new(seg.text)
-                                        .font(font_id.clone())
-                                        .color(color);
-                                    if seg.bold {
-                                        rt = rt.strong();
-                                    }
-                                    ui.label(rt);
-                                }
-                            });
+                            if let Some(file_ref) = crate::shared::terminal_line(ui, line, font_id) {
+                                open_ref = Some((session.cwd.clone(), file_ref));
+                            }
                         }
                     });
             }
         });
+        if let Some((cwd, (path, line))) = open_ref {
+            self.open_file_ref(cwd.as_deref(), &path, line);
+        }
         (close_tab, run_placeholder, stop_at)
     }
 
@@ -879,20 +1433,11 @@ impl MicrotermiApp {
             return;
         }
         let proj = &self.gitlab_projects[index];
-        let url = microtermi_core::clone_url_with_token(&proj.http_url_to_repo, &self.gitlab_token);
+        let url = proj.http_url_to_repo.clone();
         if let Some(dir) = rfd::FileDialog::new().set_title("Carpeta donde clonar").pick_folder() {
             let dest = dir.join(proj.path_with_namespace.replace('/', std::path::MAIN_SEPARATOR_STR));
             self.gitlab_status = "Clonando…".to_string();
-            match microtermi_core::clone_repo(&url, &dest) {
-                Ok(_) => {
-                    self.gitlab_status = format!("Clonado en {}", dest.display());
-                    self.message = format!("Repositorio clonado en {}", dest.display());
-                }
-                Err(e) => {
-                    self.gitlab_status = format!("Error al clonar: {}", e);
-                    self.message = format!("Error: {}", e);
-                }
-            }
+            self.start_git_task(GitTaskKind::Clone { url, to_root: false }, &dest);
         }
     }
 
@@ -909,22 +1454,10 @@ impl MicrotermiApp {
             }
         };
         let proj = &self.gitlab_projects[index];
-        let url = microtermi_core::clone_url_with_token(&proj.http_url_to_repo, &self.gitlab_token);
+        let url = proj.http_url_to_repo.clone();
         let dest = root.join(proj.path_with_namespace.replace('/', std::path::MAIN_SEPARATOR_STR));
         self.gitlab_status = "Clonando…".to_string();
-        match microtermi_core::clone_repo(&url, &dest) {
-            Ok(_) => {
-                self.git_repo_path = Some(dest.clone());
-                self.gitlab_status = format!("Clonado en {}", dest.display());
-                self.message = format!("Repositorio clonado. Pull/Push/Commit usarán esta carpeta.");
-                self.refresh_projects();
-                self.refresh_git();
-            }
-            Err(e) => {
-                self.gitlab_status = format!("Error al clonar: {}", e);
-                self.message = format!("Error: {}", e);
-            }
-        }
+        self.start_git_task(GitTaskKind::Clone { url, to_root: true }, &dest);
     }
 
     /// Agrupa proyectos GitLab por el primer segmento del path (grupo). La lista ya viene filtrada por la API si se usó búsqueda.
@@ -945,14 +1478,820 @@ impl MicrotermiApp {
             Some(p) => p,
             None => return,
         };
-        if let Ok(repo) = microtermi_core::open_repo(&root) {
-            match microtermi_core::checkout_branch(&repo, &self.selected_git_branch) {
-                Ok(()) => {
-                    self.message = format!("Rama cambiada a {}", self.selected_git_branch);
+        let branch = self.selected_git_branch.clone();
+        self.checkout(&root, &branch, false, microtermi_core::CheckoutMode::Safe);
+    }
+
+    /// Cambia de rama en el repo indicado. Si hay cambios locales que chocan deja el checkout en
+    /// `pending_checkout` para que el usuario elija (stash, forzar o cancelar).
+    pub(crate) fn checkout(&mut self, repo_path: &Path, branch: &str, remote: bool, mode: microtermi_core::CheckoutMode) {
+        let mut repo = match microtermi_core::open_repo(repo_path) {
+            Ok(r) => r,
+            Err(e) => {
+                self.message = format!("Error: {}", e);
+                return;
+            }
+        };
+        let result = if remote {
+            microtermi_core::checkout_remote_branch(&mut repo, branch, mode)
+        } else {
+            microtermi_core::checkout_branch(&mut repo, branch, mode)
+        };
+        match result {
+            Ok(()) => {
+                self.pending_checkout = None;
+                self.message = format!("Rama cambiada a {}", branch);
+            }
+            Err(microtermi_core::GitError::CheckoutConflicts(conflicts)) => {
+                self.message = format!("Hay cambios locales que se perderían al cambiar a {}.", branch);
+                self.pending_checkout = Some(PendingCheckout {
+                    repo_path: repo_path.to_path_buf(),
+                    branch: branch.to_string(),
+                    remote,
+                    conflicts,
+                    confirm_force: false,
+                });
+                return;
+            }
+            Err(microtermi_core::GitError::StashNotReapplied) => {
+                self.pending_checkout = None;
+                self.message = format!(
+                    "Rama cambiada a {}, pero el stash no se pudo reaplicar sin conflictos (sigue en stash@{{0}}).",
+                    branch
+                );
+            }
+            Err(e) => {
+                self.message = format!("Error: {}", e);
+                return;
+            }
+        }
+        self.refresh_repo(repo_path);
+    }
+
+    /// Refresca el estado Git de las vistas que muestran `repo_path` (pestaña Git y/o proyecto seleccionado).
+    /// True si el repo tiene elegida la estrategia de pull con rebase.
+    pub(crate) fn pull_uses_rebase(&self, repo_path: &Path) -> bool {
+        self.pull_rebase_repos.contains(&repo_path.to_string_lossy().to_string())
+    }
+
+    /// Cambia la estrategia de pull (merge o rebase) del repo y la guarda en config.
+    pub(crate) fn set_pull_rebase(&mut self, repo_path: &Path, rebase: bool) {
+        let key = repo_path.to_string_lossy().to_string();
+        if rebase {
+            self.pull_rebase_repos.insert(key);
+        } else {
+            self.pull_rebase_repos.remove(&key);
+        }
+        self.persist_app_config();
+    }
+
+    /// Pull con la estrategia configurada para el repo (merge o rebase), en segundo plano.
+    pub(crate) fn pull_repo(&mut self, repo_path: &Path) {
+        let rebase = self.pull_uses_rebase(repo_path);
+        self.start_git_task(GitTaskKind::Pull { rebase }, repo_path);
+    }
+
+    /// Lanza fetch/pull/push/clone (o sigue un rebase) en un hilo aparte; el progreso se recoge en `git_task_drain`.
+    pub(crate) fn start_git_task(&mut self, kind: GitTaskKind, repo_path: &Path) {
+        if self.git_task.is_some() {
+            self.message = "Ya hay una operación git en curso.".to_string();
+            return;
+        }
+        let name = repo_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let label = match &kind {
+            GitTaskKind::Fetch => format!("Fetch de {}", name),
+            GitTaskKind::Pull { rebase: true } => format!("Pull (rebase) de {}", name),
+            GitTaskKind::Pull { rebase: false } => format!("Pull de {}", name),
+            GitTaskKind::Push(_) => format!("Push de {}", name),
+            GitTaskKind::PushTags(tags) => format!("Push de {} tag(s) de {}", tags.len(), name),
+            GitTaskKind::RebaseContinue => format!("Continuando el rebase de {}", name),
+            GitTaskKind::RebaseSkip => format!("Saltando commit del rebase de {}", name),
+            GitTaskKind::MergeContinue => format!("Terminando el merge de {}", name),
+            GitTaskKind::Pick { revert: true, .. } | GitTaskKind::PickContinue { revert: true } => format!("Revert en {}", name),
+            GitTaskKind::Pick { revert: false, .. } | GitTaskKind::PickContinue { revert: false } => format!("Cherry-pick en {}", name),
+            GitTaskKind::Clone { .. } => format!("Clonando {}", name),
+        };
+        let (tx, rx) = mpsc::channel();
+        let cancel = microtermi_core::CancelToken::new();
+        let worker_cancel = cancel.clone();
+        let path = repo_path.to_path_buf();
+        let worker_kind = kind.clone();
+        std::thread::spawn(move || {
+            let progress_tx = tx.clone();
+            let on_progress = move |p| {
+                let _ = progress_tx.send(GitTaskEvent::Progress(p));
+            };
+            let sink = microtermi_core::ProgressSink::new(&on_progress, &worker_cancel);
+            let open = || microtermi_core::open_repo(&path);
+            let result = match worker_kind {
+                GitTaskKind::Fetch => open().and_then(|repo| microtermi_core::fetch(&repo, sink)).map(|()| "Fetch completado.".to_string()),
+                GitTaskKind::Pull { rebase: true } => open().and_then(|repo| microtermi_core::pull_rebase(&repo, sink)).map(|o| o.to_string()),
+                GitTaskKind::Pull { rebase: false } => open().and_then(|repo| microtermi_core::pull(&repo, sink)).map(|o| o.to_string()),
+                GitTaskKind::Push(options) => open().and_then(|repo| microtermi_core::push(&repo, options, sink)).map(|o| o.to_string()),
+                GitTaskKind::PushTags(tags) => open()
+                    .and_then(|repo| microtermi_core::push_tags(&repo, &tags, sink))
+                    .map(|()| format!("Tags subidos a origin: {}", tags.join(", "))),
+                GitTaskKind::RebaseContinue => open().and_then(|repo| microtermi_core::rebase_continue(&repo, sink)).map(|o| o.to_string()),
+                GitTaskKind::RebaseSkip => open().and_then(|repo| microtermi_core::rebase_skip(&repo, sink)).map(|o| o.to_string()),
+                GitTaskKind::MergeContinue => open().and_then(|repo| microtermi_core::merge_continue(&repo)).map(|()| "Merge completado.".to_string()),
+                GitTaskKind::Pick { id, revert } => {
+                    let what = if revert { "Revert" } else { "Cherry-pick" };
+                    let result = open().and_then(|repo| {
+                        if revert {
+                            microtermi_core::revert(&repo, &id)
+                        } else {
+                            microtermi_core::cherry_pick(&repo, &id)
+                        }
+                    });
+                    result.map(|outcome| match outcome {
+                        microtermi_core::PickOutcome::Committed(new_id) => format!("{} hecho: commit {}.", what, new_id),
+                        microtermi_core::PickOutcome::Conflicts(paths) => {
+                            format!("{} con conflictos en: {}. Resuélvelos y pulsa Continuar.", what, paths.join(", "))
+                        }
+                    })
+                }
+                GitTaskKind::PickContinue { revert } => {
+                    let what = if revert { "Revert" } else { "Cherry-pick" };
+                    open().and_then(|repo| microtermi_core::pick_continue(&repo)).map(|id| format!("{} completado: commit {}.", what, id))
+                }
+                GitTaskKind::Clone { url, .. } => microtermi_core::clone_repo(&url, &path, sink).map(|_| String::new()),
+            };
+            let _ = tx.send(GitTaskEvent::Done(result));
+        });
+        self.message = format!("{}…", label);
+        self.git_task = Some(GitTask {
+            label,
+            repo_path: repo_path.to_path_buf(),
+            kind,
+            progress: None,
+            cancel,
+            receiver: rx,
+        });
+    }
+
+    /// Recoge el progreso de la operación git en curso y, al terminar, aplica su resultado.
+    fn git_task_drain(&mut self, ctx: &egui::Context) {
+        let Some(task) = &mut self.git_task else {
+            return;
+        };
+        let mut done = None;
+        while let Ok(event) = task.receiver.try_recv() {
+            match event {
+                GitTaskEvent::Progress(p) => task.progress = Some(p),
+                GitTaskEvent::Done(result) => done = Some(result),
+            }
+        }
+        let Some(result) = done else {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+            return;
+        };
+        let Some(task) = self.git_task.take() else {
+            return;
+        };
+        let dest = task.repo_path;
+        match (task.kind, result) {
+            (_, Err(microtermi_core::GitError::Cancelled)) => {
+                self.message = format!("{}: cancelado.", task.label);
+                self.gitlab_status.clear();
+            }
+            (GitTaskKind::Push(_), Err(microtermi_core::GitError::NoUpstream(branch))) => {
+                self.message = format!("La rama «{}» no tiene upstream.", branch);
+                self.pending_push = Some(PendingPush { repo_path: dest, branch, rejected: Vec::new(), confirm_force: false });
+            }
+            (GitTaskKind::Push(_), Err(microtermi_core::GitError::PushRejected(rejected))) => {
+                self.message = format!("Push rechazado: {}", rejected.join("; "));
+                let branch = microtermi_core::open_repo(&dest)
+                    .ok()
+                    .and_then(|r| microtermi_core::current_branch(&r).ok().flatten())
+                    .unwrap_or_default();
+                self.pending_push = Some(PendingPush { repo_path: dest, branch, rejected, confirm_force: false });
+            }
+            (GitTaskKind::Clone { .. }, Err(e)) => {
+                self.gitlab_status = format!("Error al clonar: {}", e);
+                self.message = format!("Error: {}", e);
+            }
+            (GitTaskKind::Clone { to_root, .. }, Ok(_)) => {
+                self.gitlab_status = format!("Clonado en {}", dest.display());
+                if to_root {
+                    self.git_repo_path = Some(dest);
+                    self.message = "Repositorio clonado. Pull/Push/Commit usarán esta carpeta.".to_string();
+                    self.refresh_projects();
                     self.refresh_git();
+                } else {
+                    self.message = format!("Repositorio clonado en {}", dest.display());
                 }
-                Err(e) => self.message = format!("Error: {}", e),
             }
+            (_, result) => {
+                self.message = match result {
+                    Ok(msg) => msg,
+                    Err(e) => format!("Error: {}", e),
+                };
+                self.refresh_repo(&dest);
+            }
+        }
+    }
+
+    /// Aplica stage/unstage/discard a `paths` del repo y refresca su estado.
+    pub(crate) fn git_index_op(
+        &mut self,
+        repo_path: &Path,
+        paths: &[String],
+        op: fn(&microtermi_core::GitRepo, &[&Path]) -> Result<(), microtermi_core::GitError>,
+    ) {
+        let path_refs: Vec<&Path> = paths.iter().map(Path::new).collect();
+        let result = microtermi_core::open_repo(repo_path).and_then(|repo| op(&repo, &path_refs));
+        if let Err(e) = result {
+            self.message = format!("Error: {}", e);
+        }
+        self.refresh_repo(repo_path);
+        self.reload_diff();
+    }
+
+    /// Carga la plantilla y las reglas commitlint de `repo_path` si el cuadro de commit era de otro repo.
+    /// Con configuración commitlint el cuadro empieza en modo Conventional Commits.
+    pub(crate) fn prepare_commit_form(&mut self, repo_path: &Path) {
+        if self.commit_form.repo_path.as_deref() == Some(repo_path) {
+            return;
+        }
+        let workdir = microtermi_core::repo_root(repo_path);
+        let template = microtermi_core::open_repo(repo_path)
+            .and_then(|repo| microtermi_core::commit_template(&repo))
+            .unwrap_or_else(|e| {
+                self.message = format!("Error: {}", e);
+                None
+            });
+        let rules = match workdir.as_deref().map(microtermi_core::CommitLintRules::load) {
+            Some(Ok(rules)) => rules,
+            Some(Err(e)) => {
+                self.message = format!("Error: {}", e);
+                None
+            }
+            None => None,
+        };
+        // Lo ya escrito se conserva; solo se cambia un mensaje vacío o la plantilla del repo anterior.
+        let form = &mut self.commit_form;
+        if self.commit_message.trim().is_empty() || Some(&self.commit_message) == form.template.as_ref() {
+            self.commit_message = template.clone().unwrap_or_default();
+        }
+        form.repo_path = Some(repo_path.to_path_buf());
+        form.workdir = workdir;
+        form.template = template;
+        form.conventional = rules.is_some();
+        form.rules = rules.unwrap_or_default();
+        form.amend = false;
+        if form.kind.is_empty() {
+            form.kind = "feat".to_string();
+        }
+    }
+
+    /// Scopes sugeridos para lo preparado en `status`: los proyectos afectados.
+    pub(crate) fn commit_scopes(&self, status: Option<&microtermi_core::GitStatus>) -> Vec<String> {
+        match (&self.commit_form.workdir, status) {
+            (Some(workdir), Some(status)) => microtermi_core::commit_scopes(workdir, &status.staged, &self.projects),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Mensaje final: en modo Conventional Commits, la cabecera `tipo(scope)!: descripción` y el cuerpo.
+    pub(crate) fn composed_commit_message(&self, scopes: &[String]) -> String {
+        let form = &self.commit_form;
+        if !form.conventional {
+            return self.commit_message.clone();
+        }
+        let header = microtermi_core::ConventionalHeader {
+            kind: form.kind.clone(),
+            scope: Some(if form.scope.trim().is_empty() { scopes.join(",") } else { form.scope.trim().to_string() }),
+            breaking: form.breaking,
+            subject: form.subject.trim().to_string(),
+        };
+        let body = self.commit_message.trim();
+        if body.is_empty() {
+            header.to_string()
+        } else {
+            format!("{}\n\n{}", header, body)
+        }
+    }
+
+    /// Problemas del mensaje según las reglas commitlint (solo en modo Conventional Commits).
+    pub(crate) fn commit_lint(&self, message: &str) -> Vec<microtermi_core::LintProblem> {
+        if !self.commit_form.conventional {
+            return Vec::new();
+        }
+        microtermi_core::lint_commit_message(message, &self.commit_form.rules)
+    }
+
+    /// Al marcar «Amend» con el cuadro vacío, lo rellena con el mensaje del último commit.
+    pub(crate) fn load_amend_message(&mut self, repo_path: &Path) {
+        let form = &self.commit_form;
+        let untouched = self.commit_message.trim().is_empty() || Some(&self.commit_message) == form.template.as_ref();
+        if !untouched || !form.subject.is_empty() {
+            return;
+        }
+        let Ok(detail) = microtermi_core::open_repo(repo_path).and_then(|repo| microtermi_core::commit_detail(&repo, "HEAD"))
+        else {
+            return;
+        };
+        let header = self.commit_form.conventional.then(|| microtermi_core::ConventionalHeader::parse(&detail.summary)).flatten();
+        match header {
+            Some(header) => {
+                let form = &mut self.commit_form;
+                form.kind = header.kind;
+                form.scope = header.scope.unwrap_or_default();
+                form.breaking = header.breaking;
+                form.subject = header.subject;
+                self.commit_message = detail.message.lines().skip(1).collect::<Vec<_>>().join("\n").trim().to_string();
+            }
+            None => self.commit_message = detail.message.trim_end().to_string(),
+        }
+    }
+
+    /// Commit de lo preparado en el index con el mensaje escrito. En modo Conventional Commits no se
+    /// hace si el mensaje incumple alguna regla de nivel error.
+    pub(crate) fn commit_staged(&mut self, repo_path: &Path, scopes: &[String]) {
+        let message = self.composed_commit_message(scopes);
+        let errors: Vec<String> = self
+            .commit_lint(&message)
+            .into_iter()
+            .filter(|p| p.level == microtermi_core::LintLevel::Error)
+            .map(|p| p.message)
+            .collect();
+        if !errors.is_empty() {
+            self.message = format!("El mensaje no cumple las reglas: {}.", errors.join("; "));
+            return;
+        }
+        if self.commit_task.is_some() {
+            self.message = "Ya hay un commit en curso.".to_string();
+            return;
+        }
+        let options = microtermi_core::CommitOptions { amend: self.commit_form.amend, no_verify: self.commit_form.no_verify };
+        let hooks = microtermi_core::open_repo(repo_path)
+            .and_then(|repo| microtermi_core::commit_hooks(&repo, options.no_verify))
+            .unwrap_or_default();
+        // El commit se hace siempre en otro hilo: los hooks (lint-staged, commitlint…) pueden tardar y
+        // firmarlo puede quedar esperando a que pinentry pida la frase de paso.
+        let name = repo_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let (line_tx, line_rx) = mpsc::channel();
+        let (tx, rx) = mpsc::channel();
+        let cancel = microtermi_core::CancelToken::new();
+        let worker_cancel = cancel.clone();
+        let path = repo_path.to_path_buf();
+        std::thread::spawn(move || {
+            let on_output = |line: &str| {
+                let _ = line_tx.send(crate::ansi::strip_ansi(line));
+            };
+            let sink = microtermi_core::HookSink::new(&on_output, &worker_cancel);
+            let result = microtermi_core::open_repo(&path)
+                .and_then(|repo| microtermi_core::commit(&repo, &message, &[], options, sink));
+            let _ = line_tx.send(match &result {
+                Ok(()) => "[commit realizado]".to_string(),
+                Err(e) => format!("[error] {}", e),
+            });
+            let _ = tx.send(result);
+        });
+        self.commit_task = Some(CommitTask {
+            repo_path: repo_path.to_path_buf(),
+            amend: options.amend,
+            hooks: !hooks.is_empty(),
+            cancel,
+            receiver: rx,
+        });
+        if hooks.is_empty() {
+            return;
+        }
+        // La salida de los hooks se ve en una pestaña de terminal.
+        self.terminal_sessions.push(TerminalSession {
+            name: format!("{} » git commit", name),
+            cwd: microtermi_core::repo_root(repo_path),
+            lines: vec![format!("> git commit (hooks: {})", hooks.join(", "))],
+            child: None,
+            receiver: Some(line_rx),
+            secrets: Vec::new(),
+            pending_project: None,
+            pending_script: None,
+        });
+        self.selected_terminal_tab = self.terminal_sessions.len() - 1;
+        self.message = format!("Ejecutando hooks de commit ({})… La salida está en Multi-run.", hooks.join(", "));
+    }
+
+    /// Recoge el resultado del commit en segundo plano cuando termina.
+    fn commit_task_drain(&mut self, ctx: &egui::Context) {
+        let Some(task) = &self.commit_task else {
+            return;
+        };
+        match task.receiver.try_recv() {
+            Ok(result) => {
+                let (repo_path, amend) = (task.repo_path.clone(), task.amend);
+                self.commit_task = None;
+                self.finish_commit(&repo_path, amend, result);
+            }
+            Err(mpsc::TryRecvError::Empty) => ctx.request_repaint_after(std::time::Duration::from_millis(100)),
+            Err(mpsc::TryRecvError::Disconnected) => self.commit_task = None,
+        }
+    }
+
+    fn finish_commit(&mut self, repo_path: &Path, amend: bool, result: Result<(), microtermi_core::GitError>) {
+        match result {
+            Ok(()) => {
+                self.message = if amend { "Último commit modificado." } else { "Commit realizado." }.to_string();
+                let form = &mut self.commit_form;
+                form.scope.clear();
+                form.subject.clear();
+                form.breaking = false;
+                form.amend = false;
+                self.commit_message = form.template.clone().unwrap_or_default();
+            }
+            Err(microtermi_core::GitError::NothingToCommit) => {
+                self.message = "No hay cambios preparados: marca con «+» los archivos a incluir.".to_string();
+            }
+            Err(microtermi_core::GitError::HookFailed(hook, reason)) => {
+                self.message = format!("El hook {} falló ({}): no se hizo el commit. Salida en Multi-run.", hook, reason);
+            }
+            Err(microtermi_core::GitError::Cancelled) => self.message = "Commit cancelado.".to_string(),
+            Err(e) => self.message = format!("Error: {}", e),
+        }
+        self.refresh_repo(repo_path);
+    }
+
+    /// Relee en segundo plano el estado de todos los repos de la carpeta raíz y de sus proyectos.
+    pub(crate) fn refresh_repos(&mut self) {
+        if self.repos_receiver.is_some() {
+            return;
+        }
+        let mut paths: Vec<PathBuf> = self.projects.iter().map(|p| p.path.clone()).collect();
+        paths.extend(self.root_path.clone());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let roots = microtermi_core::repo_roots(&paths);
+            let _ = tx.send(microtermi_core::repo_summaries(&roots));
+        });
+        self.repos_receiver = Some(rx);
+    }
+
+    /// Lanza `op` en paralelo sobre los repos marcados; los resultados llegan a `repos_results`.
+    pub(crate) fn repos_bulk_start(&mut self, op: RepoBulkOp) {
+        if self.repos_bulk.is_some() {
+            return;
+        }
+        let paths: Vec<PathBuf> = self
+            .repos
+            .iter()
+            .map(|r| r.path.clone())
+            .filter(|p| self.repos_selected.contains(p))
+            .collect();
+        if paths.is_empty() {
+            self.message = "Marca al menos un repositorio.".to_string();
+            return;
+        }
+        let label = match &op {
+            RepoBulkOp::Fetch => format!("Fetch en {} repo(s)", paths.len()),
+            RepoBulkOp::Pull => format!("Pull en {} repo(s)", paths.len()),
+            RepoBulkOp::Checkout { branch, .. } => format!("Cambiando {} repo(s) a {}", paths.len(), branch),
+        };
+        let rebase_repos = self.pull_rebase_repos.clone();
+        let cancel = microtermi_core::CancelToken::new();
+        let worker_cancel = cancel.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let results = match &op {
+                RepoBulkOp::Checkout { branch, auto_stash } => {
+                    microtermi_core::switch_branch_in_repos(&paths, branch, *auto_stash)
+                        .into_iter()
+                        .map(|r| {
+                            let msg = r.outcome.to_string();
+                            (r.path, if r.outcome.is_on_branch() { Ok(msg) } else { Err(msg) })
+                        })
+                        .collect()
+                }
+                RepoBulkOp::Fetch | RepoBulkOp::Pull => microtermi_core::for_each_repo(&paths, |path| {
+                    let sink = microtermi_core::ProgressSink { on_progress: None, cancel: Some(&worker_cancel) };
+                    let repo = microtermi_core::open_repo(path)?;
+                    if op == RepoBulkOp::Fetch {
+                        microtermi_core::fetch(&repo, sink).map(|()| "Fetch completado.".to_string())
+                    } else if rebase_repos.contains(&path.to_string_lossy().to_string()) {
+                        microtermi_core::pull_rebase(&repo, sink).map(|o| o.to_string())
+                    } else {
+                        microtermi_core::pull(&repo, sink).map(|o| o.to_string())
+                    }
+                })
+                .into_iter()
+                .map(|(path, result)| (path, result.map_err(|e| e.to_string())))
+                .collect(),
+            };
+            let _ = tx.send(results);
+        });
+        self.message = format!("{}…", label);
+        self.repos_bulk = Some(RepoBulkTask { label, cancel, receiver: rx });
+    }
+
+    /// Recoge el refresco del panel multi-repo y el resultado de la operación en lote.
+    fn repos_drain(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.repos_receiver {
+            match rx.try_recv() {
+                Ok(repos) => {
+                    self.repos_selected.retain(|p| repos.iter().any(|r| &r.path == p));
+                    self.repos = repos;
+                    self.repos_receiver = None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.repos_receiver = None,
+                Err(mpsc::TryRecvError::Empty) => ctx.request_repaint_after(std::time::Duration::from_millis(100)),
+            }
+        }
+        if let Some(task) = &self.repos_bulk {
+            match task.receiver.try_recv() {
+                Ok(results) => {
+                    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+                    self.message = if failed == 0 {
+                        format!("{}: completado.", task.label)
+                    } else {
+                        format!("{}: {} con error.", task.label, failed)
+                    };
+                    self.repos_results = results.into_iter().collect();
+                    self.repos_bulk = None;
+                    self.refresh_repos();
+                    self.refresh_git();
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.repos_bulk = None,
+                Err(mpsc::TryRecvError::Empty) => ctx.request_repaint_after(std::time::Duration::from_millis(100)),
+            }
+        }
+    }
+
+    /// Abre el formulario de ramas para `branch` (para crear, `branch` es el punto de partida propuesto).
+    pub(crate) fn open_branch_form(&mut self, repo_path: &Path, kind: BranchFormKind, branch: &str) {
+        let input = match kind {
+            BranchFormKind::Create | BranchFormKind::Delete => String::new(),
+            BranchFormKind::Rename => branch.to_string(),
+            BranchFormKind::Upstream => microtermi_core::open_repo(repo_path)
+                .and_then(|repo| microtermi_core::branch_upstream(&repo, branch))
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| format!("origin/{}", branch)),
+        };
+        self.branch_form = Some(BranchForm {
+            repo_path: repo_path.to_path_buf(),
+            kind,
+            branch: branch.to_string(),
+            input,
+            start_point: branch.to_string(),
+            checkout: true,
+            unmerged: false,
+        });
+    }
+
+    /// Ejecuta el formulario de ramas abierto. Si la rama a borrar no está fusionada el formulario sigue
+    /// abierto pidiendo confirmar el borrado forzado.
+    pub(crate) fn submit_branch_form(&mut self) {
+        let Some(form) = self.branch_form.take() else { return };
+        let repo = match microtermi_core::open_repo(&form.repo_path) {
+            Ok(r) => r,
+            Err(e) => {
+                self.message = format!("Error: {}", e);
+                return;
+            }
+        };
+        let name = form.input.trim().to_string();
+        let result = match form.kind {
+            BranchFormKind::Create => microtermi_core::create_branch(&repo, &name, &form.start_point)
+                .map(|()| format!("Rama {} creada desde {}.", name, form.start_point.trim())),
+            BranchFormKind::Rename => microtermi_core::rename_branch(&repo, &form.branch, &name)
+                .map(|()| format!("Rama {} renombrada a {}.", form.branch, name)),
+            BranchFormKind::Delete => microtermi_core::delete_branch(&repo, &form.branch, form.unmerged)
+                .map(|()| format!("Rama {} eliminada.", form.branch)),
+            BranchFormKind::Upstream if name.is_empty() => microtermi_core::set_upstream(&repo, &form.branch, None)
+                .map(|()| format!("{} ya no sigue ninguna rama remota.", form.branch)),
+            BranchFormKind::Upstream => microtermi_core::set_upstream(&repo, &form.branch, Some(&name))
+                .map(|()| format!("{} sigue ahora a {}.", form.branch, name)),
+        };
+        drop(repo);
+        match result {
+            Ok(msg) => {
+                self.message = msg;
+                self.refresh_repo(&form.repo_path);
+                if form.kind == BranchFormKind::Create && form.checkout {
+                    self.checkout(&form.repo_path, &name, false, microtermi_core::CheckoutMode::Safe);
+                }
+            }
+            Err(microtermi_core::GitError::BranchNotMerged(_)) if form.kind == BranchFormKind::Delete => {
+                self.message = format!("La rama {} tiene commits sin fusionar.", form.branch);
+                self.branch_form = Some(BranchForm { unmerged: true, ..form });
+            }
+            Err(e) => {
+                self.message = format!("Error: {}", e);
+                self.branch_form = Some(form);
+            }
+        }
+    }
+
+    /// Guarda un stash con las opciones del formulario y limpia el mensaje.
+    pub(crate) fn stash_save(&mut self, repo_path: &Path) {
+        let result = microtermi_core::open_repo(repo_path)
+            .and_then(|mut repo| microtermi_core::stash(&mut repo, &self.stash_options));
+        match result {
+            Ok(()) => {
+                self.message = "Cambios guardados en stash.".to_string();
+                self.stash_options.message.clear();
+            }
+            Err(e) => self.message = format!("Error: {}", e),
+        }
+        self.refresh_repo(repo_path);
+    }
+
+    /// Aplica, aplica y elimina (pop) o elimina `stash@{index}`; `done` es el mensaje si va bien.
+    pub(crate) fn stash_entry_op(
+        &mut self,
+        repo_path: &Path,
+        index: usize,
+        op: fn(&mut microtermi_core::GitRepo, usize) -> Result<(), microtermi_core::GitError>,
+        done: &str,
+    ) {
+        let result = microtermi_core::open_repo(repo_path).and_then(|mut repo| op(&mut repo, index));
+        self.message = match result {
+            Ok(()) => done.to_string(),
+            Err(e) => format!("Error: {}", e),
+        };
+        if matches!(&self.diff_view, Some(v) if matches!(v.target, microtermi_core::DiffTarget::Stash(_))) {
+            self.diff_view = None;
+        }
+        self.refresh_repo(repo_path);
+    }
+
+    /// Abre (o reemplaza) la ventana de diff con `path` del repo (o todos los archivos) frente a `target`.
+    pub(crate) fn open_diff(&mut self, repo_path: &Path, target: microtermi_core::DiffTarget, path: Option<&str>) {
+        self.diff_view = Some(DiffView {
+            repo_path: repo_path.to_path_buf(),
+            path: path.map(String::from),
+            target,
+            files: Vec::new(),
+            selected: HashSet::new(),
+            error: None,
+        });
+        self.reload_diff();
+    }
+
+    /// Vuelve a calcular el diff abierto (tras preparar, quitar o refrescar el repo).
+    pub(crate) fn reload_diff(&mut self) {
+        let Some(view) = &mut self.diff_view else { return };
+        let result = microtermi_core::open_repo(&view.repo_path)
+            .and_then(|repo| microtermi_core::diff(&repo, &view.target, view.path.as_deref().map(Path::new)));
+        view.selected.clear();
+        match result {
+            Ok(files) => {
+                view.files = files;
+                view.error = None;
+            }
+            Err(e) => {
+                view.files.clear();
+                view.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Carga los tags de `repo_path` para el panel de tags.
+    pub(crate) fn load_tags(&mut self, repo_path: &Path) {
+        self.tags = microtermi_core::open_repo(repo_path)
+            .and_then(|repo| microtermi_core::list_tags(&repo))
+            .unwrap_or_default();
+        self.tags_repo = Some(repo_path.to_path_buf());
+    }
+
+    /// Crea el tag del formulario; con `push` lo sube después a origin.
+    pub(crate) fn create_tag(&mut self, repo_path: &Path, push: bool) {
+        let name = self.tag_name.trim().to_string();
+        let message = self.tag_message.trim().to_string();
+        let result = microtermi_core::open_repo(repo_path).and_then(|repo| {
+            microtermi_core::create_tag(&repo, &name, &self.tag_target, Some(&message))
+        });
+        match result {
+            Ok(()) => {
+                self.message = format!("Tag {} creado.", name);
+                self.tag_name.clear();
+                self.tag_target.clear();
+                self.tag_message.clear();
+                if push {
+                    self.start_git_task(GitTaskKind::PushTags(vec![name]), repo_path);
+                }
+            }
+            Err(e) => self.message = format!("Error: {}", e),
+        }
+        self.load_tags(repo_path);
+        self.refresh_repo(repo_path);
+    }
+
+    /// Rellena el formulario de tag con el siguiente tag de release del proyecto elegido.
+    pub(crate) fn propose_release_tag(&mut self, repo_path: &Path) {
+        let Some(project) = self.release_project.clone() else { return };
+        let result = microtermi_core::open_repo(repo_path)
+            .and_then(|repo| microtermi_core::next_release_tag(&repo, &project, self.release_bump));
+        match result {
+            Ok(release) => {
+                self.message = match &release.previous {
+                    Some(prev) => format!("Último release: {}. Propuesto: {}.", prev, release.tag),
+                    None => format!("Sin releases previos. Propuesto: {}.", release.tag),
+                };
+                self.tag_message = format!("Release {}", release.tag);
+                self.tag_name = release.tag;
+                self.tag_target.clear();
+            }
+            Err(e) => self.message = format!("Error: {}", e),
+        }
+    }
+
+    /// Abre (o reemplaza) la ventana de blame del repo en `rev` (None = árbol de trabajo), con `path`
+    /// abierto y `line` resaltada si se indican.
+    pub(crate) fn open_blame(&mut self, repo_path: &Path, path: Option<&str>, rev: Option<&str>, line: Option<usize>) {
+        let rev = rev.map(String::from);
+        let files = microtermi_core::open_repo(repo_path)
+            .and_then(|repo| microtermi_core::tracked_files(&repo, rev.as_deref()))
+            .unwrap_or_default();
+        self.blame_view = Some(BlameView {
+            repo_path: repo_path.to_path_buf(),
+            rev,
+            files,
+            filter: String::new(),
+            path: path.map(String::from),
+            hunks: Vec::new(),
+            line,
+            scroll_to_line: line.is_some(),
+            error: None,
+        });
+        self.reload_blame();
+    }
+
+    /// Vuelve a calcular el blame del archivo abierto en la ventana de blame.
+    pub(crate) fn reload_blame(&mut self) {
+        let Some(view) = &mut self.blame_view else { return };
+        let Some(path) = view.path.clone() else {
+            view.hunks.clear();
+            view.error = None;
+            return;
+        };
+        let result = microtermi_core::open_repo(&view.repo_path)
+            .and_then(|repo| microtermi_core::blame(&repo, Path::new(&path), view.rev.as_deref()));
+        match result {
+            Ok(hunks) => {
+                view.hunks = hunks;
+                view.error = None;
+            }
+            Err(e) => {
+                view.hunks.clear();
+                view.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Abre el blame de una ruta `archivo:línea` pulsada en la salida de un terminal. Las rutas relativas
+    /// se buscan desde `cwd` (o la carpeta raíz).
+    pub(crate) fn open_file_ref(&mut self, cwd: Option<&Path>, path: &str, line: usize) {
+        let full = match cwd.or(self.root_path.as_deref()) {
+            Some(base) => base.join(path),
+            None => PathBuf::from(path),
+        };
+        let Ok(full) = full.canonicalize() else {
+            self.message = format!("No se encuentra {}", path);
+            return;
+        };
+        let root = full.parent().and_then(microtermi_core::repo_root).and_then(|r| r.canonicalize().ok());
+        let Some(root) = root else {
+            self.message = format!("{} no está en un repositorio Git", path);
+            return;
+        };
+        let Ok(relative) = full.strip_prefix(&root) else { return };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        self.open_blame(&root, Some(&relative), None, Some(line));
+    }
+
+    /// Cherry-pick (o revert con `revert`) del commit `id` sobre la rama actual de `repo_path`. Va en
+    /// segundo plano: el commit puede ir firmado y esperar a pinentry.
+    pub(crate) fn pick_commit(&mut self, repo_path: &Path, id: &str, revert: bool) {
+        self.start_git_task(GitTaskKind::Pick { id: id.to_string(), revert }, repo_path);
+    }
+
+    /// Prepara (diff del árbol de trabajo) o quita del index (diff del index) un hunk del diff abierto;
+    /// con `lines` solo esas líneas del hunk.
+    pub(crate) fn diff_hunk_op(&mut self, hunk: usize, lines: Option<Vec<usize>>) {
+        let Some(view) = &self.diff_view else { return };
+        let repo_path = view.repo_path.clone();
+        let Some(path) = view.path.as_deref().map(PathBuf::from) else { return };
+        let op = match view.target {
+            microtermi_core::DiffTarget::Worktree => microtermi_core::stage_hunk,
+            microtermi_core::DiffTarget::Index => microtermi_core::unstage_hunk,
+            microtermi_core::DiffTarget::Commit(_) | microtermi_core::DiffTarget::Stash(_) => return,
+        };
+        let result = microtermi_core::open_repo(&repo_path).and_then(|repo| op(&repo, &path, hunk, lines.as_deref()));
+        if let Err(e) = result {
+            self.message = format!("Error: {}", e);
+        }
+        self.refresh_repo(&repo_path);
+        self.reload_diff();
+    }
+
+    pub(crate) fn refresh_repo(&mut self, repo_path: &Path) {
+        if self.git_root().as_deref() == Some(repo_path) {
+            self.refresh_git();
+        }
+        let selected_path = self.selected_project.and_then(|i| self.projects.get(i)).map(|p| p.path.clone());
+        if selected_path.as_deref() == Some(repo_path) {
+            self.refresh_project_git(repo_path);
         }
     }
 }
@@ -969,6 +2308,10 @@ impl eframe::App for MicrotermiApp {
             self.env_needs_refresh = false;
         }
         self.terminal_drain();
+        self.git_task_drain(ctx);
+        self.commit_task_drain(ctx);
+        self.signature_drain(ctx);
+        self.repos_drain(ctx);
 
         if let Some(rx) = &mut self.gitlab_receiver {
             if let Ok(result) = rx.try_recv() {
@@ -1014,6 +2357,11 @@ impl eframe::App for MicrotermiApp {
                     self.main_tab = MainTab::Coverage;
                     self.persist_app_config();
                 }
+                if ui.selectable_label(self.main_tab == MainTab::Repos, "Repos").clicked() {
+                    self.main_tab = MainTab::Repos;
+                    self.persist_app_config();
+                    self.refresh_repos();
+                }
                 ui.separator();
                 if self.main_tab == MainTab::Projects || self.main_tab == MainTab::MultiRun {
                     if ui.button("Seleccionar carpeta raíz").clicked() {
@@ -1030,6 +2378,7 @@ impl eframe::App for MicrotermiApp {
                         ui.label(egui::RichText::new(p.display().to_string()).color(ui.visuals().weak_text_color()));
                     }
                 }
+                crate::shared::git_task_panel(self, ui);
             });
         });
 
@@ -1039,6 +2388,7 @@ impl eframe::App for MicrotermiApp {
             MainTab::Git => crate::tabs::draw_git(self, ctx),
             MainTab::MultiRun => crate::tabs::draw_multi_run(self, ctx),
             MainTab::Coverage => crate::tabs::draw_coverage(self, ctx),
+            MainTab::Repos => crate::tabs::draw_repos(self, ctx),
         }
 
         if self.main_tab == MainTab::Projects {
@@ -1098,29 +2448,27 @@ impl eframe::App for MicrotermiApp {
                         let font_id = egui::FontId::monospace(12.0);
                         let idx = self.selected_terminal_tab.min(self.terminal_sessions.len() - 1);
                         let lines = &self.terminal_sessions[idx].lines;
+                        let mut open_ref = None;
                         egui::ScrollArea::vertical()
                             .stick_to_bottom(true)
                             .auto_shrink([false; 2])
                             .show(ui, |ui| {
-                                let default_color = ui.visuals().text_color();
                                 for line in lines {
-                                    ui.horizontal_wrapped(|ui| {
-                                        for seg in crate::ansi::parse_ansi_line(line) {
-                                            let color = seg.color.unwrap_or(default_color);
-                                            let mut rt = egui::RichText::new(seg.text)
-                                                .font(font_id.clone())
-                                                .color(color);
-                                            if seg.bold {
-                                                rt = rt.strong();
-                                            }
-                                            ui.label(rt);
-                                        }
-                                    });
+                                    if let Some(file_ref) = crate::shared::terminal_line(ui, line, &font_id) {
+                                        open_ref = Some(file_ref);
+                                    }
                                 }
                             });
+                        if let Some((path, line)) = open_ref {
+                            let cwd = self.terminal_sessions[idx].cwd.clone();
+                            self.open_file_ref(cwd.as_deref(), &path, line);
+                        }
                     }
                 }
             });
         }
+
+        crate::shared::blame_window(self, ctx);
+        crate::shared::diff_window(self, ctx);
     }
 }
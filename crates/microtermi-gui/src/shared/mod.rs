@@ -1,6 +1,10 @@
 //! Helpers y tipos compartidos entre las pestañas de la GUI.
 
 use eframe::egui;
+use std::path::Path;
+
+use crate::app::{BranchFormKind, GitTaskKind};
+use crate::MicrotermiApp;
 
 /// Pinta un mensaje de estado en color débil (para no repetir el mismo bloque en varias tabs).
 pub fn message_label(ui: &mut egui::Ui, message: &str) {
@@ -9,3 +13,1122 @@ pub fn message_label(ui: &mut egui::Ui, message: &str) {
         ui.label(egui::RichText::new(message).color(ui.visuals().weak_text_color()));
     }
 }
+
+/// Fila de un archivo cambiado; al pulsarla se abre su diff.
+fn change_row(ui: &mut egui::Ui, change: &microtermi_core::FileChange) -> egui::Response {
+    let text = match &change.old_path {
+        Some(old) => format!("{}  {} → {}", change.kind.letter(), old, change.path),
+        None => format!("{}  {}", change.kind.letter(), change.path),
+    };
+    ui.add(egui::Label::new(egui::RichText::new(text).font(egui::FontId::monospace(12.0))).sense(egui::Sense::click()))
+        .on_hover_text("Ver diff")
+}
+
+/// Botón de blame de un archivo cambiado (si ya existía en HEAD y sigue en el árbol de trabajo).
+fn blame_button(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path, change: &microtermi_core::FileChange) {
+    use microtermi_core::FileChangeKind;
+    if matches!(change.kind, FileChangeKind::Added | FileChangeKind::Deleted) {
+        return;
+    }
+    if ui.small_button("Blame").on_hover_text("Quién cambió cada línea por última vez").clicked() {
+        app.open_blame(repo_path, Some(&change.path), None, None);
+    }
+}
+
+/// Rutas a pasar a stage/unstage: en un renombrado también la ruta anterior.
+fn change_paths(change: &microtermi_core::FileChange) -> Vec<String> {
+    let mut paths = vec![change.path.clone()];
+    paths.extend(change.old_path.clone());
+    paths
+}
+
+/// Cambios del repo separados en preparados, sin preparar, sin seguimiento y en conflicto, con botones
+/// por archivo para preparar (+), quitar del index (−) y descartar (↺, con confirmación).
+pub fn changes_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path, status: &microtermi_core::GitStatus) {
+    let weak = ui.visuals().weak_text_color();
+    let error_color = ui.visuals().error_fg_color;
+    if status.is_clean && status.conflicted.is_empty() {
+        ui.label(egui::RichText::new("Estado: limpio").color(weak));
+        return;
+    }
+    if let Some((discard_repo, paths)) = app.pending_discard.clone() {
+        if discard_repo == repo_path {
+            ui.group(|ui| {
+                ui.label(egui::RichText::new(format!("¿Descartar los cambios de {}? No se puede deshacer.", paths.join(", "))).color(error_color));
+                ui.horizontal(|ui| {
+                    if ui.button(egui::RichText::new("Descartar").color(error_color)).clicked() {
+                        app.pending_discard = None;
+                        app.git_index_op(repo_path, &paths, microtermi_core::discard);
+                    }
+                    if ui.button("Cancelar").clicked() {
+                        app.pending_discard = None;
+                    }
+                });
+            });
+        }
+    }
+    if !status.conflicted.is_empty() {
+        ui.label(egui::RichText::new("En conflicto:").color(error_color));
+        for f in &status.conflicted {
+            ui.label(egui::RichText::new(format!("U  {}", f)).font(egui::FontId::monospace(12.0)).color(error_color));
+        }
+    }
+    ui.horizontal(|ui| {
+        ui.strong(format!("Preparados ({})", status.staged.len()));
+        if !status.staged.is_empty() && ui.small_button("Quitar todo").clicked() {
+            let paths: Vec<String> = status.staged.iter().flat_map(change_paths).collect();
+            app.git_index_op(repo_path, &paths, microtermi_core::unstage);
+        }
+    });
+    for change in &status.staged {
+        ui.horizontal(|ui| {
+            if ui.small_button("−").on_hover_text("Quitar del commit (unstage)").clicked() {
+                app.git_index_op(repo_path, &change_paths(change), microtermi_core::unstage);
+            }
+            if change_row(ui, change).clicked() {
+                app.open_diff(repo_path, microtermi_core::DiffTarget::Index, Some(&change.path));
+            }
+            blame_button(app, ui, repo_path, change);
+        });
+    }
+    let pending = status.unstaged.len() + status.untracked.len();
+    ui.horizontal(|ui| {
+        ui.strong(format!("Sin preparar ({})", pending));
+        if pending > 0 && ui.small_button("Preparar todo").clicked() {
+            let mut paths: Vec<String> = status.unstaged.iter().flat_map(change_paths).collect();
+            paths.extend(status.untracked.iter().cloned());
+            app.git_index_op(repo_path, &paths, microtermi_core::stage);
+        }
+    });
+    for change in &status.unstaged {
+        ui.horizontal(|ui| {
+            if ui.small_button("+").on_hover_text("Incluir en el commit (stage)").clicked() {
+                app.git_index_op(repo_path, &change_paths(change), microtermi_core::stage);
+            }
+            if ui.small_button("↺").on_hover_text("Descartar los cambios de este archivo").clicked() {
+                app.pending_discard = Some((repo_path.to_path_buf(), vec![change.path.clone()]));
+            }
+            if change_row(ui, change).clicked() {
+                app.open_diff(repo_path, microtermi_core::DiffTarget::Worktree, Some(&change.path));
+            }
+            blame_button(app, ui, repo_path, change);
+        });
+    }
+    for f in &status.untracked {
+        ui.horizontal(|ui| {
+            if ui.small_button("+").on_hover_text("Incluir en el commit (stage)").clicked() {
+                app.git_index_op(repo_path, std::slice::from_ref(f), microtermi_core::stage);
+            }
+            if ui.small_button("🗑").on_hover_text("Borrar este archivo sin seguimiento").clicked() {
+                app.pending_discard = Some((repo_path.to_path_buf(), vec![f.clone()]));
+            }
+            let text = egui::RichText::new(format!("?  {}", f)).font(egui::FontId::monospace(12.0)).color(weak);
+            if ui.add(egui::Label::new(text).sense(egui::Sense::click())).on_hover_text("Ver diff").clicked() {
+                app.open_diff(repo_path, microtermi_core::DiffTarget::Worktree, Some(f));
+            }
+        });
+    }
+}
+
+/// Cuadro de commit de lo preparado en el index: mensaje (con la plantilla del repo), modo Conventional
+/// Commits con tipo/scope validado con las reglas commitlint, amend y opción de saltar los hooks.
+pub fn commit_box(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path, status: Option<&microtermi_core::GitStatus>) {
+    app.prepare_commit_form(repo_path);
+    let weak = ui.visuals().weak_text_color();
+    let scopes = app.commit_scopes(status);
+    ui.horizontal(|ui| {
+        let source = match &app.commit_form.rules.source {
+            Some(path) => format!("Reglas de {}", path.display()),
+            None => "Reglas de @commitlint/config-conventional".to_string(),
+        };
+        ui.checkbox(&mut app.commit_form.conventional, "Conventional Commits").on_hover_text(source);
+        if ui
+            .checkbox(&mut app.commit_form.amend, "Amend")
+            .on_hover_text("Reemplaza el último commit (git commit --amend); con el mensaje vacío conserva el suyo")
+            .changed()
+            && app.commit_form.amend
+        {
+            app.load_amend_message(repo_path);
+        }
+        ui.checkbox(&mut app.commit_form.no_verify, "Sin hooks")
+            .on_hover_text("No ejecuta los hooks pre-commit ni commit-msg del repo (git commit --no-verify)");
+    });
+    if app.commit_form.conventional {
+        if let Some(warning) = &app.commit_form.rules.warning {
+            ui.label(egui::RichText::new(warning).color(ui.visuals().warn_fg_color));
+        }
+        let types = app.commit_form.rules.types();
+        let allowed_scopes = app.commit_form.rules.scopes();
+        ui.horizontal(|ui| {
+            ui.label("Tipo:");
+            egui::ComboBox::from_id_salt(("commit_type", repo_path))
+                .selected_text(&app.commit_form.kind)
+                .show_ui(ui, |ui| {
+                    for kind in &types {
+                        let description = microtermi_core::CONVENTIONAL_TYPES.iter().find(|(t, _)| t == kind).map(|(_, d)| *d);
+                        let label = match description {
+                            Some(d) => format!("{} — {}", kind, d),
+                            None => kind.clone(),
+                        };
+                        if ui.selectable_label(app.commit_form.kind == *kind, label).clicked() {
+                            app.commit_form.kind = kind.clone();
+                        }
+                    }
+                });
+            ui.label("Scope:");
+            ui.add(egui::TextEdit::singleline(&mut app.commit_form.scope).hint_text(scopes.join(",")).desired_width(140.0))
+                .on_hover_text("Vacío = los proyectos de los archivos preparados");
+            if !allowed_scopes.is_empty() {
+                ui.menu_button("▾", |ui| {
+                    for scope in &allowed_scopes {
+                        if ui.button(scope).clicked() {
+                            app.commit_form.scope = scope.clone();
+                            ui.close_menu();
+                        }
+                    }
+                });
+            }
+            ui.checkbox(&mut app.commit_form.breaking, "!").on_hover_text("Cambio incompatible (BREAKING CHANGE)");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Descripción:");
+            ui.add(egui::TextEdit::singleline(&mut app.commit_form.subject).desired_width(f32::INFINITY));
+        });
+        ui.label("Cuerpo (opcional):");
+    } else {
+        ui.label("Mensaje:");
+    }
+    ui.add(egui::TextEdit::multiline(&mut app.commit_message).desired_rows(3).desired_width(f32::INFINITY));
+    let message = app.composed_commit_message(&scopes);
+    let problems = app.commit_lint(&message);
+    if app.commit_form.conventional {
+        ui.label(egui::RichText::new(message.lines().next().unwrap_or_default()).font(egui::FontId::monospace(12.0)).color(weak));
+    }
+    for problem in &problems {
+        let color = match problem.level {
+            microtermi_core::LintLevel::Error => ui.visuals().error_fg_color,
+            microtermi_core::LintLevel::Warning => ui.visuals().warn_fg_color,
+        };
+        ui.label(egui::RichText::new(format!("• {}", problem)).color(color));
+    }
+    let has_errors = problems.iter().any(|p| p.level == microtermi_core::LintLevel::Error);
+    let (label, hover) = if app.commit_form.amend {
+        ("Amend", "Reemplaza el último commit con lo preparado y este mensaje")
+    } else {
+        ("Commit", "Crea un commit con los archivos preparados")
+    };
+    ui.horizontal(|ui| {
+        let running = app.commit_task.is_some();
+        if ui.add_enabled(!has_errors && !running, egui::Button::new(label)).on_hover_text(hover).clicked() {
+            app.commit_staged(repo_path, &scopes);
+        }
+        if let Some(task) = &app.commit_task {
+            ui.spinner();
+            if task.hooks {
+                ui.label("Ejecutando hooks…");
+                if ui.button("Cancelar").clicked() {
+                    task.cancel.cancel();
+                }
+            } else {
+                ui.label("Creando commit…");
+            }
+        }
+    });
+}
+
+pub fn branch_buttons(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path, selected: &str) {
+    if ui.small_button("Nueva…").on_hover_text("Crear una rama desde la seleccionada, una remota o un commit").clicked() {
+        app.open_branch_form(repo_path, BranchFormKind::Create, selected);
+    }
+    if selected.is_empty() {
+        return;
+    }
+    if ui.small_button("Renombrar…").clicked() {
+        app.open_branch_form(repo_path, BranchFormKind::Rename, selected);
+    }
+    if ui.small_button("Eliminar…").clicked() {
+        app.open_branch_form(repo_path, BranchFormKind::Delete, selected);
+    }
+    if ui.small_button("Upstream…").on_hover_text("Rama remota que sigue (pull/push, ↑/↓)").clicked() {
+        app.open_branch_form(repo_path, BranchFormKind::Upstream, selected);
+    }
+}
+
+/// Formulario abierto desde `branch_buttons` (crear, renombrar, borrar o cambiar upstream).
+pub fn branch_form_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path) {
+    let Some(form) = &mut app.branch_form else { return };
+    if form.repo_path != repo_path {
+        return;
+    }
+    let weak = ui.visuals().weak_text_color();
+    let error_color = ui.visuals().error_fg_color;
+    let mut submit = false;
+    let mut cancel = false;
+    ui.group(|ui| match form.kind {
+        BranchFormKind::Create => {
+            ui.horizontal(|ui| {
+                ui.label("Nueva rama:");
+                submit |= ui.text_edit_singleline(&mut form.input).lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.label("desde");
+                ui.add(egui::TextEdit::singleline(&mut form.start_point).hint_text("rama, origin/rama o commit").desired_width(160.0));
+            });
+            ui.label(egui::RichText::new("Desde una rama remota (origin/…) la rama nueva la sigue como upstream.").color(weak));
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut form.checkout, "Cambiar a la rama nueva");
+                submit |= ui.button("Crear").clicked();
+                cancel = ui.button("Cancelar").clicked();
+            });
+        }
+        BranchFormKind::Rename => {
+            ui.horizontal(|ui| {
+                ui.label(format!("Renombrar {} a:", form.branch));
+                submit |= ui.text_edit_singleline(&mut form.input).lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                submit |= ui.button("Renombrar").clicked();
+                cancel = ui.button("Cancelar").clicked();
+            });
+        }
+        BranchFormKind::Delete => {
+            let text = if form.unmerged {
+                format!("{} tiene commits que no están en HEAD ni en su upstream. ¿Eliminarla igualmente? Se perderán.", form.branch)
+            } else {
+                format!("¿Eliminar la rama local {}?", form.branch)
+            };
+            ui.label(egui::RichText::new(text).color(error_color));
+            ui.horizontal(|ui| {
+                let label = if form.unmerged { "Eliminar igualmente" } else { "Eliminar" };
+                submit |= ui.button(egui::RichText::new(label).color(error_color)).clicked();
+                cancel = ui.button("Cancelar").clicked();
+            });
+        }
+        BranchFormKind::Upstream => {
+            ui.horizontal(|ui| {
+                ui.label(format!("Upstream de {}:", form.branch));
+                ui.add(egui::TextEdit::singleline(&mut form.input).hint_text("origin/rama").desired_width(200.0));
+                submit |= ui.button("Fijar").clicked();
+                if ui.button("Quitar upstream").clicked() {
+                    form.input.clear();
+                    submit = true;
+                }
+                cancel = ui.button("Cancelar").clicked();
+            });
+        }
+    });
+    if cancel {
+        app.branch_form = None;
+    } else if submit {
+        app.submit_branch_form();
+    }
+}
+
+/// Lista de stashes con «Ver» (diff), Aplicar, Pop y Eliminar (con confirmación), y el formulario para
+/// guardar uno nuevo con mensaje, archivos sin seguimiento o manteniendo el index.
+pub fn stash_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path) {
+    let weak = ui.visuals().weak_text_color();
+    let error_color = ui.visuals().error_fg_color;
+    ui.strong(format!("Stashes ({})", app.git_stashes.len()));
+    ui.horizontal(|ui| {
+        ui.label("Mensaje:");
+        ui.text_edit_singleline(&mut app.stash_options.message);
+        ui.checkbox(&mut app.stash_options.include_untracked, "Incluir sin seguimiento")
+            .on_hover_text("git stash -u");
+        ui.checkbox(&mut app.stash_options.keep_index, "Mantener lo preparado")
+            .on_hover_text("git stash --keep-index: lo preparado se guarda y además sigue en el index");
+        if ui.button("Guardar stash").clicked() {
+            app.stash_save(repo_path);
+        }
+    });
+    if let Some((drop_repo, index)) = app.pending_stash_drop.clone() {
+        if drop_repo == repo_path {
+            ui.group(|ui| {
+                ui.label(egui::RichText::new(format!("¿Eliminar stash@{{{}}} sin aplicarlo? No se puede deshacer.", index)).color(error_color));
+                ui.horizontal(|ui| {
+                    if ui.button(egui::RichText::new("Eliminar").color(error_color)).clicked() {
+                        app.pending_stash_drop = None;
+                        app.stash_entry_op(repo_path, index, microtermi_core::stash_drop, "Stash eliminado.");
+                    }
+                    if ui.button("Cancelar").clicked() {
+                        app.pending_stash_drop = None;
+                    }
+                });
+            });
+        }
+    }
+    let stashes = app.git_stashes.clone();
+    for entry in &stashes {
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(format!("stash@{{{}}}", entry.index)).font(egui::FontId::monospace(12.0)));
+            ui.label(egui::RichText::new(&entry.date).color(weak));
+            if let Some(branch) = &entry.branch {
+                ui.label(egui::RichText::new(branch).strong());
+            }
+            // Sin el prefijo "On rama:" / "WIP on rama:" que ya se muestra aparte.
+            let message = entry.message.split_once(": ").map_or(entry.message.as_str(), |(_, m)| m);
+            ui.label(message);
+            if ui.small_button("Ver").on_hover_text("Ver el diff del stash").clicked() {
+                app.open_diff(repo_path, microtermi_core::DiffTarget::Stash(entry.index), None);
+            }
+            if ui.small_button("Aplicar").on_hover_text("Aplica el stash y lo conserva en la lista").clicked() {
+                app.stash_entry_op(repo_path, entry.index, microtermi_core::stash_apply, "Stash aplicado.");
+            }
+            if ui.small_button("Pop").on_hover_text("Aplica el stash y lo elimina de la lista").clicked() {
+                app.stash_entry_op(repo_path, entry.index, microtermi_core::stash_pop, "Stash aplicado y eliminado.");
+            }
+            if ui.small_button("🗑").on_hover_text("Eliminar sin aplicar").clicked() {
+                app.pending_stash_drop = Some((repo_path.to_path_buf(), entry.index));
+            }
+        });
+    }
+}
+
+/// Tags del repo (crear ligero o anotado, subir a origin, borrar con confirmación) y propuesta del
+/// siguiente tag de release `<paquete>@x.y.z` para un proyecto del repo.
+pub fn tags_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path, project: Option<&Path>) {
+    if app.tags_repo.as_deref() != Some(repo_path) {
+        app.load_tags(repo_path);
+        app.release_project = project.map(Path::to_path_buf);
+    }
+    let weak = ui.visuals().weak_text_color();
+    let error_color = ui.visuals().error_fg_color;
+    ui.horizontal(|ui| {
+        ui.strong(format!("Tags ({})", app.tags.len()));
+        if ui.small_button("⟳").on_hover_text("Recargar tags").clicked() {
+            app.load_tags(repo_path);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Release:");
+        let projects: Vec<(String, std::path::PathBuf)> = app
+            .projects
+            .iter()
+            .filter(|p| p.path.starts_with(repo_path) || project == Some(p.path.as_path()))
+            .map(|p| (p.name.clone(), p.path.clone()))
+            .collect();
+        let selected = projects
+            .iter()
+            .find(|(_, path)| app.release_project.as_deref() == Some(path.as_path()))
+            .map_or("—", |(name, _)| name.as_str());
+        egui::ComboBox::from_id_salt(("release_project", repo_path))
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for (name, path) in &projects {
+                    if ui.selectable_label(app.release_project.as_deref() == Some(path.as_path()), name).clicked() {
+                        app.release_project = Some(path.clone());
+                    }
+                }
+            });
+        for (bump, label) in [
+            (microtermi_core::VersionBump::Patch, "patch"),
+            (microtermi_core::VersionBump::Minor, "minor"),
+            (microtermi_core::VersionBump::Major, "major"),
+        ] {
+            ui.radio_value(&mut app.release_bump, bump, label);
+        }
+        let can_propose = app.release_project.is_some();
+        if ui
+            .add_enabled(can_propose, egui::Button::new("Proponer"))
+            .on_hover_text("Rellena el formulario con el siguiente <paquete>@x.y.z según los tags y el package.json")
+            .clicked()
+        {
+            app.propose_release_tag(repo_path);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Nombre:");
+        ui.add(egui::TextEdit::singleline(&mut app.tag_name).desired_width(160.0));
+        ui.label("En:");
+        ui.add(egui::TextEdit::singleline(&mut app.tag_target).hint_text("HEAD").desired_width(80.0));
+        ui.label("Mensaje:");
+        ui.add(egui::TextEdit::singleline(&mut app.tag_message).hint_text("vacío = tag ligero"));
+        let can_create = !app.tag_name.trim().is_empty() && app.git_task.is_none();
+        if ui.add_enabled(can_create, egui::Button::new("Crear")).clicked() {
+            app.create_tag(repo_path, false);
+        }
+        if ui.add_enabled(can_create, egui::Button::new("Crear y subir")).clicked() {
+            app.create_tag(repo_path, true);
+        }
+    });
+    if let Some((delete_repo, name)) = app.pending_tag_delete.clone() {
+        if delete_repo == repo_path {
+            ui.group(|ui| {
+                ui.label(egui::RichText::new(format!("¿Borrar el tag local «{}»? En origin se mantiene.", name)).color(error_color));
+                ui.horizontal(|ui| {
+                    if ui.button(egui::RichText::new("Borrar").color(error_color)).clicked() {
+                        app.pending_tag_delete = None;
+                        match microtermi_core::open_repo(repo_path).and_then(|repo| microtermi_core::delete_tag(&repo, &name)) {
+                            Ok(()) => app.message = format!("Tag {} borrado.", name),
+                            Err(e) => app.message = format!("Error: {}", e),
+                        }
+                        app.load_tags(repo_path);
+                    }
+                    if ui.button("Cancelar").clicked() {
+                        app.pending_tag_delete = None;
+                    }
+                });
+            });
+        }
+    }
+    let tags = app.tags.clone();
+    egui::ScrollArea::vertical().id_salt(("tags", repo_path)).max_height(160.0).show(ui, |ui| {
+        for tag in &tags {
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(&tag.name).strong());
+                ui.label(egui::RichText::new(&tag.target_short).font(egui::FontId::monospace(12.0)));
+                ui.label(egui::RichText::new(&tag.date).color(weak));
+                match &tag.message {
+                    Some(message) => {
+                        ui.label(message.lines().next().unwrap_or_default());
+                    }
+                    None => {
+                        ui.label(egui::RichText::new("(ligero)").color(weak));
+                    }
+                }
+                if ui
+                    .add_enabled(app.git_task.is_none(), egui::Button::new("Subir").small())
+                    .on_hover_text("git push origin <tag>")
+                    .clicked()
+                {
+                    app.start_git_task(GitTaskKind::PushTags(vec![tag.name.clone()]), repo_path);
+                }
+                if ui.small_button("🗑").on_hover_text("Borrar el tag local").clicked() {
+                    app.pending_tag_delete = Some((repo_path.to_path_buf(), tag.name.clone()));
+                }
+            });
+        }
+    });
+}
+
+/// Upstream, "↑2 ↓5" y avisos de estado (merge/rebase/cherry-pick en curso, HEAD separado) junto a la rama.
+pub fn tracking_label(ui: &mut egui::Ui, status: &microtermi_core::GitStatus) {
+    let weak = ui.visuals().weak_text_color();
+    let warn = ui.visuals().warn_fg_color;
+    if status.detached {
+        ui.label(egui::RichText::new("HEAD separado").color(warn));
+    }
+    match &status.upstream {
+        Some(upstream) => {
+            ui.label(egui::RichText::new(format!("→ {}", upstream)).small().color(weak));
+            let ab = status.ahead_behind_label();
+            if ab.is_empty() {
+                ui.label(egui::RichText::new("al día").small().color(weak));
+            } else {
+                ui.label(egui::RichText::new(ab).strong()).on_hover_text(format!(
+                    "{} commit(s) por subir, {} por bajar (según el último fetch)",
+                    status.ahead, status.behind
+                ));
+            }
+        }
+        None if !status.detached => {
+            ui.label(egui::RichText::new("sin upstream").small().color(weak));
+        }
+        None => {}
+    }
+    for (flag, text) in [
+        (status.merging, "merge en curso"),
+        (status.rebasing, "rebase en curso"),
+        (status.cherry_picking, "cherry-pick en curso"),
+        (status.reverting, "revert en curso"),
+    ] {
+        if flag {
+            ui.label(egui::RichText::new(text).color(warn));
+        }
+    }
+}
+
+/// Si hay un cambio de rama bloqueado por cambios locales en `repo_path`, muestra los archivos en
+/// conflicto y las opciones: stash y reaplicar, forzar (con confirmación) o cancelar.
+pub fn pending_checkout_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path) {
+    let pending = match app.pending_checkout.as_ref() {
+        Some(p) if p.repo_path == repo_path => p,
+        _ => return,
+    };
+    let branch = pending.branch.clone();
+    let remote = pending.remote;
+    let confirm_force = pending.confirm_force;
+    let conflicts = pending.conflicts.clone();
+    let error_color = ui.visuals().error_fg_color;
+    ui.group(|ui| {
+        ui.label(egui::RichText::new(format!("No se cambió a «{}»: estos archivos tienen cambios locales que se sobrescribirían:", branch)).color(error_color));
+        for f in &conflicts {
+            ui.label(egui::RichText::new(f).font(egui::FontId::monospace(12.0)));
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Stash, cambiar y reaplicar").clicked() {
+                app.checkout(repo_path, &branch, remote, microtermi_core::CheckoutMode::AutoStash);
+            }
+            if confirm_force {
+                if ui.button(egui::RichText::new("Confirmar: descartar cambios").color(error_color)).clicked() {
+                    app.checkout(repo_path, &branch, remote, microtermi_core::CheckoutMode::Force);
+                }
+            } else if ui.button("Forzar…").on_hover_text("Descarta los cambios locales de esos archivos").clicked() {
+                if let Some(p) = app.pending_checkout.as_mut() {
+                    p.confirm_force = true;
+                }
+            }
+            if ui.button("Cancelar").clicked() {
+                app.pending_checkout = None;
+            }
+        });
+    });
+}
+
+/// Push que no se completó en `repo_path`: ofrece publicar la rama con upstream o, si el servidor la
+/// rechazó, forzar con lease (solo si la rama remota no cambió desde el último fetch).
+pub fn pending_push_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path) {
+    let pending = match app.pending_push.as_ref() {
+        Some(p) if p.repo_path == repo_path => p,
+        _ => return,
+    };
+    let branch = pending.branch.clone();
+    let rejected = pending.rejected.clone();
+    let confirm_force = pending.confirm_force;
+    let error_color = ui.visuals().error_fg_color;
+    ui.group(|ui| {
+        if rejected.is_empty() {
+            ui.label(format!("La rama «{}» no existe en el remoto todavía.", branch));
+        } else {
+            ui.label(egui::RichText::new("El servidor rechazó el push:").color(error_color));
+            for r in &rejected {
+                ui.label(egui::RichText::new(r).font(egui::FontId::monospace(12.0)));
+            }
+        }
+        ui.horizontal(|ui| {
+            if rejected.is_empty() {
+                if ui.button("Publicar rama (set upstream)").on_hover_text("git push --set-upstream origin <rama>").clicked() {
+                    app.pending_push = None;
+                    let options = microtermi_core::PushOptions { set_upstream: true, ..Default::default() };
+                    app.start_git_task(GitTaskKind::Push(options), repo_path);
+                }
+            } else if confirm_force {
+                if ui.button(egui::RichText::new("Confirmar: forzar con lease").color(error_color)).clicked() {
+                    app.pending_push = None;
+                    let options = microtermi_core::PushOptions { force_with_lease: true, ..Default::default() };
+                    app.start_git_task(GitTaskKind::Push(options), repo_path);
+                }
+            } else if ui
+                .button("Forzar con lease…")
+                .on_hover_text("git push --force-with-lease: sobrescribe la rama remota solo si nadie la movió desde el último fetch")
+                .clicked()
+            {
+                if let Some(p) = app.pending_push.as_mut() {
+                    p.confirm_force = true;
+                }
+            }
+            if ui.button("Cancelar").clicked() {
+                app.pending_push = None;
+            }
+        });
+    });
+}
+
+/// Si hay un merge a medias en `repo_path` (p. ej. tras un pull con conflictos), lista los archivos
+/// en conflicto y ofrece continuar (commit de merge) o abortar.
+pub fn merge_conflicts_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path) {
+    let repo = match microtermi_core::open_repo(repo_path) {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+    if !microtermi_core::merge_in_progress(&repo) {
+        return;
+    }
+    let conflicts = microtermi_core::conflicted_paths(&repo).unwrap_or_default();
+    let error_color = ui.visuals().error_fg_color;
+    ui.group(|ui| {
+        if conflicts.is_empty() {
+            ui.label("Merge en curso sin conflictos pendientes.");
+        } else {
+            ui.label(egui::RichText::new("Merge en curso. Resuelve los conflictos en estos archivos:").color(error_color));
+            for f in &conflicts {
+                ui.label(egui::RichText::new(f).font(egui::FontId::monospace(12.0)));
+            }
+        }
+        let busy = app.git_task.is_some();
+        ui.horizontal(|ui| {
+            let continue_button = ui.add_enabled(!busy, egui::Button::new("Continuar merge"));
+            if continue_button.on_hover_text("Añade los archivos resueltos y crea el commit de merge").clicked() {
+                app.start_git_task(GitTaskKind::MergeContinue, repo_path);
+            }
+            if ui.button("Abortar merge").on_hover_text("Vuelve a HEAD los archivos afectados; conserva tus cambios en los demás").clicked() {
+                match microtermi_core::merge_abort(&repo) {
+                    Ok(()) => app.message = "Merge abortado.".to_string(),
+                    Err(e) => app.message = format!("Error: {}", e),
+                }
+                app.refresh_repo(repo_path);
+            }
+        });
+    });
+}
+
+/// Si hay un cherry-pick o revert a medias en `repo_path`, lista los conflictos y ofrece continuar
+/// (commit) o abortar.
+pub fn pick_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path) {
+    let repo = match microtermi_core::open_repo(repo_path) {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+    if !microtermi_core::pick_in_progress(&repo) {
+        return;
+    }
+    let reverting = microtermi_core::status(&repo).is_ok_and(|st| st.reverting);
+    let what = if reverting { "Revert" } else { "Cherry-pick" };
+    let conflicts = microtermi_core::conflicted_paths(&repo).unwrap_or_default();
+    let error_color = ui.visuals().error_fg_color;
+    ui.group(|ui| {
+        if conflicts.is_empty() {
+            ui.label(format!("{} en curso sin conflictos pendientes.", what));
+        } else {
+            ui.label(egui::RichText::new(format!("{} en curso. Resuelve los conflictos en estos archivos:", what)).color(error_color));
+            for f in &conflicts {
+                ui.label(egui::RichText::new(f).font(egui::FontId::monospace(12.0)));
+            }
+        }
+        let busy = app.git_task.is_some();
+        ui.horizontal(|ui| {
+            let continue_button = ui.add_enabled(!busy, egui::Button::new(format!("Continuar {}", what.to_lowercase())));
+            if continue_button.on_hover_text("Añade los archivos resueltos y crea el commit").clicked() {
+                app.start_git_task(GitTaskKind::PickContinue { revert: reverting }, repo_path);
+            }
+            if ui.button(format!("Abortar {}", what.to_lowercase())).on_hover_text("Vuelve a HEAD los archivos afectados; conserva tus cambios en los demás").clicked() {
+                match microtermi_core::pick_abort(&repo) {
+                    Ok(()) => app.message = format!("{} abortado.", what),
+                    Err(e) => app.message = format!("Error: {}", e),
+                }
+                app.refresh_repo(repo_path);
+            }
+        });
+    });
+}
+
+/// Si hay un rebase pausado en `repo_path`, lista los conflictos y ofrece continuar, saltar el commit o abortar.
+pub fn rebase_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui, repo_path: &Path) {
+    let repo = match microtermi_core::open_repo(repo_path) {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+    if !microtermi_core::rebase_in_progress(&repo) {
+        return;
+    }
+    let conflicts = microtermi_core::conflicted_paths(&repo).unwrap_or_default();
+    let error_color = ui.visuals().error_fg_color;
+    ui.group(|ui| {
+        if conflicts.is_empty() {
+            ui.label("Rebase en pausa sin conflictos pendientes.");
+        } else {
+            ui.label(egui::RichText::new("Rebase en pausa. Resuelve los conflictos en estos archivos:").color(error_color));
+            for f in &conflicts {
+                ui.label(egui::RichText::new(f).font(egui::FontId::monospace(12.0)));
+            }
+        }
+        let busy = app.git_task.is_some();
+        ui.horizontal(|ui| {
+            let continue_button = ui.add_enabled(!busy, egui::Button::new("Continuar rebase"));
+            if continue_button.on_hover_text("Añade los archivos resueltos, hace commit y sigue con el resto").clicked() {
+                app.start_git_task(GitTaskKind::RebaseContinue, repo_path);
+            }
+            let skip_button = ui.add_enabled(!busy, egui::Button::new("Saltar commit"));
+            if skip_button.on_hover_text("Descarta los cambios del commit actual y sigue con el resto").clicked() {
+                app.start_git_task(GitTaskKind::RebaseSkip, repo_path);
+            }
+            if ui.button("Abortar rebase").on_hover_text("Deja la rama como estaba antes del pull").clicked() {
+                match microtermi_core::rebase_abort(&repo) {
+                    Ok(()) => app.message = "Rebase abortado.".to_string(),
+                    Err(e) => app.message = format!("Error: {}", e),
+                }
+                app.refresh_repo(repo_path);
+            }
+        });
+    });
+}
+
+/// Texto para la barra de progreso de una operación git.
+pub fn git_progress_text(progress: &microtermi_core::GitProgress) -> String {
+    match *progress {
+        microtermi_core::GitProgress::Transfer { received_objects, indexed_objects, total_objects, received_bytes } => {
+            if received_objects < total_objects {
+                format!("{}/{} objetos · {:.1} MB", received_objects, total_objects, received_bytes as f64 / 1_048_576.0)
+            } else {
+                format!("Indexando {}/{} objetos", indexed_objects, total_objects)
+            }
+        }
+        microtermi_core::GitProgress::Push { current, total, bytes } => {
+            format!("Enviando {}/{} objetos · {:.1} MB", current, total, bytes as f64 / 1_048_576.0)
+        }
+        microtermi_core::GitProgress::Rebase { current, total } => format!("Rebase: commit {}/{}", current, total),
+    }
+}
+
+/// Barra de progreso y botón «Cancelar» de la operación git en curso (fetch/pull/push/clone).
+pub fn git_task_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui) {
+    let Some(task) = &app.git_task else {
+        return;
+    };
+    ui.separator();
+    ui.label(&task.label);
+    let bar = match &task.progress {
+        Some(p) => egui::ProgressBar::new(p.fraction()).text(git_progress_text(p)),
+        None => egui::ProgressBar::new(0.0).text("Conectando…").animate(true),
+    };
+    ui.add(bar.desired_width(220.0));
+    if task.cancel.is_cancelled() {
+        ui.label(egui::RichText::new("Cancelando…").color(ui.visuals().weak_text_color()));
+    } else if ui.button("Cancelar").clicked() {
+        task.cancel.cancel();
+    }
+}
+
+/// Fondo de las líneas añadidas/borradas (sin resaltado de sintaxis), legible en tema claro y oscuro.
+fn diff_line_background(ui: &egui::Ui, kind: microtermi_core::DiffLineKind) -> egui::Color32 {
+    let dark = ui.visuals().dark_mode;
+    match kind {
+        microtermi_core::DiffLineKind::Added if dark => egui::Color32::from_rgb(28, 64, 36),
+        microtermi_core::DiffLineKind::Added => egui::Color32::from_rgb(218, 246, 222),
+        microtermi_core::DiffLineKind::Removed if dark => egui::Color32::from_rgb(78, 32, 32),
+        microtermi_core::DiffLineKind::Removed => egui::Color32::from_rgb(252, 222, 222),
+        microtermi_core::DiffLineKind::Context => egui::Color32::TRANSPARENT,
+    }
+}
+
+fn diff_text(ui: &mut egui::Ui, text: String, kind: microtermi_core::DiffLineKind) {
+    let bg = diff_line_background(ui, kind);
+    ui.add(egui::Label::new(egui::RichText::new(text).font(egui::FontId::monospace(12.0)).background_color(bg)).extend());
+}
+
+fn lineno(n: Option<u32>) -> String {
+    n.map(|n| n.to_string()).unwrap_or_default()
+}
+
+/// Empareja borradas con añadidas para la vista lado a lado; el contexto va en ambas columnas.
+fn side_by_side_rows(
+    lines: &[microtermi_core::DiffLine],
+) -> Vec<(Option<&microtermi_core::DiffLine>, Option<&microtermi_core::DiffLine>)> {
+    use microtermi_core::DiffLineKind;
+    let mut rows = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].kind == DiffLineKind::Context {
+            rows.push((Some(&lines[i]), Some(&lines[i])));
+            i += 1;
+            continue;
+        }
+        let removed_start = i;
+        while i < lines.len() && lines[i].kind == DiffLineKind::Removed {
+            i += 1;
+        }
+        let added_start = i;
+        while i < lines.len() && lines[i].kind == DiffLineKind::Added {
+            i += 1;
+        }
+        let (removed, added) = (&lines[removed_start..added_start], &lines[added_start..i]);
+        for k in 0..removed.len().max(added.len()) {
+            rows.push((removed.get(k), added.get(k)));
+        }
+    }
+    rows
+}
+
+/// Ventana con el diff abierto (unificado o lado a lado). En los diffs del árbol de trabajo y del index
+/// cada hunk se puede preparar/quitar entero y, en la vista unificada, línea a línea.
+pub fn diff_window(app: &mut MicrotermiApp, ctx: &egui::Context) {
+    use microtermi_core::{DiffLineKind, DiffTarget};
+    let Some(mut view) = app.diff_view.take() else { return };
+    let mut open = true;
+    let mut reload = false;
+    let mut action: Option<(usize, Option<Vec<usize>>)> = None;
+    let mut side_by_side = app.diff_side_by_side;
+    let file_label = view.path.as_deref().unwrap_or("todos los archivos");
+    // Preparar/quitar hunks solo en el diff de un archivo del árbol de trabajo o del index.
+    let (title, hunk_verb) = match &view.target {
+        DiffTarget::Worktree => (format!("Cambios sin preparar — {}", file_label), Some("Preparar")),
+        DiffTarget::Index => (format!("Cambios preparados — {}", file_label), Some("Quitar")),
+        DiffTarget::Commit(id) => (format!("Commit {} — {}", id, file_label), None),
+        DiffTarget::Stash(index) => (format!("stash@{{{}}} — {}", index, file_label), None),
+    };
+    let hunk_verb = hunk_verb.filter(|_| view.path.is_some());
+    let show_file_names = view.path.is_none();
+    egui::Window::new(title)
+        .id(egui::Id::new("diff_window"))
+        .open(&mut open)
+        .default_size([780.0, 480.0])
+        .resizable(true)
+        .show(ctx, |ui| {
+            let weak = ui.visuals().weak_text_color();
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut side_by_side, false, "Unificado");
+                ui.selectable_value(&mut side_by_side, true, "Lado a lado");
+                if ui.button("Refrescar").clicked() {
+                    reload = true;
+                }
+                if hunk_verb.is_some() && side_by_side {
+                    ui.label(egui::RichText::new("Para elegir líneas sueltas usa la vista unificada.").color(weak));
+                }
+            });
+            if let Some(e) = &view.error {
+                ui.label(egui::RichText::new(format!("Error: {}", e)).color(ui.visuals().error_fg_color));
+            } else if view.files.is_empty() {
+                ui.label(egui::RichText::new("Sin cambios.").color(weak));
+            }
+            ui.separator();
+            let selected = &mut view.selected;
+            egui::ScrollArea::both().auto_shrink([false; 2]).show(ui, |ui| {
+                for (fi, file) in view.files.iter().enumerate() {
+                    if let Some(old) = &file.old_path {
+                        ui.label(egui::RichText::new(format!("{} → {} ({})", old, file.path, file.status)).strong());
+                    } else if show_file_names {
+                        ui.label(egui::RichText::new(format!("{} ({})", file.path, file.status)).strong());
+                    }
+                    if file.binary {
+                        ui.label(egui::RichText::new("Archivo binario: no se muestra el diff.").color(weak));
+                        continue;
+                    }
+                    for (hi, hunk) in file.hunks.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(&hunk.header).font(egui::FontId::monospace(12.0)).color(weak));
+                            if let Some(verb) = hunk_verb {
+                                if ui.small_button(format!("{} hunk", verb)).clicked() {
+                                    action = Some((hi, None));
+                                }
+                                let mut lines: Vec<usize> =
+                                    selected.iter().filter(|(h, _)| *h == hi).map(|(_, l)| *l).collect();
+                                lines.sort_unstable();
+                                if !lines.is_empty()
+                                    && ui.small_button(format!("{} {} línea(s)", verb, lines.len())).clicked()
+                                {
+                                    action = Some((hi, Some(lines)));
+                                }
+                            }
+                        });
+                        if side_by_side {
+                            egui::Grid::new(("diff_hunk", fi, hi)).num_columns(2).spacing([12.0, 0.0]).show(ui, |ui| {
+                                for (old, new) in side_by_side_rows(&hunk.lines) {
+                                    for (line, no) in [(old, old.and_then(|l| l.old_lineno)), (new, new.and_then(|l| l.new_lineno))] {
+                                        match line {
+                                            Some(l) => diff_text(ui, format!("{:>5} {}", lineno(no), l.content), l.kind),
+                                            None => {
+                                                ui.label("");
+                                            }
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        } else {
+                            for (li, line) in hunk.lines.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    if hunk_verb.is_some() {
+                                        if line.kind == DiffLineKind::Context {
+                                            ui.add_space(ui.spacing().interact_size.y + ui.spacing().item_spacing.x);
+                                        } else {
+                                            let mut checked = selected.contains(&(hi, li));
+                                            if ui.checkbox(&mut checked, "").changed() {
+                                                if checked {
+                                                    selected.insert((hi, li));
+                                                } else {
+                                                    selected.remove(&(hi, li));
+                                                }
+                                            }
+                                        }
+                                    }
+                                    let prefix = match line.kind {
+                                        DiffLineKind::Context => ' ',
+                                        DiffLineKind::Added => '+',
+                                        DiffLineKind::Removed => '-',
+                                    };
+                                    let text = format!(
+                                        "{:>5} {:>5} {}{}",
+                                        lineno(line.old_lineno),
+                                        lineno(line.new_lineno),
+                                        prefix,
+                                        line.content
+                                    );
+                                    diff_text(ui, text, line.kind);
+                                });
+                                if line.no_newline {
+                                    ui.label(egui::RichText::new("\\ Sin salto de línea al final del archivo").color(weak));
+                                }
+                            }
+                        }
+                        ui.add_space(6.0);
+                    }
+                }
+            });
+        });
+    if side_by_side != app.diff_side_by_side {
+        app.diff_side_by_side = side_by_side;
+        app.persist_app_config();
+    }
+    if open {
+        app.diff_view = Some(view);
+    }
+    if reload {
+        app.reload_diff();
+    }
+    if let Some((hunk, lines)) = action {
+        app.diff_hunk_op(hunk, lines);
+    }
+}
+
+/// Ventana de blame: a la izquierda los archivos versionados (con filtro), a la derecha cada línea del
+/// archivo elegido con el commit, autor y fecha que la cambiaron por última vez. Pulsar un commit abre su diff.
+pub fn blame_window(app: &mut MicrotermiApp, ctx: &egui::Context) {
+    let Some(mut view) = app.blame_view.take() else { return };
+    let mut open = true;
+    let mut reload = false;
+    let mut open_commit: Option<(String, String)> = None;
+    let rev_label = view.rev.clone().unwrap_or_else(|| "árbol de trabajo".to_string());
+    let title = match &view.path {
+        Some(path) => format!("Blame — {} ({})", path, rev_label),
+        None => format!("Blame — elige un archivo ({})", rev_label),
+    };
+    egui::Window::new(title)
+        .id(egui::Id::new("blame_window"))
+        .open(&mut open)
+        .default_size([900.0, 520.0])
+        .resizable(true)
+        .show(ctx, |ui| {
+            let weak = ui.visuals().weak_text_color();
+            egui::SidePanel::left("blame_files").resizable(true).default_width(220.0).show_inside(ui, |ui| {
+                ui.add(egui::TextEdit::singleline(&mut view.filter).hint_text("Filtrar archivos"));
+                let filter = view.filter.trim().to_lowercase();
+                egui::ScrollArea::vertical().id_salt("blame_files_scroll").show(ui, |ui| {
+                    for f in view.files.iter().filter(|f| filter.is_empty() || f.to_lowercase().contains(&filter)) {
+                        let selected = view.path.as_deref() == Some(f.as_str());
+                        if ui.selectable_label(selected, egui::RichText::new(f).font(egui::FontId::monospace(12.0))).clicked() {
+                            view.path = Some(f.clone());
+                            view.line = None;
+                            reload = true;
+                        }
+                    }
+                });
+            });
+            egui::CentralPanel::default().show_inside(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Refrescar").clicked() {
+                        reload = true;
+                    }
+                    ui.label(egui::RichText::new("Pulsa un commit para ver su diff de este archivo.").color(weak));
+                });
+                if let Some(e) = &view.error {
+                    ui.label(egui::RichText::new(format!("Error: {}", e)).color(ui.visuals().error_fg_color));
+                } else if view.path.is_none() {
+                    ui.label(egui::RichText::new("Elige un archivo en la lista.").color(weak));
+                }
+                ui.separator();
+                let mono = egui::FontId::monospace(12.0);
+                let highlight = ui.visuals().selection.bg_fill;
+                let scroll_to = view.scroll_to_line.then_some(view.line).flatten();
+                egui::ScrollArea::both().id_salt("blame_lines_scroll").auto_shrink([false; 2]).show(ui, |ui| {
+                    egui::Grid::new("blame_grid").num_columns(5).spacing([10.0, 0.0]).show(ui, |ui| {
+                        for hunk in &view.hunks {
+                            for (i, text) in hunk.lines.iter().enumerate() {
+                                let lineno = hunk.start_line + i;
+                                if i > 0 {
+                                    ui.label("");
+                                    ui.label("");
+                                    ui.label("");
+                                } else if let Some(id) = &hunk.commit_id {
+                                    if ui.link(egui::RichText::new(&hunk.id_short).font(mono.clone())).on_hover_text(&hunk.summary).clicked() {
+                                        let path = hunk.orig_path.clone().or_else(|| view.path.clone()).unwrap_or_default();
+                                        open_commit = Some((id.clone(), path));
+                                    }
+                                    ui.label(egui::RichText::new(&hunk.author).color(weak));
+                                    ui.label(egui::RichText::new(&hunk.date).color(weak));
+                                } else {
+                                    ui.label(egui::RichText::new("sin commit").color(weak));
+                                    ui.label("");
+                                    ui.label("");
+                                }
+                                ui.label(egui::RichText::new(lineno.to_string()).font(mono.clone()).color(weak));
+                                let mut rt = egui::RichText::new(text).font(mono.clone());
+                                if view.line == Some(lineno) {
+                                    rt = rt.background_color(highlight);
+                                }
+                                let resp = ui.label(rt);
+                                if scroll_to == Some(lineno) {
+                                    resp.scroll_to_me(Some(egui::Align::Center));
+                                }
+                                ui.end_row();
+                            }
+                        }
+                    });
+                });
+            });
+        });
+    if view.scroll_to_line && !view.hunks.is_empty() {
+        view.scroll_to_line = false;
+    }
+    let repo_path = view.repo_path.clone();
+    if open {
+        app.blame_view = Some(view);
+    }
+    if reload {
+        app.reload_blame();
+    }
+    if let Some((id, path)) = open_commit {
+        app.open_diff(&repo_path, microtermi_core::DiffTarget::Commit(id), Some(&path));
+    }
+}
+
+/// Ruta `archivo:línea[:columna]` dentro de una palabra de salida (p. ej. `(src/app.test.ts:12:5)`).
+fn parse_file_ref(word: &str) -> Option<(&str, usize)> {
+    let word = word.trim_matches(|c: char| "()[]<>'\"`,;".contains(c));
+    if word.contains("//") {
+        return None;
+    }
+    let mut parts = word.split(':');
+    let mut end = 0;
+    for part in parts.by_ref() {
+        if end > 0 && !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()) {
+            let path = &word[..end - 1];
+            let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+            // Nombre con extensión y que no sea una IP (`127.0.0.1:8080`).
+            let has_extension = name.rsplit_once('.').is_some_and(|(stem, ext)| !stem.is_empty() && !ext.is_empty());
+            if !has_extension || name.chars().all(|c| c.is_ascii_digit() || c == '.') {
+                return None;
+            }
+            return part.parse().ok().filter(|&l| l > 0).map(|line| (path, line));
+        }
+        end += part.len() + 1;
+    }
+    None
+}
+
+/// Pinta una línea de terminal con sus colores ANSI; las rutas `archivo:línea` salen como enlaces.
+/// Devuelve la ruta y la línea si se pulsó una.
+pub fn terminal_line(ui: &mut egui::Ui, line: &str, font_id: &egui::FontId) -> Option<(String, usize)> {
+    let default_color = ui.visuals().text_color();
+    let mut clicked = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        for seg in crate::ansi::parse_ansi_line(line) {
+            let color = seg.color.unwrap_or(default_color);
+            let styled = |text: &str| {
+                let rt = egui::RichText::new(text).font(font_id.clone()).color(color);
+                if seg.bold {
+                    rt.strong()
+                } else {
+                    rt
+                }
+            };
+            let mut plain = String::new();
+            for word in seg.text.split_inclusive(char::is_whitespace) {
+                match parse_file_ref(word.trim_end()) {
+                    Some((path, lineno)) => {
+                        if !plain.is_empty() {
+                            ui.label(styled(&plain));
+                            plain.clear();
+                        }
+                        let text = word.trim_end();
+                        if ui.link(egui::RichText::new(text).font(font_id.clone())).on_hover_text("Ver blame").clicked() {
+                            clicked = Some((path.to_string(), lineno));
+                        }
+                        plain.push_str(&word[text.len()..]);
+                    }
+                    None => plain.push_str(word),
+                }
+            }
+            if !plain.is_empty() {
+                ui.label(styled(&plain));
+            }
+        }
+    });
+    clicked
+}
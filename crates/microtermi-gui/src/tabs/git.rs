@@ -1,8 +1,182 @@
 use eframe::egui;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
+use crate::app::GitTaskKind;
 use crate::MicrotermiApp;
 
+/// Alto de una fila del historial y ancho de cada carril del grafo.
+const GRAPH_ROW_HEIGHT: f32 = 20.0;
+const GRAPH_LANE_WIDTH: f32 = 12.0;
+
+/// Dibuja la parte del grafo de una fila: segmentos que llegan al commit desde arriba, los que salen
+/// hacia sus padres y el punto del commit. Cada carril tiene su color.
+fn paint_graph_row(ui: &mut egui::Ui, graph: &microtermi_core::GraphRow) {
+    const COLORS: [egui::Color32; 6] = [
+        egui::Color32::from_rgb(90, 160, 230),
+        egui::Color32::from_rgb(230, 140, 60),
+        egui::Color32::from_rgb(110, 190, 100),
+        egui::Color32::from_rgb(200, 100, 190),
+        egui::Color32::from_rgb(220, 200, 70),
+        egui::Color32::from_rgb(90, 200, 200),
+    ];
+    let size = egui::vec2(graph.width as f32 * GRAPH_LANE_WIDTH, GRAPH_ROW_HEIGHT);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let x = |lane: usize| rect.left() + (lane as f32 + 0.5) * GRAPH_LANE_WIDTH;
+    let color = |lane: usize| COLORS[lane % COLORS.len()];
+    for &(from, to) in &graph.up {
+        painter.line_segment([egui::pos2(x(from), rect.top()), egui::pos2(x(to), rect.center().y)], (1.5, color(from)));
+    }
+    for &(from, to) in &graph.down {
+        painter.line_segment(
+            [egui::pos2(x(from), rect.center().y), egui::pos2(x(to), rect.bottom())],
+            (1.5, color(to.max(from))),
+        );
+    }
+    painter.circle_filled(egui::pos2(x(graph.column), rect.center().y), 3.5, color(graph.column));
+}
+
+/// Icono, color y descripción de la firma de un commit; `check` es None mientras se verifica.
+fn signature_badge(ui: &egui::Ui, check: Option<&microtermi_core::SignatureCheck>) -> (&'static str, egui::Color32, String) {
+    let Some(check) = check else {
+        return ("🔏", ui.visuals().weak_text_color(), "Firmado; verificando…".to_string());
+    };
+    let signer = check.signer.as_deref().or(check.key.as_deref()).unwrap_or("clave desconocida");
+    match check.status {
+        microtermi_core::SignatureStatus::Good => ("✔", egui::Color32::from_rgb(110, 190, 100), format!("Firma verificada: {}", signer)),
+        microtermi_core::SignatureStatus::Bad => ("✖", ui.visuals().error_fg_color, format!("Firma NO válida: {}", signer)),
+        microtermi_core::SignatureStatus::UnknownKey => {
+            ("🔏", ui.visuals().warn_fg_color, format!("Firmado con una clave que no se puede comprobar: {}", signer))
+        }
+        microtermi_core::SignatureStatus::Error => ("🔏", ui.visuals().weak_text_color(), "Firmado; no se pudo verificar".to_string()),
+    }
+}
+
+/// Detalle del commit seleccionado: mensaje completo, autor y committer, padres, refs y archivos con
+/// líneas añadidas/quitadas; acciones de cherry-pick, revert y crear rama.
+fn commit_detail_panel(app: &mut MicrotermiApp, ui: &mut egui::Ui, root: &std::path::Path) {
+    let Some(detail) = &app.git_commit_detail else { return };
+    let weak = ui.visuals().weak_text_color();
+    let mut open_diff: Option<String> = None;
+    let mut open_blame: Option<String> = None;
+    enum Action {
+        CherryPick,
+        Revert,
+        Branch,
+    }
+    let mut action = None;
+    ui.add_space(4.0);
+    ui.group(|ui| {
+        ui.horizontal_wrapped(|ui| {
+            ui.label(egui::RichText::new(&detail.id).monospace());
+            for r in &detail.refs {
+                ui.label(egui::RichText::new(format!("[{}]", r)).color(ui.visuals().hyperlink_color).strong());
+            }
+        });
+        let person = |label: &str, p: &microtermi_core::CommitPerson| format!("{}: {} <{}>  {}", label, p.name, p.email, p.date);
+        ui.label(person("Autor", &detail.author));
+        if detail.committer.name != detail.author.name
+            || detail.committer.email != detail.author.email
+            || detail.committer.time != detail.author.time
+        {
+            ui.label(egui::RichText::new(person("Commit", &detail.committer)).color(weak));
+        }
+        if let Some(check) = &detail.signature {
+            let (icon, color, text) = signature_badge(ui, Some(check));
+            ui.label(egui::RichText::new(format!("{} {}", icon, text)).color(color)).on_hover_text(&check.detail);
+        }
+        if !detail.parents.is_empty() {
+            let parents: Vec<&str> = detail.parents.iter().map(|p| &p[..7]).collect();
+            ui.label(egui::RichText::new(format!("Padres: {}", parents.join(" "))).color(weak));
+        }
+        ui.add_space(4.0);
+        ui.label(egui::RichText::new(&detail.message).monospace());
+        ui.add_space(4.0);
+        ui.label(format!(
+            "{} archivo(s), +{} −{} (pulsa uno para ver el diff):",
+            detail.files.len(),
+            detail.insertions,
+            detail.deletions
+        ));
+        for f in &detail.files {
+            ui.horizontal(|ui| {
+                let name = match &f.old_path {
+                    Some(old) => format!("  [{}] {} → {}", f.status, old, f.path),
+                    None => format!("  [{}] {}", f.status, f.path),
+                };
+                if ui.link(name).clicked() {
+                    open_diff = Some(f.path.clone());
+                }
+                if f.status != "deleted" && !f.binary && ui.small_button("Blame").on_hover_text("Blame del archivo en este commit").clicked() {
+                    open_blame = Some(f.path.clone());
+                }
+                if f.binary {
+                    ui.label(egui::RichText::new("binario").color(weak));
+                } else {
+                    ui.label(egui::RichText::new(format!("+{}", f.insertions)).color(egui::Color32::from_rgb(110, 190, 100)));
+                    ui.label(egui::RichText::new(format!("−{}", f.deletions)).color(ui.visuals().error_fg_color));
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            if ui.small_button("Cherry-pick").on_hover_text("Aplica este commit sobre la rama actual con un commit nuevo").clicked() {
+                action = Some(Action::CherryPick);
+            }
+            if ui.small_button("Revertir").on_hover_text("Crea un commit que deshace los cambios de este commit").clicked() {
+                action = Some(Action::Revert);
+            }
+            if ui.small_button("Crear rama desde este commit…").clicked() {
+                action = Some(Action::Branch);
+            }
+        });
+    });
+    let id = detail.id.clone();
+    if let Some(file) = open_diff {
+        app.open_diff(root, microtermi_core::DiffTarget::Commit(id.clone()), Some(&file));
+    }
+    if let Some(file) = open_blame {
+        app.open_blame(root, Some(&file), Some(&id), None);
+    }
+    match action {
+        Some(Action::CherryPick) => app.pick_commit(root, &id, false),
+        Some(Action::Revert) => app.pick_commit(root, &id, true),
+        Some(Action::Branch) => app.open_branch_form(root, crate::app::BranchFormKind::Create, &id[..7]),
+        None => {}
+    }
+}
+
+/// Filtros del historial: ramas, autor, ruta, mensaje y rango de fechas.
+fn history_filters(app: &mut MicrotermiApp, ui: &mut egui::Ui) {
+    let mut apply = false;
+    ui.horizontal_wrapped(|ui| {
+        apply |= ui.checkbox(&mut app.git_log_query.all_branches, "Todas las ramas").changed();
+        let q = &mut app.git_log_query;
+        for (label, value, hint) in [
+            ("Autor:", &mut q.author, "nombre o email"),
+            ("Ruta:", &mut q.path, "src/"),
+            ("Mensaje:", &mut q.message, ""),
+            ("Desde:", &mut q.since, "AAAA-MM-DD"),
+            ("Hasta:", &mut q.until, "AAAA-MM-DD"),
+        ] {
+            ui.label(label);
+            let resp = ui.add(egui::TextEdit::singleline(value).desired_width(100.0).hint_text(hint));
+            apply |= resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        }
+        apply |= ui.button("Aplicar").clicked();
+        if ui.button("Limpiar").clicked() {
+            let all_branches = app.git_log_query.all_branches;
+            app.git_log_query = microtermi_core::LogQuery { all_branches, ..Default::default() };
+            apply = true;
+        }
+    });
+    if app.git_log_query.is_filtered() {
+        ui.label(egui::RichText::new("Con filtros no se dibuja el grafo (faltarían commits intermedios).").color(ui.visuals().weak_text_color()));
+    }
+    if apply {
+        app.load_git_log(false);
+    }
+}
+
 pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
     egui::SidePanel::left("git_repos")
         .resizable(true)
@@ -84,32 +258,31 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                     }
                     ui.horizontal(|ui| {
                         ui.strong("Acciones:");
-                        if ui.button("Pull").clicked() {
-                            if let Ok(repo) = microtermi_core::open_repo(root) {
-                                match microtermi_core::pull(&repo) {
-                                    Ok(msg) => app.message = msg,
-                                    Err(e) => app.message = format!("Error: {}", e),
-                                }
-                                app.refresh_git();
-                            }
+                        if ui.button("Pull").on_hover_text(if app.pull_uses_rebase(root) { "git pull --rebase" } else { "git pull (merge)" }).clicked() {
+                            app.pull_repo(root);
                         }
                         if ui.button("Push").clicked() {
-                            if let Ok(repo) = microtermi_core::open_repo(root) {
-                                match microtermi_core::push(&repo) {
-                                    Ok(msg) => app.message = msg,
-                                    Err(e) => app.message = format!("Error: {}", e),
-                                }
-                                app.refresh_git();
-                            }
+                            app.start_git_task(GitTaskKind::Push(Default::default()), root);
                         }
                         if ui.button("Refrescar").clicked() {
                             app.refresh_git();
                         }
+                        let mut rebase = app.pull_uses_rebase(root);
+                        if ui
+                            .checkbox(&mut rebase, "Pull con rebase")
+                            .on_hover_text("Estrategia de pull de este repositorio: rebase en lugar de merge")
+                            .changed()
+                        {
+                            app.set_pull_rebase(root, rebase);
+                        }
                     });
                     ui.add_space(4.0);
                     ui.horizontal(|ui| {
                         ui.label("Rama:");
                         ui.label(egui::RichText::new(app.git_branch.as_deref().unwrap_or("—")).strong());
+                        if let Some(st) = &app.git_status {
+                            crate::shared::tracking_label(ui, st);
+                        }
                         if !app.git_local_branches.is_empty() {
                             egui::ComboBox::from_id_salt("git_branch_tab")
                                 .selected_text(if app.selected_git_branch.is_empty() { "—" } else { &app.selected_git_branch })
@@ -124,66 +297,91 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                                 app.git_checkout_branch();
                             }
                         }
+                        let selected = app.selected_git_branch.clone();
+                        crate::shared::branch_buttons(app, ui, root, &selected);
                     });
-                    if let Some(clean) = app.git_clean {
-                        if clean {
-                            ui.label(egui::RichText::new("Estado: limpio").color(ui.visuals().weak_text_color()));
-                        } else {
-                            ui.label("Archivos modificados:");
-                            for f in &app.git_modified {
-                                ui.label(egui::RichText::new(f).font(egui::FontId::monospace(12.0)));
-                            }
-                        }
+                    crate::shared::branch_form_panel(app, ui, root);
+                    crate::shared::pending_checkout_panel(app, ui, root);
+                    crate::shared::pending_push_panel(app, ui, root);
+                    crate::shared::merge_conflicts_panel(app, ui, root);
+                    crate::shared::pick_panel(app, ui, root);
+                    crate::shared::rebase_panel(app, ui, root);
+                    if let Some(st) = app.git_status.clone() {
+                        crate::shared::changes_panel(app, ui, root, &st);
                     }
                     ui.add_space(4.0);
-                    ui.horizontal(|ui| {
-                        ui.label("Mensaje:");
-                        ui.text_edit_singleline(&mut app.commit_message);
-                        if ui.button("Commit").clicked() {
-                            if let Ok(repo) = microtermi_core::open_repo(root) {
-                                let path_refs: Vec<&Path> = app.git_modified.iter().map(|s| Path::new(s)).collect();
-                                if let Err(e) = microtermi_core::commit(&repo, &app.commit_message, &path_refs) {
-                                    app.message = format!("Error: {}", e);
-                                } else {
-                                    app.message = "Commit realizado.".to_string();
-                                    app.commit_message.clear();
-                                    app.refresh_git();
-                                }
-                            }
-                        }
-                    });
+                    let status = app.git_status.clone();
+                    crate::shared::commit_box(app, ui, root, status.as_ref());
+                    ui.add_space(8.0);
+                    crate::shared::stash_panel(app, ui, root);
+                    ui.add_space(8.0);
+                    crate::shared::tags_panel(app, ui, root, None);
                     ui.add_space(12.0);
                     ui.separator();
                     ui.add_space(8.0);
-                    ui.heading("Historial de commits");
+                    ui.horizontal(|ui| {
+                        ui.heading("Historial de commits");
+                        if ui.button("Explorar archivos…").on_hover_text("Blame de cualquier archivo del repo").clicked() {
+                            app.open_blame(root, None, None, None);
+                        }
+                    });
+                    history_filters(app, ui);
                     let mut click_log: Option<usize> = None;
-                    egui::ScrollArea::vertical().max_height(220.0).show(ui, |ui| {
+                    let mut load_more = false;
+                    let accent = ui.visuals().hyperlink_color;
+                    let weak = ui.visuals().weak_text_color();
+                    egui::ScrollArea::vertical().id_salt("git_history").max_height(320.0).show(ui, |ui| {
+                        ui.spacing_mut().item_spacing.y = 0.0;
+                        let last = app.git_log.len().saturating_sub(1);
                         for (i, c) in app.git_log.iter().enumerate() {
-                            let selected = app.git_log_selected == Some(i);
-                            let line = format!("{}  {}  {}  {}", c.id_short, c.date, c.author, c.message);
-                            if ui.selectable_label(selected, line).clicked() {
-                                click_log = Some(i);
+                            let row = ui.horizontal(|ui| {
+                                if let Some(graph) = &c.graph {
+                                    paint_graph_row(ui, graph);
+                                }
+                                for r in &c.refs {
+                                    ui.label(egui::RichText::new(format!("[{}]", r)).color(accent).strong());
+                                }
+                                if c.signed {
+                                    let (icon, color, text) = signature_badge(ui, app.signature_checks.get(&c.id));
+                                    ui.label(egui::RichText::new(icon).color(color)).on_hover_text(text);
+                                }
+                                let selected = app.git_log_selected == Some(i);
+                                if ui.selectable_label(selected, format!("{}  {}", c.id_short, c.summary)).clicked() {
+                                    click_log = Some(i);
+                                }
+                                ui.label(egui::RichText::new(format!("{}  {}", c.author, c.date)).color(weak));
+                            });
+                            if i == last && app.git_log_next.is_some() && ui.is_rect_visible(row.response.rect) {
+                                load_more = true;
                             }
                         }
+                        if app.git_log_next.is_some() {
+                            ui.add_space(4.0);
+                            ui.label(egui::RichText::new("Cargando más commits…").color(weak));
+                        }
                     });
+                    if let Some(e) = &app.git_log_error {
+                        ui.label(egui::RichText::new(e).color(ui.visuals().error_fg_color));
+                    } else if app.git_log.is_empty() {
+                        ui.label(egui::RichText::new("Ningún commit coincide con los filtros.").color(weak));
+                    }
+                    if load_more {
+                        app.load_git_log(true);
+                    }
                     if let Some(idx) = click_log {
                         app.git_log_selected = Some(idx);
-                        if idx < app.git_log.len() {
-                            if let Ok(repo) = microtermi_core::open_repo(root) {
-                                app.git_commit_detail = microtermi_core::commit_changes(&repo, &app.git_log[idx].id_short).unwrap_or_default();
-                            }
-                        }
-                    }
-                    if let Some(idx) = app.git_log_selected {
-                        if idx < app.git_log.len() && !app.git_commit_detail.is_empty() {
-                            ui.add_space(4.0);
-                            ui.label("Archivos en este commit:");
-                            for f in &app.git_commit_detail {
-                                let st = &f.status;
-                                ui.label(format!("  [{}] {}", st, f.path));
+                        app.git_commit_detail = app.git_log.get(idx).and_then(|c| {
+                            microtermi_core::open_repo(root)
+                                .and_then(|repo| microtermi_core::commit_detail(&repo, &c.id))
+                                .ok()
+                        });
+                        if let Some(detail) = &app.git_commit_detail {
+                            if let Some(check) = &detail.signature {
+                                app.signature_checks.insert(detail.id.clone(), check.clone());
                             }
                         }
                     }
+                    commit_detail_panel(app, ui, root);
                 } else {
                     ui.label("La carpeta actual no es un repositorio Git.");
                     ui.label(egui::RichText::new("Pulsa «Clonar en carpeta raíz» en el proyecto GitLab de abajo: el repo clonado se usará aquí para Pull, Push y Commit.").color(ui.visuals().weak_text_color()));
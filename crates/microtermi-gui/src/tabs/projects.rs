@@ -1,7 +1,7 @@
 use eframe::egui;
-use microtermi_core::{save_env, Environment};
-use std::path::Path;
+use microtermi_core::{save_env, EnvEncryption, EnvFormat, Environment};
 
+use crate::app::GitTaskKind;
 use crate::MicrotermiApp;
 
 pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
@@ -32,6 +32,14 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                     ui.strong(&project.name);
                     ui.label(egui::RichText::new("·").color(ui.visuals().weak_text_color()));
                     ui.label(egui::RichText::new(project.path.display().to_string()).small().color(ui.visuals().weak_text_color()));
+                    if let Some(st) = app.project_git_status.as_ref().filter(|_| app.project_git_refreshed_for == Some(idx)) {
+                        ui.label(egui::RichText::new("·").color(ui.visuals().weak_text_color()));
+                        ui.label(egui::RichText::new(format!("🔀 {}", st.branch)).small());
+                        let ab = st.ahead_behind_label();
+                        if !ab.is_empty() {
+                            ui.label(egui::RichText::new(ab).small().strong());
+                        }
+                    }
                 });
                 ui.horizontal(|ui| {
                     ui.label("Ambiente:");
@@ -51,24 +59,60 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                         let mut to_remove = None;
                         let mut keys: Vec<_> = app.env_vars.keys().cloned().collect();
                         keys.sort();
+                        let error_color = ui.visuals().error_fg_color;
                         for k in keys {
                             ui.horizontal(|ui| {
                                 ui.label(format!("{}:", k));
+                                let secret = microtermi_core::is_secret_key(&k, &app.secret_patterns);
+                                let revealed = app.env_revealed.contains(&k);
                                 if let Some(v) = app.env_vars.get_mut(&k) {
-                                    ui.text_edit_singleline(v);
+                                    ui.add(egui::TextEdit::singleline(v).password(secret && !revealed));
+                                }
+                                if secret && ui.small_button(if revealed { "Ocultar" } else { "Mostrar" }).clicked() {
+                                    if revealed {
+                                        app.env_revealed.remove(&k);
+                                    } else {
+                                        app.env_revealed.insert(k.clone());
+                                    }
                                 }
                                 if ui.button("Eliminar").clicked() {
-                                    to_remove = Some(k);
+                                    to_remove = Some(k.clone());
+                                }
+                                let spec = app.env_schema.as_ref().and_then(|s| s.vars.get(&k));
+                                let value = app.env_vars.get(&k).map(|v| v.as_str()).unwrap_or("");
+                                if let Some(spec) = spec {
+                                    if value.is_empty() && spec.required && spec.default.is_none() {
+                                        ui.label(egui::RichText::new("obligatoria").color(error_color));
+                                    } else if !value.is_empty() {
+                                        if let Some(err) = microtermi_core::validate_env_value(spec, value) {
+                                            ui.label(egui::RichText::new(err).color(error_color));
+                                        }
+                                    }
                                 }
                             });
                         }
                         if let Some(k) = to_remove {
                             app.env_vars.remove(&k);
                         }
+                        if let Some(ref schema) = app.env_schema {
+                            for (k, spec) in &schema.vars {
+                                if app.env_vars.contains_key(k) {
+                                    continue;
+                                }
+                                let text = match &spec.default {
+                                    Some(d) => format!("{} (no definida, se usará el default \"{}\")", k, d),
+                                    None if spec.required => format!("{}: falta (obligatoria)", k),
+                                    None => continue,
+                                };
+                                let color = if spec.default.is_none() { error_color } else { ui.visuals().weak_text_color() };
+                                ui.label(egui::RichText::new(text).color(color));
+                            }
+                        }
                         ui.horizontal(|ui| {
                             ui.label("Nueva:");
                             ui.text_edit_singleline(&mut app.env_new_key);
-                            ui.text_edit_singleline(&mut app.env_new_val);
+                            let new_secret = microtermi_core::is_secret_key(&app.env_new_key, &app.secret_patterns);
+                            ui.add(egui::TextEdit::singleline(&mut app.env_new_val).password(new_secret));
                             if ui.button("Añadir").clicked() {
                                 if !app.env_new_key.is_empty() {
                                     app.env_vars.insert(app.env_new_key.clone(), app.env_new_val.clone());
@@ -77,15 +121,54 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                                 }
                             }
                         });
-                        if ui.button("Guardar en disco").clicked() {
-                            if let Some(ref root) = app.root_path {
-                                if let Err(e) = save_env(root, app.environment, &app.env_vars) {
-                                    app.message = format!("Error guardando env: {}", e);
-                                } else {
-                                    app.message = "Variables guardadas.".to_string();
+                        ui.horizontal(|ui| {
+                            if ui.button("Guardar en disco").clicked() {
+                                if let Some(ref root) = app.root_path {
+                                    if let Err(e) = save_env(root, app.environment, &app.env_vars) {
+                                        app.message = format!("Error guardando env: {}", e);
+                                    } else {
+                                        app.message = "Variables guardadas.".to_string();
+                                    }
                                 }
                             }
-                        }
+                            match app.env_encryption {
+                                EnvEncryption::Age => {
+                                    ui.label(egui::RichText::new("🔒 cifrado (age)").color(ui.visuals().weak_text_color()));
+                                }
+                                EnvEncryption::Sops => {
+                                    ui.label(egui::RichText::new("🔒 cifrado (SOPS)").color(ui.visuals().weak_text_color()));
+                                }
+                                EnvEncryption::Plain => {
+                                    if ui.button("Cifrar con age").on_hover_text("Guarda .env.<ambiente>.age y elimina el fichero en claro").clicked() {
+                                        if let Some(root) = app.root_path.clone() {
+                                            match microtermi_core::encrypt_env(&root, app.environment, &app.env_vars) {
+                                                Ok(()) => {
+                                                    app.env_encryption = EnvEncryption::Age;
+                                                    app.message = format!("{} cifrado con age.", app.environment.env_file_name());
+                                                }
+                                                Err(e) => app.message = format!("Error cifrando env: {}", e),
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Formato:");
+                            egui::ComboBox::from_id_salt("env_format")
+                                .selected_text(app.env_format.label())
+                                .show_ui(ui, |ui| {
+                                    for f in EnvFormat::ALL {
+                                        ui.selectable_value(&mut app.env_format, f, f.label());
+                                    }
+                                });
+                            if ui.button("Importar…").clicked() {
+                                app.env_import();
+                            }
+                            if ui.button("Exportar…").clicked() {
+                                app.env_export();
+                            }
+                        });
                     });
                 egui::CollapsingHeader::new("📜 Scripts")
                     .default_open(false)
@@ -147,37 +230,17 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                                     app.refresh_project_git(&path);
                                 }
                                 if ui.button("Fetch").clicked() {
-                                    if let Ok(repo) = microtermi_core::open_repo(&path) {
-                                        match microtermi_core::fetch(&repo) {
-                                            Ok(()) => {
-                                                app.message = "Fetch completado.".to_string();
-                                                app.refresh_project_git(&path);
-                                            }
-                                            Err(e) => app.message = format!("Error: {}", e),
-                                        }
-                                    }
+                                    app.start_git_task(GitTaskKind::Fetch, &path);
                                 }
-                                if ui.button("Pull").clicked() {
-                                    if let Ok(repo) = microtermi_core::open_repo(&path) {
-                                        match microtermi_core::pull(&repo) {
-                                            Ok(msg) => app.message = msg,
-                                            Err(e) => app.message = format!("Error: {}", e),
-                                        }
-                                        app.refresh_project_git(&path);
-                                    }
+                                if ui.button("Pull").on_hover_text(if app.pull_uses_rebase(&path) { "git pull --rebase" } else { "git pull (merge)" }).clicked() {
+                                    app.pull_repo(&path);
                                 }
                                 if ui.button("Push").clicked() {
-                                    if let Ok(repo) = microtermi_core::open_repo(&path) {
-                                        match microtermi_core::push(&repo) {
-                                            Ok(msg) => app.message = msg,
-                                            Err(e) => app.message = format!("Error: {}", e),
-                                        }
-                                        app.refresh_project_git(&path);
-                                    }
+                                    app.start_git_task(GitTaskKind::Push(Default::default()), &path);
                                 }
                                 if ui.button("Stash").clicked() {
                                     if let Ok(mut repo) = microtermi_core::open_repo(&path) {
-                                        match microtermi_core::stash(&mut repo) {
+                                        match microtermi_core::stash(&mut repo, &Default::default()) {
                                             Ok(()) => {
                                                 app.message = "Cambios guardados en stash.".to_string();
                                                 app.refresh_project_git(&path);
@@ -188,12 +251,16 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                                 }
                                 if ui.button("Stash pop").clicked() {
                                     if let Ok(mut repo) = microtermi_core::open_repo(&path) {
-                                        match microtermi_core::stash_pop(&mut repo) {
+                                        match microtermi_core::stash_pop(&mut repo, 0) {
                                             Ok(()) => {
                                                 app.message = "Stash aplicado y eliminado.".to_string();
                                                 app.refresh_project_git(&path);
                                             }
-                                            Err(e) => app.message = format!("Error: {}", e),
+                                            Err(e) => {
+                                                // Aplicado con conflictos: los archivos sí cambiaron.
+                                                app.message = format!("Error: {}", e);
+                                                app.refresh_project_git(&path);
+                                            }
                                         }
                                     }
                                 }
@@ -201,6 +268,9 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                             ui.horizontal(|ui| {
                                 ui.label("Rama local:");
                                 ui.label(egui::RichText::new(app.project_git_branch.as_deref().unwrap_or("—")).strong());
+                                if let Some(st) = &app.project_git_status {
+                                    crate::shared::tracking_label(ui, st);
+                                }
                                 if !app.project_git_local_branches.is_empty() {
                                     egui::ComboBox::from_id_salt("project_git_branch")
                                         .selected_text(if app.project_git_selected_branch.is_empty() { "—" } else { &app.project_git_selected_branch })
@@ -212,17 +282,14 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                                             }
                                         });
                                     if ui.button("Cambiar rama").clicked() && !app.project_git_selected_branch.is_empty() {
-                                        if let Ok(repo) = microtermi_core::open_repo(&path) {
-                                            if microtermi_core::checkout_branch(&repo, &app.project_git_selected_branch).is_ok() {
-                                                app.message = format!("Rama cambiada a {}", app.project_git_selected_branch);
-                                                app.refresh_project_git(&path);
-                                            } else {
-                                                app.message = "Error al cambiar rama.".to_string();
-                                            }
-                                        }
+                                        let branch = app.project_git_selected_branch.clone();
+                                        app.checkout(&path, &branch, false, microtermi_core::CheckoutMode::Safe);
                                     }
                                 }
+                                let selected = app.project_git_selected_branch.clone();
+                                crate::shared::branch_buttons(app, ui, &path, &selected);
                             });
+                            crate::shared::branch_form_panel(app, ui, &path);
                             ui.horizontal(|ui| {
                                 ui.label("Rama remota (origin):");
                                 if !app.project_git_remote_branches.is_empty() {
@@ -236,46 +303,24 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                                             }
                                         });
                                     if ui.button("Cambiar a rama remota").clicked() && !app.project_git_selected_remote_branch.is_empty() {
-                                        if let Ok(repo) = microtermi_core::open_repo(&path) {
-                                            match microtermi_core::checkout_remote_branch(&repo, &app.project_git_selected_remote_branch) {
-                                                Ok(()) => {
-                                                    app.message = format!("Cambiado a rama remota {}", app.project_git_selected_remote_branch);
-                                                    app.refresh_project_git(&path);
-                                                }
-                                                Err(e) => app.message = format!("Error: {}", e),
-                                            }
-                                        }
+                                        let branch = app.project_git_selected_remote_branch.clone();
+                                        app.checkout(&path, &branch, true, microtermi_core::CheckoutMode::Safe);
                                     }
                                 } else {
                                     ui.label(egui::RichText::new("Pulsa Fetch para cargar ramas remotas.").color(ui.visuals().weak_text_color()));
                                 }
                             });
-                            if let Some(clean) = app.project_git_clean {
-                                if clean {
-                                    ui.label(egui::RichText::new("Estado: limpio").color(ui.visuals().weak_text_color()));
-                                } else {
-                                    ui.label("Archivos modificados:");
-                                    for f in &app.project_git_modified {
-                                        ui.label(egui::RichText::new(f).font(egui::FontId::monospace(12.0)));
-                                    }
-                                }
+                            crate::shared::pending_checkout_panel(app, ui, &path);
+                            crate::shared::pending_push_panel(app, ui, &path);
+                            crate::shared::merge_conflicts_panel(app, ui, &path);
+                            crate::shared::pick_panel(app, ui, &path);
+                            crate::shared::rebase_panel(app, ui, &path);
+                            if let Some(st) = app.project_git_status.clone() {
+                                crate::shared::changes_panel(app, ui, &path, &st);
                             }
-                            ui.horizontal(|ui| {
-                                ui.label("Mensaje:");
-                                ui.text_edit_singleline(&mut app.commit_message);
-                                if ui.button("Commit").clicked() {
-                                    if let Ok(repo) = microtermi_core::open_repo(&path) {
-                                        let path_refs: Vec<&Path> = app.project_git_modified.iter().map(|s| Path::new(s)).collect();
-                                        if let Err(e) = microtermi_core::commit(&repo, &app.commit_message, &path_refs) {
-                                            app.message = format!("Error: {}", e);
-                                        } else {
-                                            app.message = "Commit realizado.".to_string();
-                                            app.commit_message.clear();
-                                            app.refresh_project_git(&path);
-                                        }
-                                    }
-                                }
-                            });
+                            let status = app.project_git_status.clone();
+                            crate::shared::commit_box(app, ui, &path, status.as_ref());
+                            crate::shared::tags_panel(app, ui, &path, Some(&path));
                             ui.label("Historial de commits:");
                             let mut click_log: Option<usize> = None;
                             egui::ScrollArea::vertical().max_height(180.0).show(ui, |ui| {
@@ -297,9 +342,16 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                             }
                             if let Some(i) = app.project_git_log_selected {
                                 if i < app.project_git_log.len() && !app.project_git_commit_detail.is_empty() {
-                                    ui.label("Archivos en este commit:");
+                                    ui.label("Archivos en este commit (pulsa para ver el diff):");
+                                    let mut open_diff: Option<String> = None;
                                     for f in &app.project_git_commit_detail {
-                                        ui.label(format!("  [{}] {}", f.status, f.path));
+                                        if ui.link(format!("  [{}] {}  +{} −{}", f.status, f.path, f.insertions, f.deletions)).clicked() {
+                                            open_diff = Some(f.path.clone());
+                                        }
+                                    }
+                                    if let Some(file) = open_diff {
+                                        let id = app.project_git_log[i].id_short.clone();
+                                        app.open_diff(&path, microtermi_core::DiffTarget::Commit(id), Some(&file));
                                     }
                                 }
                             }
@@ -0,0 +1,148 @@
+use eframe::egui;
+
+use crate::app::{MainTab, RepoBulkOp};
+use crate::MicrotermiApp;
+
+/// "limpio", "3 cambios", "merge en curso"... a partir del estado del repo.
+fn state_text(st: &microtermi_core::GitStatus) -> String {
+    if !st.conflicted.is_empty() {
+        return format!("{} en conflicto", st.conflicted.len());
+    }
+    if st.merging {
+        return "merge en curso".to_string();
+    }
+    if st.rebasing {
+        return "rebase en curso".to_string();
+    }
+    let changes = st.modified.len() + st.untracked.len();
+    if changes == 0 {
+        "limpio".to_string()
+    } else {
+        format!("{} cambio(s)", changes)
+    }
+}
+
+pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        let weak = ui.visuals().weak_text_color();
+        let warn = ui.visuals().warn_fg_color;
+        let error_color = ui.visuals().error_fg_color;
+        ui.heading("Repositorios");
+        ui.label(egui::RichText::new("Cada repo que contiene algún proyecto de la carpeta raíz, una sola vez. Marca varios para Fetch, Pull o cambiar de rama a la vez.").color(weak));
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            if ui.button("Refrescar").clicked() {
+                app.refresh_repos();
+            }
+            if app.repos_receiver.is_some() {
+                ui.spinner();
+            }
+            ui.separator();
+            if ui.small_button("Marcar todos").clicked() {
+                app.repos_selected = app.repos.iter().map(|r| r.path.clone()).collect();
+            }
+            if ui.small_button("Ninguno").clicked() {
+                app.repos_selected.clear();
+            }
+            ui.label(egui::RichText::new(format!("{} marcado(s)", app.repos_selected.len())).color(weak));
+        });
+        ui.horizontal(|ui| {
+            let idle = app.repos_bulk.is_none();
+            if ui.add_enabled(idle, egui::Button::new("Fetch")).clicked() {
+                app.repos_bulk_start(RepoBulkOp::Fetch);
+            }
+            if ui
+                .add_enabled(idle, egui::Button::new("Pull"))
+                .on_hover_text("Con rebase en los repos que lo tengan activado en la pestaña Git")
+                .clicked()
+            {
+                app.repos_bulk_start(RepoBulkOp::Pull);
+            }
+            ui.separator();
+            ui.label("Rama:");
+            ui.add(egui::TextEdit::singleline(&mut app.repos_branch).desired_width(180.0));
+            let branch = app.repos_branch.trim().to_string();
+            ui.checkbox(&mut app.repos_auto_stash, "Auto-stash")
+                .on_hover_text("Guarda los cambios locales en el stash, cambia y los reaplica; sin marcar, los repos con cambios se saltan");
+            if ui
+                .add_enabled(idle && !branch.is_empty(), egui::Button::new("Cambiar rama"))
+                .on_hover_text("Donde la rama solo existe en origin se crea siguiéndola")
+                .clicked()
+            {
+                let auto_stash = app.repos_auto_stash;
+                app.repos_bulk_start(RepoBulkOp::Checkout { branch, auto_stash });
+            }
+            if let Some(task) = &app.repos_bulk {
+                ui.separator();
+                ui.spinner();
+                ui.label(&task.label);
+                if ui.button("Cancelar").clicked() {
+                    task.cancel.cancel();
+                }
+            }
+        });
+        ui.add_space(6.0);
+        if app.repos.is_empty() && app.repos_receiver.is_none() {
+            ui.label("No hay repositorios: elige una carpeta raíz con proyectos clonados.");
+        }
+        let mut open_in_git = None;
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::Grid::new("repos_dashboard").striped(true).num_columns(6).spacing([14.0, 4.0]).show(ui, |ui| {
+                ui.label("");
+                ui.strong("Repo");
+                ui.strong("Rama");
+                ui.strong("Estado");
+                ui.strong("↑/↓");
+                ui.strong("Última operación");
+                ui.end_row();
+                for repo in &app.repos {
+                    let mut checked = app.repos_selected.contains(&repo.path);
+                    if ui.checkbox(&mut checked, "").changed() {
+                        if checked {
+                            app.repos_selected.insert(repo.path.clone());
+                        } else {
+                            app.repos_selected.remove(&repo.path);
+                        }
+                    }
+                    if ui.link(&repo.name).on_hover_text(repo.path.display().to_string()).clicked() {
+                        open_in_git = Some(repo.path.clone());
+                    }
+                    match (&repo.status, &repo.error) {
+                        (Some(st), _) => {
+                            let branch = if st.detached { format!("({})", st.branch) } else { st.branch.clone() };
+                            ui.label(egui::RichText::new(branch).strong());
+                            let state = state_text(st);
+                            if st.is_clean && st.conflicted.is_empty() {
+                                ui.label(egui::RichText::new(state).color(weak));
+                            } else {
+                                ui.label(egui::RichText::new(state).color(warn));
+                            }
+                            match &st.upstream {
+                                Some(_) => ui.label(st.ahead_behind_label()),
+                                None => ui.label(egui::RichText::new("sin upstream").color(weak)),
+                            };
+                        }
+                        (None, error) => {
+                            ui.label("—");
+                            ui.label(egui::RichText::new(error.as_deref().unwrap_or("error")).color(error_color));
+                            ui.label("");
+                        }
+                    }
+                    match app.repos_results.get(&repo.path) {
+                        Some(Ok(msg)) => ui.label(egui::RichText::new(msg).color(weak)),
+                        Some(Err(e)) => ui.label(egui::RichText::new(e).color(error_color)),
+                        None => ui.label(""),
+                    };
+                    ui.end_row();
+                }
+            });
+        });
+        if let Some(path) = open_in_git {
+            app.git_repo_path = Some(path);
+            app.main_tab = MainTab::Git;
+            app.persist_app_config();
+            app.refresh_git();
+        }
+        crate::shared::message_label(ui, &app.message);
+    });
+}
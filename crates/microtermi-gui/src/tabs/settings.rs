@@ -20,9 +20,33 @@ pub fn draw(app: &mut MicrotermiApp, ctx: &egui::Context) {
                             .desired_width(280.0),
                     );
                 });
+                ui.checkbox(&mut app.gitlab_token_plaintext, "Guardar el token en texto plano (config.json)")
+                    .on_hover_text("Por defecto el token se guarda en el llavero del sistema.");
                 if ui.button("Guardar").clicked() {
+                    app.save_gitlab_settings();
+                }
+            });
+            ui.add_space(12.0);
+            ui.collapsing("Secretos", |ui| {
+                ui.label(
+                    egui::RichText::new("Claves que coinciden con estos patrones se ocultan en el editor de env y se redactan en la terminal (`*` = cualquier texto).")
+                        .small()
+                        .color(ui.visuals().weak_text_color()),
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Patrones:");
+                    ui.add(egui::TextEdit::singleline(&mut app.secret_patterns_input).desired_width(320.0));
+                });
+                if ui.button("Guardar").clicked() {
+                    app.secret_patterns = app
+                        .secret_patterns_input
+                        .split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect();
+                    app.secret_patterns_input = app.secret_patterns.join(", ");
                     app.persist_app_config();
-                    app.message = "GitLab guardado.".to_string();
+                    app.message = "Patrones de secretos guardados.".to_string();
                 }
             });
             ui.add_space(12.0);
@@ -1,9 +1,9 @@
 use crate::config;
 use microtermi_core::{
     branches, branches_remote, checkout_branch, checkout_remote_branch, clone_repo,
-    clone_url_with_token, commit, commit_changes, fetch, list_branches, list_projects, log,
+    commit, commit_changes, fetch, list_branches, list_projects, log,
     open_repo, push, pull, stash, stash_pop, status,
-    run_script_captured, Environment, Project,
+    run_script_captured, CancelToken, CheckoutMode, Environment, GitProgress, ProgressSink, Project,
 };
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
@@ -34,8 +34,8 @@ pub fn save_config_root(root: String) {
 }
 
 #[tauri::command]
-pub fn save_config_gitlab(url: String, token: String) {
-    config::save_config_gitlab(&url, &token);
+pub fn save_config_gitlab(url: String, token: String, plaintext: Option<bool>) -> Result<(), String> {
+    config::save_config_gitlab(&url, &token, plaintext.unwrap_or(false))
 }
 
 #[tauri::command]
@@ -48,6 +48,60 @@ pub fn scan_projects(root: String) -> Result<Vec<Project>, String> {
     microtermi_core::scan_projects(Path::new(&root)).map_err(|e| e.to_string())
 }
 
+/// Estado de cada repo que contiene la carpeta raíz o alguno de sus proyectos (sin repetir).
+#[tauri::command(async)]
+pub fn git_repos_status(root: String) -> Result<Vec<microtermi_core::RepoSummary>, String> {
+    let root = Path::new(&root);
+    let mut paths: Vec<std::path::PathBuf> = microtermi_core::scan_projects(root)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|p| p.path)
+        .collect();
+    paths.push(root.to_path_buf());
+    Ok(microtermi_core::repo_summaries(&microtermi_core::repo_roots(&paths)))
+}
+
+/// Fetch en paralelo en varios repos; resultado por repo (ruta, Ok/Err).
+#[tauri::command(async)]
+pub fn git_repos_fetch(paths: Vec<String>) -> Vec<(String, Result<(), String>)> {
+    let paths: Vec<std::path::PathBuf> = paths.into_iter().map(Into::into).collect();
+    microtermi_core::for_each_repo(&paths, |p| open_repo(p).and_then(|repo| fetch(&repo, Default::default())))
+        .into_iter()
+        .map(|(p, r)| (p.to_string_lossy().into_owned(), r.map_err(|e| e.to_string())))
+        .collect()
+}
+
+/// Pull en paralelo en varios repos (con rebase si `rebase`); resultado por repo.
+#[tauri::command(async)]
+pub fn git_repos_pull(paths: Vec<String>, rebase: Option<bool>) -> Vec<(String, Result<microtermi_core::PullOutcome, String>)> {
+    let paths: Vec<std::path::PathBuf> = paths.into_iter().map(Into::into).collect();
+    let rebase = rebase.unwrap_or(false);
+    microtermi_core::for_each_repo(&paths, |p| {
+        open_repo(p).and_then(|repo| {
+            if rebase {
+                microtermi_core::pull_rebase(&repo, Default::default())
+            } else {
+                pull(&repo, Default::default())
+            }
+        })
+    })
+    .into_iter()
+    .map(|(p, r)| (p.to_string_lossy().into_owned(), r.map_err(|e| e.to_string())))
+    .collect()
+}
+
+/// Cambia varios repos a `branch` (creándola desde origin/<branch> donde solo existe en remoto). Sin
+/// `auto_stash` los repos con cambios locales se saltan. Resultado por repo.
+#[tauri::command(async)]
+pub fn git_repos_checkout(
+    paths: Vec<String>,
+    branch: String,
+    auto_stash: Option<bool>,
+) -> Vec<microtermi_core::BranchSwitchResult> {
+    let paths: Vec<std::path::PathBuf> = paths.into_iter().map(Into::into).collect();
+    microtermi_core::switch_branch_in_repos(&paths, &branch, auto_stash.unwrap_or(false))
+}
+
 fn env_from_str(s: &str) -> Environment {
     match s.to_lowercase().as_str() {
         "staging" => Environment::Staging,
@@ -70,6 +124,47 @@ pub fn save_env(
     microtermi_core::save_env(Path::new(&root), env_from_str(&environment), &vars).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn export_env(
+    vars: HashMap<String, String>,
+    environment: String,
+    format: microtermi_core::EnvFormat,
+    name: String,
+) -> Result<String, String> {
+    microtermi_core::export_env(&vars, env_from_str(&environment), format, &name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn import_env(
+    content: String,
+    format: microtermi_core::EnvFormat,
+) -> Result<HashMap<String, String>, String> {
+    microtermi_core::import_env(&content, format).map_err(|e| e.to_string())
+}
+
+/// Cifra el ambiente con age (escribe .env.<env>.age y elimina el fichero en claro).
+#[tauri::command]
+pub fn encrypt_env(
+    root: String,
+    environment: String,
+    vars: HashMap<String, String>,
+) -> Result<(), String> {
+    microtermi_core::encrypt_env(Path::new(&root), env_from_str(&environment), &vars).map_err(|e| e.to_string())
+}
+
+/// Valida las variables contra el esquema de la raíz; devuelve la lista de errores por clave (vacía si son válidas).
+#[tauri::command]
+pub fn validate_env(
+    root: String,
+    vars: HashMap<String, String>,
+) -> Result<Vec<microtermi_core::EnvVarIssue>, String> {
+    let schema = match microtermi_core::load_env_schema(Path::new(&root)).map_err(|e| e.to_string())? {
+        Some(s) => s,
+        None => return Ok(Vec::new()),
+    };
+    Ok(microtermi_core::validate_env(&schema, &vars).err().unwrap_or_default())
+}
+
 #[tauri::command]
 pub fn common_script_names(root: String) -> Result<Vec<String>, String> {
     let projects = microtermi_core::scan_projects(Path::new(&root)).map_err(|e| e.to_string())?;
@@ -89,14 +184,24 @@ pub fn common_script_names(root: String) -> Result<Vec<String>, String> {
     Ok(names)
 }
 
+/// Lanza el script con las variables validadas contra el esquema de `root` (con sus defaults aplicados).
+/// Si no pasan la validación no se lanza nada y el error lista los problemas por clave.
 #[tauri::command]
 pub fn run_script_start(
     app: AppHandle,
+    root: String,
     project_path: String,
     script_name: String,
     env_vars: HashMap<String, String>,
     state: State<'_, crate::TerminalState>,
 ) -> Result<String, String> {
+    let env_vars = match microtermi_core::load_env_schema(Path::new(&root)).map_err(|e| e.to_string())? {
+        Some(schema) => microtermi_core::validate_env(&schema, &env_vars).map_err(|issues| {
+            let list: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+            format!("Invalid environment variables:\n{}", list.join("\n"))
+        })?,
+        None => env_vars,
+    };
     let project = Project {
         name: script_name.clone(),
         path: std::path::PathBuf::from(&project_path),
@@ -109,10 +214,12 @@ pub fn run_script_start(
         let mut procs = state.processes.lock().unwrap();
         procs.insert(session_id.clone(), child);
     }
+    let secrets = microtermi_core::secret_values(&env_vars, &crate::config::load_secret_patterns());
     let app_emit = app.clone();
     let sid = session_id.clone();
     thread::spawn(move || {
         while let Ok(line) = receiver.recv() {
+            let line = microtermi_core::redact_secrets(&line, &secrets);
             let _ = app_emit.emit("terminal-line", (sid.clone(), line));
         }
         let _ = app_emit.emit("terminal-end", sid);
@@ -156,41 +263,274 @@ pub fn git_branches_remote(path: String) -> Result<Vec<String>, String> {
     branches_remote(&repo).map_err(|e| e.to_string())
 }
 
+/// Ejecuta una operación git de red emitiendo "git-progress" con (task_id, progreso). Mientras dura,
+/// `git_cancel(task_id)` la cancela. Sin `task_id` se genera uno (solo útil para filtrar eventos).
+fn with_git_task<T>(
+    app: &AppHandle,
+    state: &crate::GitTaskState,
+    task_id: Option<String>,
+    op: impl FnOnce(ProgressSink<'_>) -> Result<T, microtermi_core::GitError>,
+) -> Result<T, String> {
+    let task_id = task_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let cancel = CancelToken::new();
+    state.cancels.lock().unwrap().insert(task_id.clone(), cancel.clone());
+    let app_emit = app.clone();
+    let event_id = task_id.clone();
+    let on_progress = move |p: GitProgress| {
+        let _ = app_emit.emit("git-progress", (event_id.clone(), p));
+    };
+    let result = op(ProgressSink::new(&on_progress, &cancel));
+    state.cancels.lock().unwrap().remove(&task_id);
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn git_cancel(task_id: String, state: State<'_, crate::GitTaskState>) -> Result<(), String> {
+    if let Some(cancel) = state.cancels.lock().unwrap().get(&task_id) {
+        cancel.cancel();
+    }
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub fn git_fetch(
+    app: AppHandle,
+    path: String,
+    task_id: Option<String>,
+    state: State<'_, crate::GitTaskState>,
+) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    with_git_task(&app, &state, task_id, |sink| fetch(&repo, sink))
+}
+
+/// `mode`: "safe" (por defecto), "auto_stash" o "force".
+#[tauri::command]
+pub fn git_checkout_branch(path: String, branch: String, mode: Option<CheckoutMode>) -> Result<(), String> {
+    let mut repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    checkout_branch(&mut repo, &branch, mode.unwrap_or(CheckoutMode::Safe)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn git_checkout_remote_branch(path: String, branch: String, mode: Option<CheckoutMode>) -> Result<(), String> {
+    let mut repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    checkout_remote_branch(&mut repo, &branch, mode.unwrap_or(CheckoutMode::Safe)).map_err(|e| e.to_string())
+}
+
+/// Crea `name` desde `start_point` (rama, `origin/rama` o commit; por defecto HEAD). Desde una rama
+/// remota queda siguiéndola. Con `checkout` cambia a ella.
+#[tauri::command]
+pub fn git_create_branch(path: String, name: String, start_point: Option<String>, checkout: Option<bool>) -> Result<(), String> {
+    let mut repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::create_branch(&repo, &name, start_point.as_deref().unwrap_or("HEAD")).map_err(|e| e.to_string())?;
+    if checkout.unwrap_or(false) {
+        checkout_branch(&mut repo, name.trim(), CheckoutMode::Safe).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn git_rename_branch(path: String, branch: String, new_name: String) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::rename_branch(&repo, &branch, &new_name).map_err(|e| e.to_string())
+}
+
+/// Sin `force` falla si la rama tiene commits sin fusionar en HEAD ni en su upstream.
+#[tauri::command]
+pub fn git_delete_branch(path: String, branch: String, force: Option<bool>) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::delete_branch(&repo, &branch, force.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+/// Fija el upstream (`origin/rama`) de una rama local, o lo quita si `upstream` es None.
+#[tauri::command]
+pub fn git_set_upstream(path: String, branch: String, upstream: Option<String>) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::set_upstream(&repo, &branch, upstream.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command(async)]
+pub fn git_pull(
+    app: AppHandle,
+    path: String,
+    rebase: Option<bool>,
+    task_id: Option<String>,
+    state: State<'_, crate::GitTaskState>,
+) -> Result<microtermi_core::PullOutcome, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    with_git_task(&app, &state, task_id, |sink| {
+        if rebase.unwrap_or(false) {
+            microtermi_core::pull_rebase(&repo, sink)
+        } else {
+            pull(&repo, sink)
+        }
+    })
+}
+
+#[tauri::command(async)]
+pub fn git_rebase_continue(
+    app: AppHandle,
+    path: String,
+    task_id: Option<String>,
+    state: State<'_, crate::GitTaskState>,
+) -> Result<microtermi_core::PullOutcome, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    with_git_task(&app, &state, task_id, |sink| microtermi_core::rebase_continue(&repo, sink))
+}
+
+#[tauri::command(async)]
+pub fn git_rebase_skip(
+    app: AppHandle,
+    path: String,
+    task_id: Option<String>,
+    state: State<'_, crate::GitTaskState>,
+) -> Result<microtermi_core::PullOutcome, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    with_git_task(&app, &state, task_id, |sink| microtermi_core::rebase_skip(&repo, sink))
+}
+
+#[tauri::command]
+pub fn git_rebase_abort(path: String) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::rebase_abort(&repo).map_err(|e| e.to_string())
+}
+
+#[tauri::command(async)]
+pub fn git_merge_continue(path: String) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::merge_continue(&repo).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn git_merge_abort(path: String) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::merge_abort(&repo).map_err(|e| e.to_string())
+}
+
+/// `options`: `{ "set_upstream": bool, "force_with_lease": bool }` (ambos opcionales, por defecto false).
+#[tauri::command(async)]
+pub fn git_push(
+    app: AppHandle,
+    path: String,
+    options: Option<microtermi_core::PushOptions>,
+    task_id: Option<String>,
+    state: State<'_, crate::GitTaskState>,
+) -> Result<microtermi_core::PushOutcome, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    with_git_task(&app, &state, task_id, |sink| push(&repo, options.unwrap_or_default(), sink))
+}
+
 #[tauri::command]
-pub fn git_fetch(path: String) -> Result<(), String> {
+pub fn git_stage(path: String, paths: Vec<String>) -> Result<(), String> {
     let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
-    fetch(&repo).map_err(|e| e.to_string())
+    let path_refs: Vec<&Path> = paths.iter().map(Path::new).collect();
+    microtermi_core::stage(&repo, &path_refs).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn git_checkout_branch(path: String, branch: String) -> Result<(), String> {
+pub fn git_unstage(path: String, paths: Vec<String>) -> Result<(), String> {
     let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
-    checkout_branch(&repo, &branch).map_err(|e| e.to_string())
+    let path_refs: Vec<&Path> = paths.iter().map(Path::new).collect();
+    microtermi_core::unstage(&repo, &path_refs).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn git_checkout_remote_branch(path: String, branch: String) -> Result<(), String> {
+pub fn git_discard(path: String, paths: Vec<String>) -> Result<(), String> {
     let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
-    checkout_remote_branch(&repo, &branch).map_err(|e| e.to_string())
+    let path_refs: Vec<&Path> = paths.iter().map(Path::new).collect();
+    microtermi_core::discard(&repo, &path_refs).map_err(|e| e.to_string())
 }
 
+/// Diff por archivo con hunks y líneas del árbol de trabajo, del index o de un commit; `file` limita a un archivo.
 #[tauri::command]
-pub fn git_pull(path: String) -> Result<String, String> {
+pub fn git_diff(
+    path: String,
+    target: microtermi_core::DiffTarget,
+    file: Option<String>,
+) -> Result<Vec<microtermi_core::FileDiff>, String> {
     let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
-    pull(&repo).map_err(|e| e.to_string())
+    microtermi_core::diff(&repo, &target, file.as_deref().map(Path::new)).map_err(|e| e.to_string())
 }
 
+/// Prepara un hunk (índice en el diff del árbol de trabajo de `file`) o solo las líneas `lines` del hunk.
 #[tauri::command]
-pub fn git_push(path: String) -> Result<String, String> {
+pub fn git_stage_hunk(path: String, file: String, hunk: usize, lines: Option<Vec<usize>>) -> Result<(), String> {
     let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
-    push(&repo).map_err(|e| e.to_string())
+    microtermi_core::stage_hunk(&repo, Path::new(&file), hunk, lines.as_deref()).map_err(|e| e.to_string())
 }
 
+/// Quita del index un hunk (índice en el diff del index de `file`) o solo las líneas `lines` del hunk.
 #[tauri::command]
-pub fn git_commit(path: String, message: String, paths: Vec<String>) -> Result<(), String> {
+pub fn git_unstage_hunk(path: String, file: String, hunk: usize, lines: Option<Vec<usize>>) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::unstage_hunk(&repo, Path::new(&file), hunk, lines.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Commit de lo preparado en el index; si `paths` no está vacío se preparan antes esos archivos.
+/// `options`: `{ "amend": bool, "no_verify": bool }` (opcionales). El mensaje no se valida: usar antes
+/// `git_lint_commit_message`. La salida de los hooks del repo se emite como "git-hook-output" con
+/// (task_id, línea); `git_cancel(task_id)` los detiene y cancela el commit.
+#[tauri::command(async)]
+pub fn git_commit(
+    app: AppHandle,
+    path: String,
+    message: String,
+    paths: Vec<String>,
+    options: Option<microtermi_core::CommitOptions>,
+    task_id: Option<String>,
+    state: State<'_, crate::GitTaskState>,
+) -> Result<(), String> {
     let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
     let path_refs: Vec<&Path> = paths.iter().map(|s| Path::new(s)).collect();
-    commit(&repo, &message, &path_refs).map_err(|e| e.to_string())
+    let task_id = task_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let cancel = CancelToken::new();
+    state.cancels.lock().unwrap().insert(task_id.clone(), cancel.clone());
+    let on_output = |line: &str| {
+        let _ = app.emit("git-hook-output", (task_id.clone(), line.to_string()));
+    };
+    let sink = microtermi_core::HookSink::new(&on_output, &cancel);
+    let result = commit(&repo, &message, &path_refs, options.unwrap_or_default(), sink);
+    state.cancels.lock().unwrap().remove(&task_id);
+    result.map_err(|e| e.to_string())
+}
+
+/// Hooks de commit que ejecutaría el repo (respetando `core.hooksPath`); con `no_verify`, solo los
+/// que `--no-verify` no salta.
+#[tauri::command]
+pub fn git_commit_hooks(path: String, no_verify: Option<bool>) -> Result<Vec<String>, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::commit_hooks(&repo, no_verify.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+/// Plantilla de `commit.template` del repo, o null si no hay.
+#[tauri::command]
+pub fn git_commit_template(path: String) -> Result<Option<String>, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::commit_template(&repo).map_err(|e| e.to_string())
+}
+
+/// Reglas commitlint del repo (o las de config-conventional si no tiene) y si el repo las configura.
+#[tauri::command]
+pub fn git_commit_lint_rules(path: String) -> Result<(microtermi_core::CommitLintRules, bool), String> {
+    let root = microtermi_core::repo_root(Path::new(&path)).ok_or_else(|| microtermi_core::GitError::NoRepo.to_string())?;
+    let rules = microtermi_core::CommitLintRules::load(&root).map_err(|e| e.to_string())?;
+    let configured = rules.is_some();
+    Ok((rules.unwrap_or_default(), configured))
+}
+
+/// Problemas del mensaje según las reglas commitlint del repo; vacío si se puede commitear tal cual.
+#[tauri::command]
+pub fn git_lint_commit_message(path: String, message: String) -> Result<Vec<microtermi_core::LintProblem>, String> {
+    let (rules, _) = git_commit_lint_rules(path)?;
+    Ok(microtermi_core::lint_commit_message(&message, &rules))
+}
+
+/// Scopes sugeridos (proyectos afectados) para los archivos preparados del repo.
+#[tauri::command]
+pub fn git_commit_scopes(path: String, projects: Vec<Project>) -> Result<Vec<String>, String> {
+    let root = microtermi_core::repo_root(Path::new(&path)).ok_or_else(|| microtermi_core::GitError::NoRepo.to_string())?;
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    let status = microtermi_core::status(&repo).map_err(|e| e.to_string())?;
+    Ok(microtermi_core::commit_scopes(&root, &status.staged, &projects))
 }
 
 #[tauri::command]
@@ -199,6 +539,20 @@ pub fn git_log(path: String, max_count: usize) -> Result<Vec<microtermi_core::Co
     log(&repo, max_count).map_err(|e| e.to_string())
 }
 
+/// Página del historial con grafo y filtros. Pasar el `next` de la página anterior como `cursor` para
+/// seguir; `limit` por defecto 100.
+#[tauri::command(async)]
+pub fn git_log_page(
+    path: String,
+    query: Option<microtermi_core::LogQuery>,
+    cursor: Option<microtermi_core::LogCursor>,
+    limit: Option<usize>,
+) -> Result<microtermi_core::LogPage, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::log_page(&repo, &query.unwrap_or_default(), cursor.as_ref(), limit.unwrap_or(100))
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn git_commit_changes(
     path: String,
@@ -208,16 +562,133 @@ pub fn git_commit_changes(
     commit_changes(&repo, &commit_id).map_err(|e| e.to_string())
 }
 
+/// Verifica la firma del commit `rev` (gpg, ssh o x509); null si no está firmado.
+#[tauri::command(async)]
+pub fn git_verify_commit_signature(path: String, rev: String) -> Result<Option<microtermi_core::SignatureCheck>, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::verify_commit_signature(&repo, &rev).map_err(|e| e.to_string())
+}
+
+/// `commit_id`: id completo o prefijo no ambiguo, rama, tag o revisión (`HEAD~1`).
+/// Incluye la verificación de la firma, que lanza gpg/ssh-keygen: por eso no corre en el hilo principal.
+#[tauri::command(async)]
+pub fn git_commit_detail(path: String, commit_id: String) -> Result<microtermi_core::CommitDetail, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::commit_detail(&repo, &commit_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command(async)]
+pub fn git_cherry_pick(path: String, commit_id: String) -> Result<microtermi_core::PickOutcome, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::cherry_pick(&repo, &commit_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command(async)]
+pub fn git_revert(path: String, commit_id: String) -> Result<microtermi_core::PickOutcome, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::revert(&repo, &commit_id).map_err(|e| e.to_string())
+}
+
+/// `file`: ruta relativa a la raíz del repo. Sin `rev`, el archivo del árbol de trabajo (las líneas sin
+/// commit vienen con `commit_id: null`).
+#[tauri::command(async)]
+pub fn git_blame(path: String, file: String, rev: Option<String>) -> Result<Vec<microtermi_core::BlameHunk>, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::blame(&repo, Path::new(&file), rev.as_deref()).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
-pub fn git_stash(path: String) -> Result<(), String> {
+pub fn git_tracked_files(path: String, rev: Option<String>) -> Result<Vec<String>, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::tracked_files(&repo, rev.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn git_list_tags(path: String) -> Result<Vec<microtermi_core::TagInfo>, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::list_tags(&repo).map_err(|e| e.to_string())
+}
+
+/// `target`: revisión (por defecto HEAD). Con `message` no vacío el tag es anotado.
+#[tauri::command]
+pub fn git_create_tag(path: String, name: String, target: Option<String>, message: Option<String>) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::create_tag(&repo, &name, target.as_deref().unwrap_or_default(), message.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn git_delete_tag(path: String, name: String) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::delete_tag(&repo, &name).map_err(|e| e.to_string())
+}
+
+#[tauri::command(async)]
+pub fn git_push_tags(
+    app: AppHandle,
+    path: String,
+    names: Vec<String>,
+    task_id: Option<String>,
+    state: State<'_, crate::GitTaskState>,
+) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    with_git_task(&app, &state, task_id, |sink| microtermi_core::push_tags(&repo, &names, sink))
+}
+
+/// `project`: carpeta con el `package.json`; `bump`: "major", "minor" o "patch" (por defecto).
+#[tauri::command]
+pub fn git_next_release_tag(
+    path: String,
+    project: String,
+    bump: Option<microtermi_core::VersionBump>,
+) -> Result<microtermi_core::ReleaseTag, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::next_release_tag(&repo, Path::new(&project), bump.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+/// Termina un cherry-pick o revert con conflictos resueltos; devuelve el id corto del commit.
+#[tauri::command(async)]
+pub fn git_pick_continue(path: String) -> Result<String, String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::pick_continue(&repo).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn git_pick_abort(path: String) -> Result<(), String> {
+    let repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::pick_abort(&repo).map_err(|e| e.to_string())
+}
+
+/// Guarda un stash; sin `options` como `git stash` (sin mensaje, solo archivos con seguimiento).
+#[tauri::command]
+pub fn git_stash(path: String, options: Option<microtermi_core::StashOptions>) -> Result<(), String> {
+    let mut repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    stash(&mut repo, &options.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn git_stash_list(path: String) -> Result<Vec<microtermi_core::StashEntry>, String> {
     let mut repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
-    stash(&mut repo).map_err(|e| e.to_string())
+    microtermi_core::stash_list(&mut repo).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn git_stash_pop(path: String) -> Result<(), String> {
+pub fn git_stash_apply(path: String, index: usize) -> Result<(), String> {
     let mut repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
-    stash_pop(&mut repo).map_err(|e| e.to_string())
+    microtermi_core::stash_apply(&mut repo, index).map_err(|e| e.to_string())
+}
+
+/// Aplica y elimina `stash@{index}` (por defecto el último, stash@{0}).
+#[tauri::command]
+pub fn git_stash_pop(path: String, index: Option<usize>) -> Result<(), String> {
+    let mut repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    stash_pop(&mut repo, index.unwrap_or(0)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn git_stash_drop(path: String, index: usize) -> Result<(), String> {
+    let mut repo = open_repo(Path::new(&path)).map_err(|e| e.to_string())?;
+    microtermi_core::stash_drop(&mut repo, index).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -238,10 +709,19 @@ pub fn gitlab_list_branches(
     list_branches(&url, &token, project_id).map_err(|e| e.to_string())
 }
 
-#[tauri::command]
-pub fn gitlab_clone(http_url: String, token: String, dest: String) -> Result<(), String> {
-    let url = clone_url_with_token(&http_url, &token);
+#[tauri::command(async)]
+pub fn gitlab_clone(
+    app: AppHandle,
+    http_url: String,
+    token: String,
+    dest: String,
+    task_id: Option<String>,
+    state: State<'_, crate::GitTaskState>,
+) -> Result<(), String> {
+    if !token.trim().is_empty() {
+        microtermi_core::set_session_gitlab_token(Some(&token));
+    }
     let path = std::path::Path::new(&dest);
-    clone_repo(&url, path).map_err(|e| e.to_string())?;
+    with_git_task(&app, &state, task_id, |sink| clone_repo(&http_url, path, sink))?;
     Ok(())
 }
@@ -4,54 +4,82 @@ fn config_path() -> Option<PathBuf> {
     dirs::config_dir().map(|d| d.join("microtermi").join("config.json"))
 }
 
-pub fn load_config() -> (Option<PathBuf>, Option<String>, Option<String>) {
+fn load_config_json() -> serde_json::Value {
     let path = match config_path() {
         Some(p) => p,
-        None => return (None, None, None),
+        None => return serde_json::json!({}),
     };
     let content = match std::fs::read_to_string(&path) {
         Ok(c) => c,
-        Err(_) => return (None, None, None),
-    };
-    let json: serde_json::Value = match serde_json::from_str(&content) {
-        Ok(j) => j,
-        Err(_) => return (None, None, None),
+        Err(_) => return serde_json::json!({}),
     };
+    serde_json::from_str(&content).unwrap_or_else(|_| serde_json::json!({}))
+}
+
+fn save_config_json(json: &serde_json::Value) {
+    if let Some(path) = config_path() {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = std::fs::write(path, json.to_string());
+    }
+}
+
+/// Patrones de claves secretas configurados (los mismos que usa la GUI); por defecto `DEFAULT_SECRET_PATTERNS`.
+pub fn load_secret_patterns() -> Vec<String> {
+    match load_config_json().get("secret_patterns").and_then(|v| v.as_array()) {
+        Some(arr) => arr.iter().filter_map(|v| v.as_str()).map(String::from).collect(),
+        None => microtermi_core::DEFAULT_SECRET_PATTERNS.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+fn token_plaintext(json: &serde_json::Value) -> bool {
+    json.get("gitlab_token_plaintext").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// Carga raíz, URL y token de GitLab. El token se lee del llavero del sistema salvo opt-in a texto plano;
+/// un token heredado en config.json se migra al llavero la primera vez.
+pub fn load_config() -> (Option<PathBuf>, Option<String>, Option<String>) {
+    let mut json = load_config_json();
     let root = json
         .get("last_root")
         .and_then(|v| v.as_str())
         .map(PathBuf::from);
     let gitlab_url = json.get("gitlab_url").and_then(|v| v.as_str()).map(String::from);
-    let gitlab_token = json.get("gitlab_token").and_then(|v| v.as_str()).map(String::from);
+    let config_token = json.get("gitlab_token").and_then(|v| v.as_str()).map(String::from);
+    let source = microtermi_core::resolve_gitlab_token(config_token.as_deref(), token_plaintext(&json));
+    if config_token.is_some_and(|t| !t.trim().is_empty()) && !source.in_config {
+        // Migrado al llavero: se quita de config.json.
+        json["gitlab_token"] = serde_json::json!("");
+        save_config_json(&json);
+    }
+    let gitlab_token = source.token;
+    microtermi_core::set_session_gitlab_token(gitlab_token.as_deref());
+    microtermi_core::set_session_gitlab_url(gitlab_url.as_deref());
     (root, gitlab_url, gitlab_token)
 }
 
 pub fn save_config_root(root: &Path) {
-    if let Some(path) = config_path() {
-        if let Some(parent) = path.parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
-        let (_, url, token) = load_config();
-        let json = serde_json::json!({
-            "last_root": root.to_string_lossy(),
-            "gitlab_url": url.unwrap_or_default(),
-            "gitlab_token": token.unwrap_or_default(),
-        });
-        let _ = std::fs::write(path, json.to_string());
-    }
+    let mut json = load_config_json();
+    json["last_root"] = serde_json::json!(root.to_string_lossy());
+    save_config_json(&json);
 }
 
-pub fn save_config_gitlab(url: &str, token: &str) {
-    if let Some(path) = config_path() {
-        if let Some(parent) = path.parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
-        let (root, _, _) = load_config();
-        let json = serde_json::json!({
-            "last_root": root.map(|p| p.to_string_lossy().to_string()).unwrap_or_default(),
-            "gitlab_url": url,
-            "gitlab_token": token,
-        });
-        let _ = std::fs::write(path, json.to_string());
+/// Guarda URL y token. El token va al llavero; solo se escribe en config.json con `plaintext` (opt-in, y
+/// entonces se borra del llavero) o si el llavero falla.
+pub fn save_config_gitlab(url: &str, token: &str, plaintext: bool) -> Result<(), String> {
+    let mut json = load_config_json();
+    json["gitlab_url"] = serde_json::json!(url);
+    json["gitlab_token_plaintext"] = serde_json::json!(plaintext);
+    microtermi_core::set_session_gitlab_token(Some(token));
+    microtermi_core::set_session_gitlab_url(Some(url));
+    let saved = microtermi_core::save_gitlab_token(token, plaintext);
+    // Si el llavero falla, el token se queda en config.json en vez de perderse.
+    let in_config = plaintext || saved.is_err();
+    json["gitlab_token"] = serde_json::json!(if in_config { token } else { "" });
+    save_config_json(&json);
+    if !plaintext {
+        saved.map_err(|e| e.to_string())?;
     }
+    Ok(())
 }
@@ -9,6 +9,12 @@ pub struct TerminalState {
     pub processes: Mutex<HashMap<String, std::process::Child>>,
 }
 
+/// Tokens de cancelación de las operaciones git de red en curso, por id de tarea.
+#[derive(Default)]
+pub struct GitTaskState {
+    pub cancels: Mutex<HashMap<String, microtermi_core::CancelToken>>,
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -17,6 +23,7 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_http::init())
         .manage(TerminalState::default())
+        .manage(GitTaskState::default())
         .invoke_handler(tauri::generate_handler![
             commands::load_config,
             commands::save_config_root,
@@ -25,22 +32,68 @@ pub fn run() {
             commands::scan_projects,
             commands::load_env,
             commands::save_env,
+            commands::validate_env,
+            commands::encrypt_env,
+            commands::export_env,
+            commands::import_env,
             commands::run_script_start,
             commands::terminal_stop,
             commands::terminal_stop_all,
             commands::git_status,
+            commands::git_repos_status,
+            commands::git_repos_fetch,
+            commands::git_repos_pull,
+            commands::git_repos_checkout,
             commands::git_branches,
             commands::git_branches_remote,
             commands::git_fetch,
             commands::git_checkout_branch,
             commands::git_checkout_remote_branch,
+            commands::git_create_branch,
+            commands::git_rename_branch,
+            commands::git_delete_branch,
+            commands::git_set_upstream,
             commands::git_pull,
+            commands::git_merge_continue,
+            commands::git_merge_abort,
+            commands::git_rebase_continue,
+            commands::git_rebase_skip,
+            commands::git_rebase_abort,
             commands::git_push,
+            commands::git_cancel,
+            commands::git_stage,
+            commands::git_unstage,
+            commands::git_discard,
+            commands::git_diff,
+            commands::git_stage_hunk,
+            commands::git_unstage_hunk,
             commands::git_commit,
+            commands::git_commit_hooks,
+            commands::git_commit_template,
+            commands::git_commit_lint_rules,
+            commands::git_lint_commit_message,
+            commands::git_commit_scopes,
             commands::git_log,
+            commands::git_log_page,
             commands::git_commit_changes,
+            commands::git_commit_detail,
+            commands::git_verify_commit_signature,
+            commands::git_cherry_pick,
+            commands::git_revert,
+            commands::git_pick_continue,
+            commands::git_pick_abort,
+            commands::git_blame,
+            commands::git_tracked_files,
+            commands::git_list_tags,
+            commands::git_create_tag,
+            commands::git_delete_tag,
+            commands::git_push_tags,
+            commands::git_next_release_tag,
             commands::git_stash,
+            commands::git_stash_list,
+            commands::git_stash_apply,
             commands::git_stash_pop,
+            commands::git_stash_drop,
             commands::gitlab_list_projects,
             commands::gitlab_list_branches,
             commands::gitlab_clone,